@@ -19,6 +19,8 @@
 //   This is much less intuitive, though, so it should wait until we're certain
 //   things are already working.
 
+use std::ops::ControlFlow;
+
 /// Representation of a type that can appear in a .mojom file.
 ///
 /// These include the primitive types from
@@ -151,3 +153,759 @@ impl MojomWireType {
         return self.size();
     }
 }
+
+/******************************************************************************
+ * All the following types describe the declarations in a .mojom source file,
+ * as produced by the parser in parse_source.rs.
+ *
+ * With the `serde` feature they can also be serialized, e.g. to JSON with
+ * to_json below. Enums are written as `{"kind": <variant>, "value": <data>}`,
+ * so that every variant can be told apart.
+ ******************************************************************************/
+
+/// The location of a declaration or member in the source it was parsed from,
+/// as byte offsets. `end` is one past the last byte.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The text that this span covers.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+}
+
+/// Convert a byte offset into `source` to a 1-based line and column, for
+/// display. Columns count characters, not bytes.
+pub fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+/// A type as written in a .mojom file, e.g. `array<string?>`.
+///
+/// Unlike MojomType, this describes the syntax of the type rather than its
+/// structure; user-defined types are referenced by name and nothing more.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Type {
+    // Flattened, so that a type is written as e.g.
+    // `{"kind": "Named", "value": "Foo", "nullable": true}`.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub kind: TypeKind,
+    /// Whether the type was written with a trailing `?`.
+    pub nullable: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum TypeKind {
+    Bool,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float,
+    Double,
+    String,
+    Array {
+        element_type: Box<Type>,
+        /// The N in `array<T, N>`, for arrays which always have N elements.
+        length: Option<u32>,
+    },
+    /// `map<K, V>`
+    Map {
+        key: Box<Type>,
+        value: Box<Type>,
+    },
+    /// A reference to a user-defined type, possibly qualified, e.g. `Foo.Bar`.
+    Named(String),
+    /// `pending_remote<Foo>`, holding the name of the interface. Likewise for
+    /// the next three.
+    PendingRemote(String),
+    PendingReceiver(String),
+    PendingAssociatedRemote(String),
+    PendingAssociatedReceiver(String),
+    /// `handle`, or `handle<...>` for a specific kind of handle.
+    Handle(HandleKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum HandleKind {
+    /// Just `handle`, which may be any kind of handle.
+    Untyped,
+    MessagePipe,
+    DataPipeConsumer,
+    DataPipeProducer,
+    SharedBuffer,
+    Platform,
+}
+
+impl HandleKind {
+    /// Every handle kind which can be written in `handle<...>`, along with how
+    /// it's written.
+    pub const NAMED: [(HandleKind, &'static str); 5] = [
+        (HandleKind::MessagePipe, "message_pipe"),
+        (HandleKind::DataPipeConsumer, "data_pipe_consumer"),
+        (HandleKind::DataPipeProducer, "data_pipe_producer"),
+        (HandleKind::SharedBuffer, "shared_buffer"),
+        (HandleKind::Platform, "platform"),
+    ];
+}
+
+impl TypeKind {
+    /// Whether a value of this type may be declared nullable with `?`.
+    ///
+    /// Bools and numeric types are stored inline, so there is no way to
+    /// represent a missing value on the wire.
+    pub fn can_be_nullable(&self) -> bool {
+        match self {
+            TypeKind::Bool
+            | TypeKind::Int8
+            | TypeKind::UInt8
+            | TypeKind::Int16
+            | TypeKind::UInt16
+            | TypeKind::Int32
+            | TypeKind::UInt32
+            | TypeKind::Int64
+            | TypeKind::UInt64
+            | TypeKind::Float
+            | TypeKind::Double => false,
+            TypeKind::String
+            | TypeKind::Array { .. }
+            | TypeKind::Map { .. }
+            | TypeKind::Named(_)
+            | TypeKind::PendingRemote(_)
+            | TypeKind::PendingReceiver(_)
+            | TypeKind::PendingAssociatedRemote(_)
+            | TypeKind::PendingAssociatedReceiver(_)
+            | TypeKind::Handle(_) => true,
+        }
+    }
+}
+
+/// The contents of a single .mojom file.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Module {
+    /// The name from the `module foo.bar;` statement, if there is one.
+    pub name: Option<String>,
+    pub imports: Vec<Import>,
+    pub declarations: Vec<Declaration>,
+}
+
+impl Module {
+    /// Find the declaration called `name` in this module.
+    pub fn find_declaration(&self, name: &str) -> Option<&Declaration> {
+        self.declarations.iter().find(|declaration| declaration.name() == name)
+    }
+}
+
+/// Whether a declaration or member is included, depending on which features are
+/// enabled. See parse_module_with_features in parse_source.rs.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum FeatureCondition {
+    /// `[EnableIf=feature]`: included only if `feature` is enabled.
+    EnableIf(String),
+    /// `[EnableIfNot=feature]`: included only if `feature` is not enabled.
+    EnableIfNot(String),
+}
+
+/// The value of an attribute without a dedicated field in the AST, as in
+/// `[ServiceSandbox=sandbox.mojom.Sandbox.kService]`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum AttrValue {
+    /// A name, which may be qualified like `sandbox.mojom.Sandbox.kService`,
+    /// or words joined by `-` like a UUID.
+    Identifier(String),
+    /// The contents of a string literal, with escapes replaced.
+    String(String),
+    Integer(i128),
+}
+
+/// A statement like `import "foo/bar.mojom";`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Import {
+    /// The path of the imported file, relative to one of the include
+    /// directories passed to resolve_imports.
+    pub path: String,
+    pub span: Span,
+}
+
+/// Anything which can appear at the top level of a .mojom file.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum Declaration {
+    Struct(Struct),
+    Union(Union),
+    Interface(Interface),
+    Enum(Enum),
+    Const(Const),
+    Feature(Feature),
+}
+
+impl Declaration {
+    pub fn name(&self) -> &str {
+        match self {
+            Declaration::Struct(s) => &s.name,
+            Declaration::Union(u) => &u.name,
+            Declaration::Interface(interface) => &interface.name,
+            Declaration::Enum(e) => &e.name,
+            Declaration::Const(constant) => &constant.name,
+            Declaration::Feature(feature) => &feature.name,
+        }
+    }
+
+    /// The declaration's `[EnableIf]` or `[EnableIfNot]` condition, if any.
+    pub fn condition(&self) -> &Option<FeatureCondition> {
+        match self {
+            Declaration::Struct(s) => &s.condition,
+            Declaration::Union(u) => &u.condition,
+            Declaration::Interface(interface) => &interface.condition,
+            Declaration::Enum(e) => &e.condition,
+            Declaration::Const(constant) => &constant.condition,
+            Declaration::Feature(feature) => &feature.condition,
+        }
+    }
+
+    /// The declaration's attributes without a dedicated field.
+    pub fn extra_attributes(&self) -> &[(String, Option<AttrValue>)] {
+        match self {
+            Declaration::Struct(s) => &s.extra_attributes,
+            Declaration::Union(u) => &u.extra_attributes,
+            Declaration::Interface(interface) => &interface.extra_attributes,
+            Declaration::Enum(e) => &e.extra_attributes,
+            Declaration::Const(constant) => &constant.extra_attributes,
+            Declaration::Feature(feature) => &feature.extra_attributes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Struct {
+    pub name: String,
+    pub span: Span,
+    /// The `//` comment lines directly above the declaration, if any.
+    pub doc: Option<String>,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    pub fields: Vec<Field>,
+    /// Whether the struct has the `[Native]` attribute, meaning it has no body
+    /// and is serialized by a hand-written C++ typemap instead.
+    pub is_native: bool,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+/// A tagged union: exactly one of its fields is present at a time.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Union {
+    pub name: String,
+    pub span: Span,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    pub fields: Vec<Field>,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+/// A named, typed value. This is used for struct and union fields as well as
+/// method parameters, since they are all declared the same way.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Field {
+    pub name: String,
+    pub span: Span,
+    /// The `//` comment lines directly above the declaration, if any.
+    pub doc: Option<String>,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    pub ty: Type,
+    /// The explicit ordinal from an `@N` after the name, if there was one. See
+    /// assign_ordinals in pack.rs for how the others are numbered.
+    pub ordinal: Option<u32>,
+    /// The version of the containing declaration which added this field, from
+    /// a `[MinVersion=N]` attribute.
+    pub min_version: Option<u32>,
+    /// The default value from an `= value` after a struct field's name, if
+    /// there was one. See resolve_field_defaults in resolve.rs for checking it
+    /// against the field's type.
+    pub default: Option<ValueExpr>,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Interface {
+    pub name: String,
+    pub span: Span,
+    /// The `//` comment lines directly above the declaration, if any.
+    pub doc: Option<String>,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    pub methods: Vec<Method>,
+    /// Whether the interface has the `[Stable]` attribute, meaning it may only
+    /// change in backward-compatible ways.
+    pub stable: bool,
+    /// From a `[Uuid=...]` attribute, which only stable interfaces may have.
+    pub uuid: Option<u128>,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Method {
+    pub name: String,
+    pub span: Span,
+    /// The `//` comment lines directly above the declaration, if any.
+    pub doc: Option<String>,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    /// The explicit ordinal after the name, like the `@3` in `Foo@3()`, if any.
+    pub ordinal: Option<u32>,
+    pub parameters: Vec<Field>,
+    /// The parameters after `=>`, or None if the method doesn't reply.
+    pub response_parameters: Option<Vec<Field>>,
+    /// The version of the interface which added this method, from a
+    /// `[MinVersion=N]` attribute.
+    pub min_version: Option<u32>,
+    /// Whether the method has the `[Sync]` attribute, meaning callers may block
+    /// waiting for the response.
+    pub is_sync: bool,
+    /// Whether the method has the `[NoInterrupt]` attribute, meaning a sync
+    /// call to it can't be interrupted by other incoming sync calls.
+    pub no_interrupt: bool,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+impl Method {
+    /// The request parameters as a struct, which is how they are serialized.
+    /// The struct is named like the generated bindings' `Foo_Params`.
+    pub fn request_struct(&self) -> Struct {
+        self.params_struct(format!("{}_Params", self.name), &self.parameters)
+    }
+
+    /// The response parameters as a struct, or None if the method doesn't
+    /// reply.
+    pub fn response_struct(&self) -> Option<Struct> {
+        let parameters = self.response_parameters.as_ref()?;
+        Some(self.params_struct(format!("{}_ResponseParams", self.name), parameters))
+    }
+
+    fn params_struct(&self, name: String, parameters: &[Field]) -> Struct {
+        Struct {
+            name,
+            span: self.span,
+            doc: None,
+            condition: None,
+            fields: parameters.to_vec(),
+            is_native: false,
+            extra_attributes: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Enum {
+    pub name: String,
+    pub span: Span,
+    /// The `//` comment lines directly above the declaration, if any.
+    pub doc: Option<String>,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    pub values: Vec<EnumValue>,
+    /// Whether the enum has the `[Extensible]` attribute, meaning that values
+    /// unknown to the receiver are replaced with the `[Default]` value.
+    pub extensible: bool,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EnumValue {
+    pub name: String,
+    pub span: Span,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    /// The value after the `=`, if any. Values without one are one greater than
+    /// the previous value; see resolve_enum_values in resolve.rs.
+    pub value: Option<ValueExpr>,
+    /// The version of the enum which added this value, from a
+    /// `[MinVersion=N]` attribute.
+    pub min_version: Option<u32>,
+    /// Whether the value has the `[Default]` attribute.
+    pub is_default: bool,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+/// A declaration like `const int32 kMax = 1 << 20;`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Const {
+    pub name: String,
+    pub span: Span,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    pub ty: Type,
+    pub value: ValueExpr,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+/// A runtime feature flag, declared like
+/// `feature kFoo { const string name = "Foo"; const bool default_state = false; };`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Feature {
+    pub name: String,
+    pub span: Span,
+    /// From an `[EnableIf]` or `[EnableIfNot]` attribute, if there was one.
+    pub condition: Option<FeatureCondition>,
+    /// The constants in the body, in declaration order. The parser only allows
+    /// `name` and `default_state`.
+    pub constants: Vec<Const>,
+    /// Attributes without a dedicated field, in the order they were written.
+    pub extra_attributes: Vec<(String, Option<AttrValue>)>,
+}
+
+impl Feature {
+    fn constant(&self, name: &str) -> Option<&ValueExpr> {
+        self.constants.iter().find(|constant| constant.name == name).map(|constant| &constant.value)
+    }
+
+    /// The value of the `name` constant, which names the feature at runtime.
+    pub fn feature_name(&self) -> Option<&str> {
+        match &self.constant("name")?.kind {
+            ValueExprKind::String(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The value of the `default_state` constant, i.e. whether the feature is
+    /// enabled by default.
+    pub fn default_state(&self) -> Option<bool> {
+        match &self.constant("default_state")?.kind {
+            ValueExprKind::Name(name) if name == "true" => Some(true),
+            ValueExprKind::Name(name) if name == "false" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// An expression which gives the value of an enum value or constant. See
+/// eval_const_expr in resolve.rs for how these are evaluated.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ValueExpr {
+    // Flattened in the same way as Type::kind.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub kind: ValueExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum ValueExprKind {
+    /// An integer literal, including its sign. This is wide enough to hold any
+    /// int64 or uint64.
+    Integer(i128),
+    /// A floating point literal, including its sign.
+    Float(f64),
+    /// A string literal, with any escape sequences already replaced.
+    String(String),
+    /// A reference to a previously-declared constant or enum value, or one of
+    /// the special values `float.INFINITY`, `float.NEGATIVE_INFINITY` and
+    /// `float.NAN`, or their `double` equivalents.
+    Name(String),
+    /// An array literal such as `[1, 2, 3]`.
+    Array(Vec<ValueExpr>),
+    Unary {
+        op: UnaryOp,
+        operand: Box<ValueExpr>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<ValueExpr>,
+        rhs: Box<ValueExpr>,
+    },
+}
+
+/// The value of a constant, after evaluating its ValueExpr.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum Value {
+    /// The value of a constant of any integer type.
+    Integer(i128),
+    Float(f32),
+    Double(f64),
+    String(String),
+    /// Not-a-number, for a constant of either floating point type. This is
+    /// kept separate from Float and Double so that values can be compared.
+    NaN,
+    /// A value of the enum called `enum_name`, such as `MyEnum.FOO`, along with
+    /// its integer value.
+    Enum {
+        enum_name: String,
+        value: i128,
+    },
+    /// The value of an array constant, holding the value of each element.
+    Array(Vec<Value>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum UnaryOp {
+    /// `-x`. A `-` directly before an integer literal is instead folded into
+    /// the literal.
+    Negate,
+    /// `~x`
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum BinaryOp {
+    Or,
+    Xor,
+    And,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl BinaryOp {
+    /// How tightly the operator binds; higher binds tighter. These are the same
+    /// as in C.
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 0,
+            BinaryOp::Xor => 1,
+            BinaryOp::And => 2,
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => 3,
+            BinaryOp::Add | BinaryOp::Subtract => 4,
+            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Remainder => 5,
+        }
+    }
+
+    /// The operator as written in a .mojom file.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Or => "|",
+            BinaryOp::Xor => "^",
+            BinaryOp::And => "&",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Remainder => "%",
+        }
+    }
+}
+
+/// A walk over the declarations of a .mojom file; see walk_module.
+///
+/// Each `visit_*` method is called on every node of its kind, parents before
+/// children, and does nothing by default. Returning `ControlFlow::Break` from
+/// any of them stops the walk.
+pub trait Visitor {
+    fn visit_import(&mut self, _import: &Import) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_declaration(&mut self, _declaration: &Declaration) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_struct(&mut self, _s: &Struct) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_union(&mut self, _u: &Union) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_interface(&mut self, _interface: &Interface) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_method(&mut self, _method: &Method) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_enum(&mut self, _e: &Enum) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_enum_value(&mut self, _enum_value: &EnumValue) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called for top-level constants, and for the constants in a feature.
+    fn visit_const(&mut self, _constant: &Const) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_feature(&mut self, _feature: &Feature) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called for struct and union fields, as well as method parameters.
+    fn visit_field(&mut self, _field: &Field) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called for the type of every field and constant, and for each type
+    /// nested inside it, e.g. the element type of an array.
+    fn visit_type(&mut self, _ty: &Type) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called for every constant and enum value expression, and for each of
+    /// their operands.
+    fn visit_value_expr(&mut self, _expr: &ValueExpr) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Walk over every node in `module`, calling the matching method of `visitor`
+/// on each. Returns `ControlFlow::Break` if the visitor stopped the walk early.
+pub fn walk_module(visitor: &mut impl Visitor, module: &Module) -> ControlFlow<()> {
+    for import in &module.imports {
+        visitor.visit_import(import)?;
+    }
+    for declaration in &module.declarations {
+        walk_declaration(visitor, declaration)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn walk_declaration(visitor: &mut impl Visitor, declaration: &Declaration) -> ControlFlow<()> {
+    visitor.visit_declaration(declaration)?;
+    match declaration {
+        Declaration::Struct(s) => {
+            visitor.visit_struct(s)?;
+            walk_fields(visitor, &s.fields)
+        }
+        Declaration::Union(u) => {
+            visitor.visit_union(u)?;
+            walk_fields(visitor, &u.fields)
+        }
+        Declaration::Interface(interface) => {
+            visitor.visit_interface(interface)?;
+            for method in &interface.methods {
+                visitor.visit_method(method)?;
+                walk_fields(visitor, &method.parameters)?;
+                walk_fields(visitor, method.response_parameters.as_deref().unwrap_or_default())?;
+            }
+            ControlFlow::Continue(())
+        }
+        Declaration::Enum(e) => {
+            visitor.visit_enum(e)?;
+            for enum_value in &e.values {
+                visitor.visit_enum_value(enum_value)?;
+                if let Some(value) = &enum_value.value {
+                    walk_value_expr(visitor, value)?;
+                }
+            }
+            ControlFlow::Continue(())
+        }
+        Declaration::Const(constant) => walk_const(visitor, constant),
+        Declaration::Feature(feature) => {
+            visitor.visit_feature(feature)?;
+            for constant in &feature.constants {
+                walk_const(visitor, constant)?;
+            }
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+fn walk_const(visitor: &mut impl Visitor, constant: &Const) -> ControlFlow<()> {
+    visitor.visit_const(constant)?;
+    walk_type(visitor, &constant.ty)?;
+    walk_value_expr(visitor, &constant.value)
+}
+
+fn walk_fields(visitor: &mut impl Visitor, fields: &[Field]) -> ControlFlow<()> {
+    for field in fields {
+        visitor.visit_field(field)?;
+        walk_type(visitor, &field.ty)?;
+        if let Some(default) = &field.default {
+            walk_value_expr(visitor, default)?;
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+fn walk_type(visitor: &mut impl Visitor, ty: &Type) -> ControlFlow<()> {
+    visitor.visit_type(ty)?;
+    match &ty.kind {
+        TypeKind::Array { element_type, .. } => walk_type(visitor, element_type),
+        TypeKind::Map { key, value } => {
+            walk_type(visitor, key)?;
+            walk_type(visitor, value)
+        }
+        _ => ControlFlow::Continue(()),
+    }
+}
+
+fn walk_value_expr(visitor: &mut impl Visitor, expr: &ValueExpr) -> ControlFlow<()> {
+    visitor.visit_value_expr(expr)?;
+    match &expr.kind {
+        ValueExprKind::Unary { operand, .. } => walk_value_expr(visitor, operand),
+        ValueExprKind::Binary { lhs, rhs, .. } => {
+            walk_value_expr(visitor, lhs)?;
+            walk_value_expr(visitor, rhs)
+        }
+        ValueExprKind::Array(elements) => {
+            for element in elements {
+                walk_value_expr(visitor, element)?;
+            }
+            ControlFlow::Continue(())
+        }
+        _ => ControlFlow::Continue(()),
+    }
+}
+
+/// Serialize a parsed .mojom file to JSON, for use by tools outside of Rust.
+#[cfg(feature = "serde")]
+pub fn to_json(module: &Module) -> String {
+    // The AST contains no maps, so serialization can't fail.
+    serde_json::to_string(module).unwrap()
+}
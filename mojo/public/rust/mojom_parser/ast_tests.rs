@@ -0,0 +1,89 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use mojom_parser::*;
+
+use std::ops::ControlFlow;
+
+const MOJOM: &str = "
+    struct Point { int32 x; int32 y; };
+    interface Canvas {
+      Clear();
+      Draw(array<Point> points) => (bool success);
+    };
+    const int32 kMax = 1 + 2;
+    interface Observer {
+      [Sync] OnDrawn() => ();
+    };";
+
+/// Counts methods, stopping after `limit` of them if set.
+#[derive(Default)]
+struct MethodCounter {
+    names: Vec<String>,
+    limit: Option<usize>,
+}
+
+impl Visitor for MethodCounter {
+    fn visit_method(&mut self, method: &Method) -> ControlFlow<()> {
+        self.names.push(method.name.clone());
+        if Some(self.names.len()) == self.limit {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+#[gtest(MojomAstTest, VisitorCountsMethods)]
+fn test_visitor_counts_methods() {
+    let module = parse_module(MOJOM).unwrap();
+    let mut counter = MethodCounter::default();
+    expect_eq!(walk_module(&mut counter, &module), ControlFlow::Continue(()));
+    expect_eq!(counter.names, ["Clear", "Draw", "OnDrawn"]);
+
+    let mut counter = MethodCounter { limit: Some(2), ..Default::default() };
+    expect_eq!(walk_module(&mut counter, &module), ControlFlow::Break(()));
+    expect_eq!(counter.names, ["Clear", "Draw"]);
+}
+
+/// Records every named type, and every name used in an expression.
+#[derive(Default)]
+struct NameCollector {
+    names: Vec<String>,
+}
+
+impl Visitor for NameCollector {
+    fn visit_type(&mut self, ty: &Type) -> ControlFlow<()> {
+        if let TypeKind::Named(name) = &ty.kind {
+            self.names.push(name.clone());
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn visit_value_expr(&mut self, expr: &ValueExpr) -> ControlFlow<()> {
+        if let ValueExprKind::Name(name) = &expr.kind {
+            self.names.push(name.clone());
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[gtest(MojomAstTest, VisitorWalksNestedNodes)]
+fn test_visitor_walks_nested_nodes() {
+    let module = parse_module(
+        "struct S { map<string, array<Foo?>> foos; };
+         interface I { F(Bar bar) => (Baz baz); };
+         enum E { A = kOne << kTwo };",
+    )
+    .unwrap();
+    let mut collector = NameCollector::default();
+    expect_eq!(walk_module(&mut collector, &module), ControlFlow::Continue(()));
+    expect_eq!(collector.names, ["Foo", "Bar", "Baz", "kOne", "kTwo"]);
+}
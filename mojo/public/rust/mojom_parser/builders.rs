@@ -0,0 +1,236 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Builders for AST nodes, for generating .mojom declarations from Rust rather
+//! than parsing them. Together with deparse_module, this turns a schema from
+//! some other format into a .mojom file.
+//!
+//! Nodes built this way have empty spans and no doc comments, and the builders
+//! check only what's needed for the output to parse: that names are valid
+//! identifiers other than keywords, and that no two members share a name.
+
+use std::collections::HashSet;
+
+use crate::ast::*;
+use crate::lexer::KEYWORDS;
+
+use anyhow::{bail, Result};
+
+/// Check that `name`, the name of a `what`, could be written in a .mojom file.
+fn check_identifier(name: &str, what: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("Invalid {} name `{}`", what, name)
+    }
+    if KEYWORDS.contains(&name) {
+        bail!("Invalid {} name `{}`, which is a reserved word", what, name)
+    }
+    Ok(())
+}
+
+/// Check `names`, the names of the `what`s in `container`.
+fn check_member_names<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    what: &str,
+    container: &str,
+) -> Result<()> {
+    let mut seen = HashSet::new();
+    for name in names {
+        check_identifier(name, what)?;
+        if !seen.insert(name) {
+            bail!("Duplicate {} `{}` in {}", what, name, container)
+        }
+    }
+    Ok(())
+}
+
+/// A field or parameter with nothing but a name and a type.
+fn plain_field(name: String, ty: Type) -> Field {
+    Field {
+        name,
+        span: Span::default(),
+        doc: None,
+        condition: None,
+        ty,
+        ordinal: None,
+        min_version: None,
+        default: None,
+        extra_attributes: vec![],
+    }
+}
+
+/// Builds a Struct; see Struct::builder.
+#[derive(Debug, Clone)]
+pub struct StructBuilder {
+    s: Struct,
+}
+
+impl Struct {
+    /// Start building a struct called `name`, with no fields.
+    pub fn builder(name: impl Into<String>) -> StructBuilder {
+        StructBuilder {
+            s: Struct {
+                name: name.into(),
+                span: Span::default(),
+                doc: None,
+                condition: None,
+                fields: vec![],
+                is_native: false,
+                extra_attributes: vec![],
+            },
+        }
+    }
+}
+
+impl StructBuilder {
+    /// Add a field after the existing ones.
+    pub fn field(mut self, name: impl Into<String>, ty: Type) -> Self {
+        self.s.fields.push(plain_field(name.into(), ty));
+        self
+    }
+
+    /// Finish the struct, failing if any name is invalid or two fields share
+    /// one.
+    pub fn build(self) -> Result<Struct> {
+        check_identifier(&self.s.name, "struct")?;
+        let container = format!("struct `{}`", self.s.name);
+        check_member_names(self.s.fields.iter().map(|f| f.name.as_str()), "field", &container)?;
+        Ok(self.s)
+    }
+}
+
+/// Builds a Method; see Method::builder.
+#[derive(Debug, Clone)]
+pub struct MethodBuilder {
+    method: Method,
+}
+
+impl Method {
+    /// Start building a method called `name`, with no parameters and no
+    /// response.
+    pub fn builder(name: impl Into<String>) -> MethodBuilder {
+        MethodBuilder {
+            method: Method {
+                name: name.into(),
+                span: Span::default(),
+                doc: None,
+                condition: None,
+                ordinal: None,
+                parameters: vec![],
+                response_parameters: None,
+                min_version: None,
+                is_sync: false,
+                no_interrupt: false,
+                extra_attributes: vec![],
+            },
+        }
+    }
+}
+
+impl MethodBuilder {
+    /// Add a request parameter after the existing ones.
+    pub fn parameter(mut self, name: impl Into<String>, ty: Type) -> Self {
+        self.method.parameters.push(plain_field(name.into(), ty));
+        self
+    }
+
+    /// Make the method reply, with no response parameters unless some are
+    /// added by response_parameter.
+    pub fn with_response(mut self) -> Self {
+        self.method.response_parameters.get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Add a response parameter after the existing ones, making the method
+    /// reply if it didn't already.
+    pub fn response_parameter(mut self, name: impl Into<String>, ty: Type) -> Self {
+        let parameter = plain_field(name.into(), ty);
+        self.method.response_parameters.get_or_insert_with(Vec::new).push(parameter);
+        self
+    }
+
+    /// Give the method an explicit ordinal, as with `Foo@3()`.
+    pub fn ordinal(mut self, ordinal: u32) -> Self {
+        self.method.ordinal = Some(ordinal);
+        self
+    }
+
+    /// Mark the method `[Sync]`.
+    pub fn sync(mut self) -> Self {
+        self.method.is_sync = true;
+        self
+    }
+
+    /// Finish the method, failing if any name is invalid or two parameters in
+    /// the request or in the response share one.
+    pub fn build(self) -> Result<Method> {
+        let method = &self.method;
+        check_identifier(&method.name, "method")?;
+        let container = format!("method `{}`", method.name);
+        check_member_names(
+            method.parameters.iter().map(|p| p.name.as_str()),
+            "parameter",
+            &container,
+        )?;
+        if let Some(response_parameters) = &method.response_parameters {
+            let container = format!("the response of method `{}`", method.name);
+            check_member_names(
+                response_parameters.iter().map(|p| p.name.as_str()),
+                "parameter",
+                &container,
+            )?;
+        }
+        Ok(self.method)
+    }
+}
+
+/// Builds an Interface; see Interface::builder.
+#[derive(Debug, Clone)]
+pub struct InterfaceBuilder {
+    interface: Interface,
+}
+
+impl Interface {
+    /// Start building an interface called `name`, with no methods.
+    pub fn builder(name: impl Into<String>) -> InterfaceBuilder {
+        InterfaceBuilder {
+            interface: Interface {
+                name: name.into(),
+                span: Span::default(),
+                doc: None,
+                condition: None,
+                methods: vec![],
+                stable: false,
+                uuid: None,
+                extra_attributes: vec![],
+            },
+        }
+    }
+}
+
+impl InterfaceBuilder {
+    /// Add a method after the existing ones.
+    pub fn method(mut self, method: Method) -> Self {
+        self.interface.methods.push(method);
+        self
+    }
+
+    /// Mark the interface `[Stable]`.
+    pub fn stable(mut self) -> Self {
+        self.interface.stable = true;
+        self
+    }
+
+    /// Finish the interface, failing if its name is invalid or two methods
+    /// share one.
+    pub fn build(self) -> Result<Interface> {
+        check_identifier(&self.interface.name, "interface")?;
+        let container = format!("interface `{}`", self.interface.name);
+        let names = self.interface.methods.iter().map(|m| m.name.as_str());
+        check_member_names(names, "method", &container)?;
+        Ok(self.interface)
+    }
+}
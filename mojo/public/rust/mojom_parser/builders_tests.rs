@@ -0,0 +1,92 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use crate::without_spans;
+use mojom_parser::*;
+
+#[gtest(MojomBuildersTest, BuildDeparseReparse)]
+fn test_build_deparse_reparse() {
+    let ty = |source| parse_type(source).unwrap();
+    let point = Struct::builder("Point").field("x", ty("int32")).field("y", ty("int32")).build();
+    let canvas = Interface::builder("Canvas")
+        .stable()
+        .method(Method::builder("Clear").build().unwrap())
+        .method(
+            Method::builder("Draw")
+                .ordinal(3)
+                .parameter("points", ty("array<Point>"))
+                .parameter("label", ty("string?"))
+                .response_parameter("success", ty("bool"))
+                .build()
+                .unwrap(),
+        )
+        .method(Method::builder("Flush").sync().with_response().build().unwrap())
+        .build();
+    let module = Module {
+        name: Some("drawing.mojom".to_string()),
+        imports: vec![],
+        declarations: vec![
+            Declaration::Struct(point.unwrap()),
+            Declaration::Interface(canvas.unwrap()),
+        ],
+    };
+
+    let deparsed = deparse_module(&module);
+    expect_eq!(
+        deparsed,
+        "module drawing.mojom;\n\n\
+         struct Point {\n  int32 x;\n  int32 y;\n};\n\n\
+         [Stable] interface Canvas {\n\
+         \x20 Clear();\n\
+         \x20 Draw@3(array<Point> points, string? label) => (bool success);\n\
+         \x20 [Sync] Flush() => ();\n\
+         };\n"
+    );
+    expect_eq!(without_spans(&parse_module(&deparsed).unwrap()), without_spans(&module));
+}
+
+#[gtest(MojomBuildersTest, BuildInvalid)]
+fn test_build_invalid() {
+    let int32 = parse_type("int32").unwrap();
+    fn err<T, E: std::fmt::Display>(result: Result<T, E>) -> String {
+        result.err().unwrap().to_string()
+    }
+    let errors = [
+        err(Struct::builder("").build()),
+        err(Struct::builder("interface").build()),
+        err(Struct::builder("S").field("1x", int32.clone()).build()),
+        err(Struct::builder("S").field("default", int32.clone()).build()),
+        err(Struct::builder("S").field("x", int32.clone()).field("x", int32.clone()).build()),
+        err(Method::builder("F")
+            .response_parameter("a", int32.clone())
+            .response_parameter("a", int32.clone())
+            .build()),
+        err(Interface::builder("I")
+            .method(Method::builder("F").build().unwrap())
+            .method(Method::builder("F").build().unwrap())
+            .build()),
+    ];
+    expect_eq!(
+        errors,
+        [
+            "Invalid struct name ``",
+            "Invalid struct name `interface`, which is a reserved word",
+            "Invalid field name `1x`",
+            "Invalid field name `default`, which is a reserved word",
+            "Duplicate field `x` in struct `S`",
+            "Duplicate parameter `a` in the response of method `F`",
+            "Duplicate method `F` in interface `I`",
+        ]
+    );
+    // The request and response may reuse names.
+    let method =
+        Method::builder("F").parameter("a", int32.clone()).response_parameter("a", int32).build();
+    expect_true!(method.is_ok());
+}
@@ -0,0 +1,380 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Defines functions for turning a parsed .mojom file back into text.
+//!
+//! This is the inverse of parse_source.rs: parsing the output of these
+//! functions yields the original AST. Attributes the parser understands are
+//! written back in a canonical order, each with its value in a canonical form,
+//! so they survive a round trip even if they were written differently.
+
+use crate::ast::*;
+
+use std::fmt::Write;
+
+/// The indentation for the contents of a declaration.
+const INDENT: &str = "  ";
+
+/// Write out a type as it would appear in a .mojom file.
+pub fn deparse_type(ty: &Type) -> String {
+    let mut ret = match &ty.kind {
+        TypeKind::Bool => "bool".to_string(),
+        TypeKind::Int8 => "int8".to_string(),
+        TypeKind::UInt8 => "uint8".to_string(),
+        TypeKind::Int16 => "int16".to_string(),
+        TypeKind::UInt16 => "uint16".to_string(),
+        TypeKind::Int32 => "int32".to_string(),
+        TypeKind::UInt32 => "uint32".to_string(),
+        TypeKind::Int64 => "int64".to_string(),
+        TypeKind::UInt64 => "uint64".to_string(),
+        TypeKind::Float => "float".to_string(),
+        TypeKind::Double => "double".to_string(),
+        TypeKind::String => "string".to_string(),
+        TypeKind::Array { element_type, length: None } => {
+            format!("array<{}>", deparse_type(element_type))
+        }
+        TypeKind::Array { element_type, length: Some(length) } => {
+            format!("array<{}, {}>", deparse_type(element_type), length)
+        }
+        TypeKind::Map { key, value } => {
+            format!("map<{}, {}>", deparse_type(key), deparse_type(value))
+        }
+        TypeKind::Named(name) => name.clone(),
+        TypeKind::PendingRemote(interface) => format!("pending_remote<{}>", interface),
+        TypeKind::PendingReceiver(interface) => format!("pending_receiver<{}>", interface),
+        TypeKind::PendingAssociatedRemote(interface) => {
+            format!("pending_associated_remote<{}>", interface)
+        }
+        TypeKind::PendingAssociatedReceiver(interface) => {
+            format!("pending_associated_receiver<{}>", interface)
+        }
+        TypeKind::Handle(HandleKind::Untyped) => "handle".to_string(),
+        TypeKind::Handle(kind) => {
+            let (_, name) = HandleKind::NAMED.iter().find(|(named, _)| named == kind).unwrap();
+            format!("handle<{}>", name)
+        }
+    };
+    if ty.nullable {
+        ret.push('?');
+    }
+    ret
+}
+
+/// Write out a string literal, with quotes, escaping any characters which
+/// can't appear in it directly.
+pub fn deparse_string(value: &str) -> String {
+    let mut ret = String::with_capacity(value.len() + 2);
+    ret.push('"');
+    for c in value.chars() {
+        match c {
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            c if c.is_control() => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// Write out an expression, with only as many parentheses as are needed to
+/// parse it back the same way.
+pub fn deparse_value_expr(expr: &ValueExpr) -> String {
+    match &expr.kind {
+        ValueExprKind::Integer(value) => value.to_string(),
+        // The Debug format always includes a `.` or exponent, and is precise
+        // enough to parse back to the same value.
+        ValueExprKind::Float(value) => format!("{:?}", value),
+        ValueExprKind::String(value) => deparse_string(value),
+        ValueExprKind::Name(name) => name.clone(),
+        ValueExprKind::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(deparse_value_expr).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        ValueExprKind::Unary { op, operand } => {
+            let symbol = match op {
+                UnaryOp::Negate => "-",
+                UnaryOp::Not => "~",
+            };
+            match operand.kind {
+                ValueExprKind::Binary { .. } => {
+                    format!("{}({})", symbol, deparse_value_expr(operand))
+                }
+                _ => format!("{}{}", symbol, deparse_value_expr(operand)),
+            }
+        }
+        ValueExprKind::Binary { op, lhs, rhs } => {
+            // Binary operators are left-associative, so the right operand needs
+            // parentheses even if its operator has the same precedence.
+            let lhs = deparse_operand(lhs, |precedence| precedence < op.precedence());
+            let rhs = deparse_operand(rhs, |precedence| precedence <= op.precedence());
+            format!("{} {} {}", lhs, op.symbol(), rhs)
+        }
+    }
+}
+
+/// Write out an operand of a binary operator, wrapping it in parentheses if it
+/// is itself a binary expression whose precedence satisfies `needs_parens`.
+fn deparse_operand(expr: &ValueExpr, needs_parens: impl Fn(u8) -> bool) -> String {
+    match &expr.kind {
+        ValueExprKind::Binary { op, .. } if needs_parens(op.precedence()) => {
+            format!("({})", deparse_value_expr(expr))
+        }
+        _ => deparse_value_expr(expr),
+    }
+}
+
+/// Write out an attribute list like `[MinVersion=1, Sync]`, including a
+/// trailing space, or nothing if there are no attributes.
+fn deparse_attributes(attributes: &[String]) -> String {
+    if attributes.is_empty() {
+        return String::new();
+    }
+    format!("[{}] ", attributes.join(", "))
+}
+
+/// The `EnableIf=feature` or `EnableIfNot=feature` attribute, if there is a
+/// condition.
+fn condition_attribute(condition: &Option<FeatureCondition>) -> Vec<String> {
+    match condition {
+        Some(FeatureCondition::EnableIf(feature)) => vec![format!("EnableIf={}", feature)],
+        Some(FeatureCondition::EnableIfNot(feature)) => vec![format!("EnableIfNot={}", feature)],
+        None => vec![],
+    }
+}
+
+/// Write out attributes without a dedicated field, like
+/// `ServiceSandbox=sandbox.mojom.Sandbox.kService`.
+fn extra_attributes(extra_attributes: &[(String, Option<AttrValue>)]) -> Vec<String> {
+    extra_attributes
+        .iter()
+        .map(|(name, value)| match value {
+            None => name.clone(),
+            Some(AttrValue::Identifier(value)) => format!("{}={}", name, value),
+            Some(AttrValue::String(value)) => format!("{}={}", name, deparse_string(value)),
+            Some(AttrValue::Integer(value)) => format!("{}={}", name, value),
+        })
+        .collect()
+}
+
+/// The attributes shared by all members of a declaration, in canonical order.
+fn member_attributes(
+    condition: &Option<FeatureCondition>,
+    min_version: Option<u32>,
+) -> Vec<String> {
+    let mut attributes = condition_attribute(condition);
+    attributes.extend(min_version.map(|version| format!("MinVersion={}", version)));
+    attributes
+}
+
+/// The attributes of a method, in canonical order.
+fn method_attributes(method: &Method) -> Vec<String> {
+    let mut attributes = member_attributes(&method.condition, method.min_version);
+    if method.is_sync {
+        attributes.push("Sync".to_string());
+    }
+    if method.no_interrupt {
+        attributes.push("NoInterrupt".to_string());
+    }
+    attributes.extend(extra_attributes(&method.extra_attributes));
+    attributes
+}
+
+/// The attributes of an enum value, in canonical order.
+fn enum_value_attributes(enum_value: &EnumValue) -> Vec<String> {
+    let mut attributes = member_attributes(&enum_value.condition, enum_value.min_version);
+    if enum_value.is_default {
+        attributes.push("Default".to_string());
+    }
+    attributes.extend(extra_attributes(&enum_value.extra_attributes));
+    attributes
+}
+
+/// Write out a UUID in the canonical form `01234567-89ab-cdef-0123-456789abcdef`.
+fn deparse_uuid(uuid: u128) -> String {
+    let hex = format!("{:032x}", uuid);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// The attributes of a top-level declaration, in canonical order.
+fn declaration_attributes(declaration: &Declaration) -> Vec<String> {
+    let mut attributes = condition_attribute(declaration.condition());
+    match declaration {
+        Declaration::Struct(s) if s.is_native => attributes.push("Native".to_string()),
+        Declaration::Enum(e) if e.extensible => attributes.push("Extensible".to_string()),
+        Declaration::Interface(interface) => {
+            if interface.stable {
+                attributes.push("Stable".to_string());
+            }
+            attributes.extend(interface.uuid.map(|uuid| format!("Uuid={}", deparse_uuid(uuid))));
+        }
+        _ => (),
+    }
+    attributes.extend(extra_attributes(declaration.extra_attributes()));
+    attributes
+}
+
+/// Write out a doc comment as `//` lines at the given indentation.
+fn deparse_doc(out: &mut String, indent: &str, doc: &Option<String>) {
+    for line in doc.iter().flat_map(|doc| doc.split('\n')) {
+        if line.is_empty() {
+            writeln!(out, "{}//", indent).unwrap();
+        } else {
+            writeln!(out, "{}// {}", indent, line).unwrap();
+        }
+    }
+}
+
+/// Write out a struct field or method parameter, without the terminator.
+fn deparse_field(field: &Field) -> String {
+    let mut attributes = member_attributes(&field.condition, field.min_version);
+    attributes.extend(extra_attributes(&field.extra_attributes));
+    let ordinal = field.ordinal.map(|ordinal| format!("@{}", ordinal)).unwrap_or_default();
+    let default = field.default.as_ref().map(|value| format!(" = {}", deparse_value_expr(value)));
+    format!(
+        "{}{} {}{}{}",
+        deparse_attributes(&attributes),
+        deparse_type(&field.ty),
+        field.name,
+        ordinal,
+        default.unwrap_or_default()
+    )
+}
+
+/// Write out a parenthesized list of method parameters.
+fn deparse_parameters(parameters: &[Field]) -> String {
+    let parameters: Vec<String> = parameters.iter().map(deparse_field).collect();
+    format!("({})", parameters.join(", "))
+}
+
+/// Write out the body of a struct or union, each field on its own line.
+fn deparse_fields(out: &mut String, fields: &[Field]) {
+    for field in fields {
+        deparse_doc(out, INDENT, &field.doc);
+        writeln!(out, "{}{};", INDENT, deparse_field(field)).unwrap();
+    }
+}
+
+/// Write out a constant, including its terminating `;` but not its attributes.
+fn deparse_const(constant: &Const) -> String {
+    format!(
+        "const {} {} = {};",
+        deparse_type(&constant.ty),
+        constant.name,
+        deparse_value_expr(&constant.value)
+    )
+}
+
+/// Write out a single top-level declaration, including its terminating `;`
+/// and a newline.
+pub fn deparse_declaration(declaration: &Declaration) -> String {
+    let mut out = String::new();
+    let doc = match declaration {
+        Declaration::Struct(s) => &s.doc,
+        Declaration::Interface(interface) => &interface.doc,
+        Declaration::Enum(e) => &e.doc,
+        Declaration::Union(_) | Declaration::Const(_) | Declaration::Feature(_) => &None,
+    };
+    deparse_doc(&mut out, "", doc);
+    out.push_str(&deparse_attributes(&declaration_attributes(declaration)));
+    match declaration {
+        Declaration::Struct(s) if s.is_native => {
+            writeln!(out, "struct {};", s.name).unwrap();
+            return out;
+        }
+        Declaration::Struct(s) => {
+            writeln!(out, "struct {} {{", s.name).unwrap();
+            deparse_fields(&mut out, &s.fields);
+        }
+        Declaration::Union(u) => {
+            writeln!(out, "union {} {{", u.name).unwrap();
+            deparse_fields(&mut out, &u.fields);
+        }
+        Declaration::Interface(interface) => {
+            writeln!(out, "interface {} {{", interface.name).unwrap();
+            for method in &interface.methods {
+                deparse_doc(&mut out, INDENT, &method.doc);
+                let ordinal =
+                    method.ordinal.map(|ordinal| format!("@{}", ordinal)).unwrap_or_default();
+                write!(
+                    out,
+                    "{}{}{}{}{}",
+                    INDENT,
+                    deparse_attributes(&method_attributes(method)),
+                    method.name,
+                    ordinal,
+                    deparse_parameters(&method.parameters)
+                )
+                .unwrap();
+                if let Some(response_parameters) = &method.response_parameters {
+                    write!(out, " => {}", deparse_parameters(response_parameters)).unwrap();
+                }
+                out.push_str(";\n");
+            }
+        }
+        Declaration::Enum(e) => {
+            writeln!(out, "enum {} {{", e.name).unwrap();
+            for enum_value in &e.values {
+                write!(
+                    out,
+                    "{}{}{}",
+                    INDENT,
+                    deparse_attributes(&enum_value_attributes(enum_value)),
+                    enum_value.name
+                )
+                .unwrap();
+                if let Some(value) = &enum_value.value {
+                    write!(out, " = {}", deparse_value_expr(value)).unwrap();
+                }
+                out.push_str(",\n");
+            }
+        }
+        Declaration::Const(constant) => {
+            writeln!(out, "{}", deparse_const(constant)).unwrap();
+            return out;
+        }
+        Declaration::Feature(feature) => {
+            writeln!(out, "feature {} {{", feature.name).unwrap();
+            for constant in &feature.constants {
+                let mut attributes = condition_attribute(&constant.condition);
+                attributes.extend(extra_attributes(&constant.extra_attributes));
+                writeln!(
+                    out,
+                    "{}{}{}",
+                    INDENT,
+                    deparse_attributes(&attributes),
+                    deparse_const(constant)
+                )
+                .unwrap();
+            }
+        }
+    }
+    out.push_str("};\n");
+    out
+}
+
+/// Write out an entire .mojom file, in a canonical format with declarations
+/// separated by blank lines.
+///
+/// Running the output back through parse_module and deparse_module gives the
+/// same text.
+pub fn deparse_module(module: &Module) -> String {
+    let mut sections: Vec<String> = vec![];
+    if let Some(name) = &module.name {
+        sections.push(format!("module {};\n", name));
+    }
+    if !module.imports.is_empty() {
+        let imports: Vec<String> = module
+            .imports
+            .iter()
+            .map(|import| format!("import {};\n", deparse_string(&import.path)))
+            .collect();
+        sections.push(imports.concat());
+    }
+    sections.extend(module.declarations.iter().map(deparse_declaration));
+    sections.join("\n")
+}
@@ -0,0 +1,186 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Compares two versions of a .mojom file in terms of the wire format, for
+//! reviewing changes to an API.
+//!
+//! Structs and interfaces are matched up by name, as are the fields and methods
+//! within them. A field which is renamed therefore shows up as one field being
+//! removed and another added.
+
+use crate::ast::*;
+use crate::deparse_source::deparse_type;
+use crate::pack::{assign_method_ordinals, assign_ordinals};
+
+use anyhow::Result;
+
+/// One difference between two versions of a module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// The name of the struct or interface which changed.
+    pub container: String,
+    pub kind: ChangeKind,
+    /// Whether messages from one version may be misread by the other.
+    pub breaking: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    FieldAdded {
+        name: String,
+        ordinal: Ordinal,
+    },
+    FieldRemoved {
+        name: String,
+        ordinal: Ordinal,
+    },
+    /// The field with the same name has a different ordinal.
+    FieldReordered {
+        name: String,
+        old_ordinal: Ordinal,
+        new_ordinal: Ordinal,
+    },
+    /// The field with the same name and ordinal has a different type, written
+    /// as in the .mojom file.
+    FieldTypeChanged {
+        name: String,
+        old_type: String,
+        new_type: String,
+    },
+    MethodAdded {
+        name: String,
+    },
+    MethodRemoved {
+        name: String,
+    },
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}`: ", self.container)?;
+        match &self.kind {
+            ChangeKind::FieldAdded { name, ordinal } => {
+                write!(f, "added field `{}` with ordinal {}", name, ordinal)
+            }
+            ChangeKind::FieldRemoved { name, ordinal } => {
+                write!(f, "removed field `{}` with ordinal {}", name, ordinal)
+            }
+            ChangeKind::FieldReordered { name, old_ordinal, new_ordinal } => write!(
+                f,
+                "changed the ordinal of field `{}` from {} to {}",
+                name, old_ordinal, new_ordinal
+            ),
+            ChangeKind::FieldTypeChanged { name, old_type, new_type } => write!(
+                f,
+                "changed the type of field `{}` from `{}` to `{}`",
+                name, old_type, new_type
+            ),
+            ChangeKind::MethodAdded { name } => write!(f, "added method `{}`", name),
+            ChangeKind::MethodRemoved { name } => write!(f, "removed method `{}`", name),
+        }?;
+        if self.breaking {
+            f.write_str(" (breaking)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Add the changes between two versions of a struct to `changes`.
+fn diff_structs(old: &Struct, new: &Struct, changes: &mut Vec<Change>) -> Result<()> {
+    let old_fields: Vec<(Ordinal, &Field)> =
+        assign_ordinals(old)?.into_iter().zip(&old.fields).collect();
+    let new_fields: Vec<(Ordinal, &Field)> =
+        assign_ordinals(new)?.into_iter().zip(&new.fields).collect();
+    let mut change = |kind, breaking| {
+        changes.push(Change { container: new.name.clone(), kind, breaking });
+    };
+    for (old_ordinal, old_field) in &old_fields {
+        let name = old_field.name.clone();
+        let Some((new_ordinal, new_field)) =
+            new_fields.iter().find(|(_, field)| field.name == old_field.name)
+        else {
+            change(ChangeKind::FieldRemoved { name, ordinal: *old_ordinal }, true);
+            continue;
+        };
+        if old_ordinal != new_ordinal {
+            let kind = ChangeKind::FieldReordered {
+                name,
+                old_ordinal: *old_ordinal,
+                new_ordinal: *new_ordinal,
+            };
+            change(kind, true);
+        } else {
+            let (old_type, new_type) = (deparse_type(&old_field.ty), deparse_type(&new_field.ty));
+            if old_type != new_type {
+                change(ChangeKind::FieldTypeChanged { name, old_type, new_type }, true);
+            }
+        }
+    }
+    // A new field is only compatible if it comes after all the old ones, in a
+    // new version, so that older receivers know to ignore it.
+    let old_version = old_fields.iter().filter_map(|(_, field)| field.min_version).max();
+    let old_max_ordinal = old_fields.iter().map(|(ordinal, _)| *ordinal).max();
+    for (ordinal, field) in &new_fields {
+        if old_fields.iter().any(|(_, old_field)| old_field.name == field.name) {
+            continue;
+        }
+        let is_versioned =
+            field.min_version.is_some_and(|version| version > old_version.unwrap_or(0));
+        let is_last = old_max_ordinal.is_none_or(|max| *ordinal > max);
+        let kind = ChangeKind::FieldAdded { name: field.name.clone(), ordinal: *ordinal };
+        change(kind, !(is_versioned && is_last));
+    }
+    Ok(())
+}
+
+/// Add the changes between two versions of an interface to `changes`.
+fn diff_interfaces(old: &Interface, new: &Interface, changes: &mut Vec<Change>) -> Result<()> {
+    // Check the ordinals are valid, even though methods are matched by name.
+    assign_method_ordinals(old)?;
+    assign_method_ordinals(new)?;
+    let has_method = |interface: &Interface, name: &str| {
+        interface.methods.iter().any(|method| method.name == name)
+    };
+    for method in &old.methods {
+        if !has_method(new, &method.name) {
+            changes.push(Change {
+                container: new.name.clone(),
+                kind: ChangeKind::MethodRemoved { name: method.name.clone() },
+                breaking: true,
+            });
+        }
+    }
+    for method in &new.methods {
+        if !has_method(old, &method.name) {
+            changes.push(Change {
+                container: new.name.clone(),
+                kind: ChangeKind::MethodAdded { name: method.name.clone() },
+                breaking: false,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Return the wire-relevant changes from `old` to `new`, in the order of the
+/// declarations in `new`: fields added, removed, or given another ordinal or
+/// type, and methods added or removed.
+///
+/// Only structs and interfaces present in both versions are compared. It's an
+/// error for either version to have invalid ordinals; see assign_ordinals.
+pub fn diff_modules(old: &Module, new: &Module) -> Result<Vec<Change>> {
+    let mut changes = vec![];
+    for declaration in &new.declarations {
+        match (old.find_declaration(declaration.name()), declaration) {
+            (Some(Declaration::Struct(old)), Declaration::Struct(new)) => {
+                diff_structs(old, new, &mut changes)?
+            }
+            (Some(Declaration::Interface(old)), Declaration::Interface(new)) => {
+                diff_interfaces(old, new, &mut changes)?
+            }
+            _ => (),
+        }
+    }
+    Ok(changes)
+}
@@ -0,0 +1,115 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use mojom_parser::*;
+
+/// Return the changes from the module `old` to the module `new`.
+fn diff(old: &str, new: &str) -> Vec<Change> {
+    diff_modules(&parse_module(old).unwrap(), &parse_module(new).unwrap()).unwrap()
+}
+
+#[gtest(MojomDiffTest, TrailingVersionedField)]
+fn test_trailing_versioned_field() {
+    let old = "struct S { int32 a; [MinVersion=1] bool b; };";
+    let changes =
+        diff(old, "struct S { int32 a; [MinVersion=1] bool b; [MinVersion=2] string c; };");
+    expect_eq!(
+        changes,
+        vec![Change {
+            container: "S".to_string(),
+            kind: ChangeKind::FieldAdded { name: "c".to_string(), ordinal: 2 },
+            breaking: false,
+        }]
+    );
+    expect_eq!(changes[0].to_string(), "`S`: added field `c` with ordinal 2");
+
+    // Without a new version, or before the existing fields, older receivers
+    // would misread the struct.
+    for new in [
+        "struct S { int32 a; [MinVersion=1] bool b; string c; };",
+        "struct S { int32 a; [MinVersion=1] bool b; [MinVersion=1] string c; };",
+        "struct S { int32 a@1; [MinVersion=1] bool b@2; [MinVersion=2] string c@0; };",
+    ] {
+        let changes = diff(old, new);
+        let added: Vec<bool> = changes
+            .iter()
+            .filter(|change| matches!(change.kind, ChangeKind::FieldAdded { .. }))
+            .map(|change| change.breaking)
+            .collect();
+        expect_eq!(added, vec![true], "{}", new);
+    }
+}
+
+#[gtest(MojomDiffTest, ReorderedOrdinals)]
+fn test_reordered_ordinals() {
+    let changes = diff("struct S { int32 a; int64 b; };", "struct S { int32 a@1; int64 b@0; };");
+    expect_eq!(
+        changes,
+        vec![
+            Change {
+                container: "S".to_string(),
+                kind: ChangeKind::FieldReordered {
+                    name: "a".to_string(),
+                    old_ordinal: 0,
+                    new_ordinal: 1
+                },
+                breaking: true,
+            },
+            Change {
+                container: "S".to_string(),
+                kind: ChangeKind::FieldReordered {
+                    name: "b".to_string(),
+                    old_ordinal: 1,
+                    new_ordinal: 0
+                },
+                breaking: true,
+            },
+        ]
+    );
+    expect_eq!(
+        changes[0].to_string(),
+        "`S`: changed the ordinal of field `a` from 0 to 1 (breaking)"
+    );
+}
+
+#[gtest(MojomDiffTest, TypesAndRemovals)]
+fn test_types_and_removals() {
+    let changes = diff(
+        "struct S { int32 a; string? b; bool c; }; struct Gone { int32 x; };",
+        "struct S { int64 a; string? b; };",
+    );
+    let summary: Vec<String> = changes.iter().map(Change::to_string).collect();
+    expect_eq!(
+        summary,
+        vec![
+            "`S`: changed the type of field `a` from `int32` to `int64` (breaking)",
+            "`S`: removed field `c` with ordinal 2 (breaking)",
+        ]
+    );
+    expect_eq!(diff("struct S { int32 a; };", "struct S { int32 a; };"), vec![]);
+}
+
+#[gtest(MojomDiffTest, Methods)]
+fn test_methods() {
+    let changes = diff(
+        "interface I { Keep(); Drop(int32 x); };",
+        "interface I { Keep(); [MinVersion=1] Add() => (); };",
+    );
+    let summary: Vec<String> = changes.iter().map(Change::to_string).collect();
+    expect_eq!(summary, vec!["`I`: removed method `Drop` (breaking)", "`I`: added method `Add`"]);
+
+    let err = diff_modules(
+        &parse_module("struct S { int32 a; };").unwrap(),
+        &parse_module("struct S { int32 a@0; int32 b@0; };").unwrap(),
+    )
+    .unwrap_err()
+    .to_string();
+    expect_true!(err.contains("both have ordinal 0"), "{}", err);
+}
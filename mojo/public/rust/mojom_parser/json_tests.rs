@@ -0,0 +1,53 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use mojom_parser::*;
+
+use serde_json::json;
+
+#[gtest(MojomJsonTest, ToJson)]
+fn test_to_json() {
+    let module = parse_module(
+        "struct Point { array<int32, 2>? coords; };
+         const int32 kMax = -1;",
+    )
+    .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&to_json(&module)).unwrap();
+
+    let point = &json["declarations"][0];
+    expect_eq!(point["kind"], "Struct");
+    expect_eq!(point["value"]["name"], "Point");
+    let coords = &point["value"]["fields"][0];
+    expect_eq!(coords["name"], "coords");
+    expect_eq!(
+        coords["ty"],
+        json!({
+            "kind": "Array",
+            "value": {
+                "element_type": {
+                    "kind": "Int32",
+                    "nullable": false,
+                    "span": {"start": 21, "end": 26},
+                },
+                "length": 2,
+            },
+            "nullable": true,
+            "span": {"start": 15, "end": 31},
+        })
+    );
+
+    let constant = &json["declarations"][1];
+    expect_eq!(constant["kind"], "Const");
+    expect_eq!(
+        constant["value"]["value"],
+        json!({"kind": "Integer", "value": -1, "span": {"start": 71, "end": 73}})
+    );
+    expect_eq!(constant["value"]["span"], json!({"start": 52, "end": 73}));
+}
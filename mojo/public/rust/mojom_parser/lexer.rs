@@ -0,0 +1,287 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Splits the text of a .mojom file into tokens.
+//!
+//! This is the lowest level of the .mojom source parser in parse_source.rs.
+//! Whitespace and comments are dropped by tokenize, so the parser only ever
+//! sees meaningful tokens. The one exception is doc comments: a block of `//`
+//! comments directly above a token is kept on that token.
+//!
+//! Lexer gives every token instead, for tools which only need to lex.
+
+use crate::ast::Span;
+use crate::parse_source::ParseError;
+
+use anyhow::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A name or keyword, e.g. `struct` or `MyStruct`.
+    Identifier,
+    /// An integer literal, in decimal or hexadecimal.
+    Integer,
+    /// A floating point literal, e.g. `1.5` or `2e-10`.
+    Float,
+    /// A double-quoted string literal. The text includes the quotes.
+    String,
+    /// A single punctuation character, or the two-character `=>`.
+    Punct,
+    /// One of KEYWORDS. Only Lexer tells these apart from identifiers.
+    Keyword,
+    /// A `//` or `/* */` comment, including the delimiters. Only produced by
+    /// Lexer.
+    Comment,
+    /// A run of whitespace. Only produced by Lexer.
+    Whitespace,
+}
+
+/// A single token of a .mojom file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// The text of the token, exactly as it appears in the source.
+    pub text: &'a str,
+    /// Offset in bytes from the start of the source to the start of the token.
+    pub start: usize,
+    /// The `//` comment lines directly above this token, exactly as they appear
+    /// in the source, if the token is the first on its line. A blank line
+    /// between the comments and the token means they don't belong to it.
+    pub leading_comment: Option<&'a str>,
+}
+
+impl Token<'_> {
+    /// The span of the token's text in the source.
+    pub fn span(&self) -> Span {
+        Span { start: self.start, end: self.start + self.text.len() }
+    }
+}
+
+// Note that `<<` and `>>` are lexed as two tokens each, since `>>` also closes
+// nested types like `array<array<int32>>`. The parser recombines them where a
+// shift is allowed.
+const PUNCTUATION: &[u8] = b"{}()[]<>;,.=?@-+*/%~|^&";
+
+/// Return the number of bytes at the start of `bytes` satisfying `pred`.
+fn count_while(bytes: &[u8], pred: impl Fn(u8) -> bool) -> usize {
+    bytes.iter().take_while(|b| pred(**b)).count()
+}
+
+/// Return the kind and length of the number at the start of `bytes`.
+fn lex_number(bytes: &[u8]) -> (TokenKind, usize) {
+    let is_digit_at = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+    let mut kind = TokenKind::Integer;
+    let mut len = count_while(bytes, |b| b.is_ascii_digit());
+    let is_hex = len == 1 && matches!(bytes.get(1), Some(b'x' | b'X'));
+    if !is_hex && bytes.get(len) == Some(&b'.') && is_digit_at(len + 1) {
+        kind = TokenKind::Float;
+        len += 1 + count_while(&bytes[len + 1..], |b| b.is_ascii_digit());
+    }
+    if !is_hex && matches!(bytes.get(len), Some(b'e' | b'E')) {
+        let sign_len = usize::from(matches!(bytes.get(len + 1), Some(b'+' | b'-')));
+        if is_digit_at(len + 1 + sign_len) {
+            kind = TokenKind::Float;
+            len += 1 + sign_len;
+            len += count_while(&bytes[len..], |b| b.is_ascii_digit());
+        }
+    }
+    // Literals are lexed along with any trailing letters, so that a typo like
+    // `12ab` is reported as one bad literal, not two tokens. This also covers
+    // the digits of hex literals.
+    len += count_while(&bytes[len..], |b| b.is_ascii_alphanumeric() || b == b'_');
+    (kind, len)
+}
+
+/// Return the length of the identifier at the start of `source[pos..]`, which
+/// must be ASCII. A word with other letters or digits in it is an error, since
+/// splitting it into tokens would give a confusing one.
+fn lex_identifier(source: &str, pos: usize) -> Result<usize> {
+    let rest = &source[pos..];
+    let len = count_while(rest.as_bytes(), |b| b.is_ascii_alphanumeric() || b == b'_');
+    let word_len = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+    if word_len != len {
+        return Err(ParseError {
+            message: format!(
+                "Identifiers may only contain ASCII letters, digits and `_`, but found `{}`",
+                &rest[..word_len]
+            ),
+            span: Span { start: pos, end: pos + word_len },
+        }
+        .into());
+    }
+    Ok(len)
+}
+
+/// Return the kind and length of the token at the start of `source[pos..]`,
+/// which must not be empty. Keywords are reported as identifiers.
+fn lex_token(source: &str, pos: usize) -> Result<(TokenKind, usize)> {
+    let rest = &source.as_bytes()[pos..];
+    let token = match rest[0] {
+        b if b.is_ascii_whitespace() => {
+            (TokenKind::Whitespace, count_while(rest, |b| b.is_ascii_whitespace()))
+        }
+        b'/' if rest.get(1) == Some(&b'/') => {
+            (TokenKind::Comment, count_while(rest, |b| b != b'\n'))
+        }
+        b'/' if rest.get(1) == Some(&b'*') => {
+            let Some(len) = source[pos + 2..].find("*/") else {
+                return Err(ParseError {
+                    message: "Unterminated block comment".to_string(),
+                    span: Span { start: pos, end: pos + 2 },
+                }
+                .into());
+            };
+            (TokenKind::Comment, len + 4)
+        }
+        b if b.is_ascii_alphabetic() || b == b'_' => {
+            (TokenKind::Identifier, lex_identifier(source, pos)?)
+        }
+        b if b.is_ascii_digit() => lex_number(rest),
+        b'"' => {
+            // Skip over escaped characters, so that `\"` doesn't end the string.
+            let mut len = 1;
+            loop {
+                match rest.get(len) {
+                    Some(b'"') => break,
+                    Some(b'\\') => len += 2,
+                    Some(b'\n') | None => {
+                        return Err(ParseError {
+                            message: "Unterminated string".to_string(),
+                            span: Span { start: pos, end: pos + len.min(rest.len()) },
+                        }
+                        .into());
+                    }
+                    Some(_) => len += 1,
+                }
+            }
+            (TokenKind::String, len + 1)
+        }
+        b'=' if rest.get(1) == Some(&b'>') => (TokenKind::Punct, 2),
+        b if PUNCTUATION.contains(&b) => (TokenKind::Punct, 1),
+        _ => {
+            let c = source[pos..].chars().next().unwrap();
+            if c.is_alphabetic() {
+                lex_identifier(source, pos)?;
+            }
+            return Err(ParseError {
+                message: format!("Unexpected character {:?}", c),
+                span: Span { start: pos, end: pos + c.len_utf8() },
+            }
+            .into());
+        }
+    };
+    Ok(token)
+}
+
+/// Split `source` into tokens, dropping whitespace and comments. This is what
+/// the parser uses; see Lexer for a token stream which keeps them.
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>> {
+    let mut tokens = vec![];
+    let mut pos = 0;
+    // The start and end of the current block of `//` comments, and the number
+    // of newlines since the last comment or token.
+    let mut comment: Option<(usize, usize)> = None;
+    let mut newlines = 0;
+    let mut line_has_token = false;
+    while pos < source.len() {
+        let (kind, len) = lex_token(source, pos)?;
+        let text = &source[pos..pos + len];
+        match kind {
+            TokenKind::Whitespace => {
+                let count = text.matches('\n').count();
+                if count > 0 {
+                    newlines += count;
+                    line_has_token = false;
+                }
+            }
+            TokenKind::Comment if text.starts_with("//") => {
+                // A comment after a token on the same line describes that
+                // token, not the next one.
+                comment = match comment {
+                    _ if line_has_token => None,
+                    Some((start, _)) if newlines <= 1 => Some((start, pos + len)),
+                    _ => Some((pos, pos + len)),
+                };
+                newlines = 0;
+            }
+            TokenKind::Comment => comment = None,
+            _ => {
+                let leading_comment = match comment.take() {
+                    Some((start, end)) if newlines <= 1 => Some(&source[start..end]),
+                    _ => None,
+                };
+                tokens.push(Token { kind, text, start: pos, leading_comment });
+                newlines = 0;
+                line_has_token = true;
+            }
+        }
+        pos += len;
+    }
+    Ok(tokens)
+}
+
+/// The words which have a special meaning in .mojom files, and which Lexer
+/// reports as keywords.
+pub const KEYWORDS: &[&str] = &[
+    "array",
+    "const",
+    "default",
+    "enum",
+    "false",
+    "feature",
+    "handle",
+    "import",
+    "interface",
+    "map",
+    "module",
+    "pending_associated_receiver",
+    "pending_associated_remote",
+    "pending_receiver",
+    "pending_remote",
+    "struct",
+    "true",
+    "union",
+];
+
+/// An iterator over every token of a .mojom file, including whitespace and
+/// comments, for tools like syntax highlighters which don't need a full parse.
+///
+/// Joining the text of the tokens gives back the source. After an error, the
+/// iterator ends.
+pub struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+    failed: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Lexer { source, pos: 0, failed: false }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos == self.source.len() {
+            return None;
+        }
+        let (kind, len) = match lex_token(self.source, self.pos) {
+            Ok(token) => token,
+            Err(error) => {
+                self.failed = true;
+                return Some(Err(error));
+            }
+        };
+        let text = &self.source[self.pos..self.pos + len];
+        let kind = match kind {
+            TokenKind::Identifier if KEYWORDS.contains(&text) => TokenKind::Keyword,
+            kind => kind,
+        };
+        let token = Token { kind, text, start: self.pos, leading_comment: None };
+        self.pos += len;
+        Some(Ok(token))
+    }
+}
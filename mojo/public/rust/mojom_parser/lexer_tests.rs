@@ -0,0 +1,131 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use mojom_parser::*;
+
+#[gtest(MojomLexerTest, LexInterface)]
+fn test_lex_interface() {
+    let source = "// Pings.\ninterface I { Ping@1(int32 n) => (string s); };";
+    let tokens: Vec<Token> = Lexer::new(source).collect::<anyhow::Result<_>>().unwrap();
+    let kinds: Vec<(TokenKind, &str)> = tokens
+        .iter()
+        .filter(|token| token.kind != TokenKind::Whitespace)
+        .map(|token| (token.kind, token.text))
+        .collect();
+    use TokenKind::*;
+    expect_eq!(
+        kinds,
+        vec![
+            (Comment, "// Pings."),
+            (Keyword, "interface"),
+            (Identifier, "I"),
+            (Punct, "{"),
+            (Identifier, "Ping"),
+            (Punct, "@"),
+            (Integer, "1"),
+            (Punct, "("),
+            (Identifier, "int32"),
+            (Identifier, "n"),
+            (Punct, ")"),
+            (Punct, "=>"),
+            (Punct, "("),
+            (Identifier, "string"),
+            (Identifier, "s"),
+            (Punct, ")"),
+            (Punct, ";"),
+            (Punct, "}"),
+            (Punct, ";"),
+        ]
+    );
+
+    // Nothing is dropped, so the tokens cover the whole source.
+    expect_eq!(tokens.iter().map(|token| token.text).collect::<Vec<_>>().concat(), source);
+    expect_eq!(tokens[1].kind, Whitespace);
+    expect_eq!(tokens[2].span(), Span { start: 10, end: 19 });
+    expect_eq!(tokens[2].span().text(source), "interface");
+}
+
+#[gtest(MojomLexerTest, LexTrivia)]
+fn test_lex_trivia() {
+    let source = "const /* inline */ float k = 1.5e3; // trailing";
+    let kinds: Vec<(TokenKind, &str)> = Lexer::new(source)
+        .map(|token| token.unwrap())
+        .map(|token| (token.kind, token.text))
+        .collect();
+    use TokenKind::*;
+    expect_eq!(
+        kinds,
+        vec![
+            (Keyword, "const"),
+            (Whitespace, " "),
+            (Comment, "/* inline */"),
+            (Whitespace, " "),
+            (Identifier, "float"),
+            (Whitespace, " "),
+            (Identifier, "k"),
+            (Whitespace, " "),
+            (Punct, "="),
+            (Whitespace, " "),
+            (Float, "1.5e3"),
+            (Punct, ";"),
+            (Whitespace, " "),
+            (Comment, "// trailing"),
+        ]
+    );
+}
+
+#[gtest(MojomLexerTest, LexError)]
+fn test_lex_error() {
+    let mut lexer = Lexer::new("struct \"unterminated");
+    expect_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Keyword);
+    expect_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Whitespace);
+    let err = lexer.next().unwrap().unwrap_err().downcast::<ParseError>().unwrap();
+    expect_eq!(err.message, "Unterminated string");
+    expect_eq!(err.span.start, 7);
+    expect_true!(lexer.next().is_none());
+}
+
+#[gtest(MojomLexerTest, LexUtf8String)]
+fn test_lex_utf8_string() {
+    let source = "const string kGreeting = \"héllo 🌍\"; // ✓";
+    let tokens = tokenize(source).unwrap();
+    let literal = tokens.iter().find(|token| token.kind == TokenKind::String).unwrap();
+    expect_eq!(literal.text, "\"héllo 🌍\"");
+    expect_eq!(literal.span().text(source), literal.text);
+    expect_eq!(tokens.last().unwrap().text, ";");
+
+    // The contents come out unchanged, and deparse back to the same source.
+    let module = parse_module(source).unwrap();
+    let constants = resolve_constants(&module).unwrap();
+    expect_eq!(constants["kGreeting"], Value::String("héllo 🌍".to_string()));
+    let deparsed = deparse_module(&module);
+    expect_eq!(deparsed, "const string kGreeting = \"héllo 🌍\";\n");
+    expect_eq!(parse_module(&deparsed).unwrap(), module);
+}
+
+#[gtest(MojomLexerTest, LexNonAsciiIdentifier)]
+fn test_lex_non_ascii_identifier() {
+    for (source, word) in [("struct Café {};", "Café"), ("struct été {};", "été")] {
+        let (_, errors) = parse_module_with_recovery(source);
+        expect_eq!(errors.len(), 1, "{}", source);
+        expect_eq!(
+            errors[0].message,
+            format!(
+                "Identifiers may only contain ASCII letters, digits and `_`, but found `{}`",
+                word
+            )
+        );
+        expect_eq!(errors[0].span.text(source), word);
+    }
+    // Other characters are still just unexpected.
+    let err = parse_module("struct S {}; €").unwrap_err().downcast::<ParseError>().unwrap();
+    expect_eq!(err.message, "Unexpected character '€'");
+    expect_eq!(err.span.start, 13);
+}
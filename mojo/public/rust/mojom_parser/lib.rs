@@ -8,14 +8,26 @@
 // public, etc. For now, just export everything blindly.
 
 mod ast;
+mod builders;
+mod deparse_source;
 mod deparse_values;
+mod diff;
+mod lexer;
 mod pack;
 mod parse_messages;
 mod parse_primitives;
+mod parse_source;
 mod parse_values;
+mod resolve;
 
 pub use crate::ast::*;
+pub use crate::builders::*;
+pub use crate::deparse_source::*;
 pub use crate::deparse_values::*;
+pub use crate::diff::*;
+pub use crate::lexer::*;
 pub use crate::pack::*;
 pub use crate::parse_messages::*;
+pub use crate::parse_source::*;
 pub use crate::parse_values::*;
+pub use crate::resolve::*;
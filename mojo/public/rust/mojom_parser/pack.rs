@@ -19,6 +19,28 @@
 // bitfields.)
 
 use crate::ast::*;
+use crate::deparse_source::deparse_type;
+use crate::resolve::SemanticError;
+
+use anyhow::{anyhow, bail, Result};
+
+use std::ops::RangeInclusive;
+
+/// The size of the header at the start of every serialized struct, which holds
+/// the struct's size and version.
+pub const STRUCT_HEADER_SIZE: usize = 8;
+
+/// The size of the header at the start of every message, before the request
+/// or response struct, when the message has no request ID.
+pub const MESSAGE_HEADER_SIZE: usize = 24;
+
+/// The size of a message header with a request ID, which a request needs if
+/// it expects a response.
+pub const MESSAGE_HEADER_WITH_REQUEST_ID_SIZE: usize = 32;
+
+/// The size of a message header which also points to the IDs of associated
+/// interfaces sent in the message.
+pub const MESSAGE_HEADER_WITH_INTERFACE_IDS_SIZE: usize = 48;
 
 /// Return the number of bytes we need to skip to reach the given alignment.
 fn bytes_to_align(current_offset: usize, required_alignment: usize) -> usize {
@@ -28,7 +50,7 @@ fn bytes_to_align(current_offset: usize, required_alignment: usize) -> usize {
 /// Represents a single field which has been packed into wire format, with enough
 /// information to both construct the binary representation and map back to the
 /// original type.
-struct PackedField<'a> {
+struct PackedWireField<'a> {
     /// The name of the field in the original struct definition.
     name: &'a str,
     /// The type of the field, which has been recursively packed.
@@ -39,17 +61,22 @@ struct PackedField<'a> {
     end_offset: usize,
 }
 
-impl<'a> PackedField<'a> {
-    /// Create a new PackedField given the original field's information and its location
-    fn new(name: &'a str, ty: MojomWireType, start_offset: usize) -> PackedField<'a> {
-        PackedField { start_offset: start_offset, end_offset: start_offset + ty.size(), name, ty }
+impl<'a> PackedWireField<'a> {
+    /// Create a new PackedWireField given the original field's information and its location
+    fn new(name: &'a str, ty: MojomWireType, start_offset: usize) -> PackedWireField<'a> {
+        PackedWireField {
+            start_offset: start_offset,
+            end_offset: start_offset + ty.size(),
+            name,
+            ty,
+        }
     }
 }
 
 /// Transform the fields of a Mojom struct into their packed representation.
 /// This uses the basic algorithm from mojo/public/tools/mojom/mojom/generate/pack.py
-fn pack_struct(fields: &Vec<(String, MojomType)>) -> Vec<(String, MojomWireType)> {
-    let mut packed_fields: Vec<PackedField> = vec![];
+fn pack_wire_struct(fields: &Vec<(String, MojomType)>) -> Vec<(String, MojomWireType)> {
+    let mut packed_fields: Vec<PackedWireField> = vec![];
     let mut total_length = 0;
     // For each field, see if we can fit it between two existing packed fields.
     // If not, put it at the end.
@@ -65,7 +92,7 @@ fn pack_struct(fields: &Vec<(String, MojomType)>) -> Vec<(String, MojomWireType)
             if (field_size + bytes_to_align(end_of_last_field, field_size)) <= empty_space {
                 packed_fields.insert(
                     i,
-                    PackedField::new(
+                    PackedWireField::new(
                         field_name,
                         field_ty,
                         end_of_last_field + bytes_to_align(end_of_last_field, field_size),
@@ -75,7 +102,7 @@ fn pack_struct(fields: &Vec<(String, MojomType)>) -> Vec<(String, MojomWireType)
             }
         }
         // If we get here, we weren't able to fit it in anywhere.
-        let packed_field = PackedField::new(
+        let packed_field = PackedWireField::new(
             field_name,
             field_ty,
             total_length + bytes_to_align(total_length, field_size),
@@ -98,7 +125,7 @@ pub fn pack_mojom_type(ty: &MojomType, ordinal: Ordinal) -> MojomWireType {
         MojomType::Struct { fields } => MojomWireType::Pointer {
             ordinal,
             nested_data_type: PackedStructuredType::Struct {
-                packed_field_types: pack_struct(fields),
+                packed_field_types: pack_wire_struct(fields),
             },
         },
         MojomType::Array { element_type, num_elements } => {
@@ -134,3 +161,424 @@ pub fn pack_mojom_type(ty: &MojomType, ordinal: Ordinal) -> MojomWireType {
         MojomType::Bool => todo!("pack_mojom_type: Bools not yet implemented"),
     }
 }
+
+/******************************************************************************
+ * All the following functions pack the structs declared in a .mojom source
+ * file, as produced by parse_source.rs.
+ ******************************************************************************/
+
+/// The location of one field of a .mojom struct in the struct's serialized
+/// body, as computed by pack_struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedField {
+    /// The name of the field in the original struct definition.
+    pub name: String,
+    /// The ordinal of the field, as computed by assign_ordinals.
+    pub ordinal: Ordinal,
+    /// Number of bytes from the end of the struct header to the start of the
+    /// field.
+    pub offset: usize,
+    /// For bools, which bit of the byte at `offset` holds the value. None for
+    /// all other types.
+    pub bit: Option<u8>,
+    /// Number of bytes the field takes up. Bools count as a whole byte, which
+    /// they may share with up to seven other bools.
+    pub size: usize,
+}
+
+/// Return the size and alignment of a struct field of type `ty`, in bytes.
+/// User-defined types are looked up in `module` if we have one, and are
+/// otherwise assumed to be structs.
+fn field_layout(ty: &Type, module: Option<&Module>) -> (usize, usize) {
+    match &ty.kind {
+        TypeKind::Bool | TypeKind::Int8 | TypeKind::UInt8 => (1, 1),
+        TypeKind::Int16 | TypeKind::UInt16 => (2, 2),
+        TypeKind::Int32 | TypeKind::UInt32 | TypeKind::Float => (4, 4),
+        TypeKind::Int64 | TypeKind::UInt64 | TypeKind::Double => (8, 8),
+        // Strings, arrays and maps are stored as 64-bit pointers
+        TypeKind::String | TypeKind::Array { .. } | TypeKind::Map { .. } => (8, 8),
+        // Handles are indices into the message's list of handles.
+        TypeKind::Handle(_)
+        | TypeKind::PendingReceiver(_)
+        | TypeKind::PendingAssociatedReceiver(_) => (4, 4),
+        // Remotes are a handle followed by a 4-byte version.
+        TypeKind::PendingRemote(_) | TypeKind::PendingAssociatedRemote(_) => (8, 4),
+        TypeKind::Named(name) => match module.and_then(|module| module.find_declaration(name)) {
+            Some(Declaration::Enum(_)) => (4, 4),
+            // Unions are stored inline: a 4-byte size, a 4-byte tag, and the
+            // 8-byte value.
+            Some(Declaration::Union(_)) => (16, 8),
+            // Interfaces are a 4-byte handle followed by a 4-byte version.
+            Some(Declaration::Interface(_)) => (8, 4),
+            // Structs are stored as 64-bit pointers
+            _ => (8, 8),
+        },
+    }
+}
+
+/// Return the ordinal of each field of `s`, in declaration order.
+///
+/// Fields without an explicit `@N` ordinal get the one after the previous
+/// field's, starting from 0. The ordinals must be unique, but may skip some
+/// values; see ordinal_gaps.
+pub fn assign_ordinals(s: &Struct) -> Result<Vec<Ordinal>> {
+    assign_ordinals_to(s.fields.iter().map(|field| field.ordinal)).map_err(
+        |(first, second, ordinal)| {
+            anyhow!(
+                "Fields `{}` and `{}` of struct `{}` both have ordinal {}",
+                s.fields[first].name,
+                s.fields[second].name,
+                s.name,
+                ordinal
+            )
+        },
+    )
+}
+
+/// Return the ordinal of each method of `interface`, in declaration order.
+///
+/// Ordinals are assigned like those of struct fields, so methods without an
+/// explicit `@N` get the one after the previous method's. The ordinals must be
+/// unique; a duplicate is a SemanticError with the span of the later method.
+pub fn assign_method_ordinals(interface: &Interface) -> Result<Vec<Ordinal>> {
+    assign_ordinals_to(interface.methods.iter().map(|method| method.ordinal)).map_err(
+        |(first, second, ordinal)| {
+            SemanticError {
+                message: format!(
+                    "Methods `{}` and `{}` of interface `{}` both have ordinal {}",
+                    interface.methods[first].name,
+                    interface.methods[second].name,
+                    interface.name,
+                    ordinal
+                ),
+                span: interface.methods[second].span,
+            }
+            .into()
+        },
+    )
+}
+
+/// Assign ordinals to a list of items given their explicit ordinals, for
+/// assign_ordinals and assign_method_ordinals. On a duplicate, returns the
+/// index of both items along with the ordinal they share.
+fn assign_ordinals_to(
+    explicit_ordinals: impl Iterator<Item = Option<u32>>,
+) -> std::result::Result<Vec<Ordinal>, (usize, usize, Ordinal)> {
+    let mut ordinals: Vec<Ordinal> = vec![];
+    for (index, explicit_ordinal) in explicit_ordinals.enumerate() {
+        let ordinal = match explicit_ordinal {
+            Some(ordinal) => ordinal as Ordinal,
+            None => ordinals.last().map_or(0, |last| last + 1),
+        };
+        if let Some(other) = ordinals.iter().position(|other| *other == ordinal) {
+            return Err((other, index, ordinal));
+        }
+        ordinals.push(ordinal);
+    }
+    Ok(ordinals)
+}
+
+/// Return the ranges of ordinals below the largest one in `s` which no field
+/// has, in increasing order.
+pub fn ordinal_gaps(s: &Struct) -> Result<Vec<RangeInclusive<Ordinal>>> {
+    let mut ordinals = assign_ordinals(s)?;
+    ordinals.sort();
+    let mut gaps = vec![];
+    let mut next = 0;
+    for ordinal in ordinals {
+        if ordinal > next {
+            gaps.push(next..=ordinal - 1);
+        }
+        next = ordinal + 1;
+    }
+    Ok(gaps)
+}
+
+/// Return the fields of `s` paired with their ordinals, in ordinal order.
+fn fields_in_ordinal_order(s: &Struct) -> Result<Vec<(Ordinal, &Field)>> {
+    if s.is_native {
+        bail!("Struct `{}` is Native, so its layout is defined by its typemap", s.name)
+    }
+    let mut fields: Vec<_> = assign_ordinals(s)?.into_iter().zip(&s.fields).collect();
+    fields.sort_by_key(|(ordinal, _)| *ordinal);
+    Ok(fields)
+}
+
+/// Pack the fields of a struct, which must be in ordinal order, returning them
+/// in the same order.
+///
+/// This is the same algorithm as pack_wire_struct, except that bools are
+/// packed into bits: a bool placed right after another bool shares its byte if
+/// there's a bit left.
+fn pack_fields(fields: &[(Ordinal, &Field)], module: Option<&Module>) -> Vec<PackedField> {
+    // Kept in offset order while packing.
+    let mut packed_fields: Vec<PackedField> = vec![];
+    for &(ordinal, field) in fields {
+        let (size, alignment) = field_layout(&field.ty, module);
+        let is_bool = field.ty.kind == TypeKind::Bool;
+        // Where this field goes if we put it right after `last_field`.
+        let place_after = |last_field: &PackedField| match last_field.bit {
+            Some(bit) if is_bool && bit < 7 => (last_field.offset, Some(bit + 1)),
+            _ => {
+                let end_of_last_field = last_field.offset + last_field.size;
+                let offset = end_of_last_field + bytes_to_align(end_of_last_field, alignment);
+                (offset, is_bool.then_some(0))
+            }
+        };
+        // Try every pair (i-1, i) of adjacent packed fields. If we don't fit
+        // anywhere, put it at the end.
+        let (index, (offset, bit)) = (1..packed_fields.len())
+            .map(|i| (i, place_after(&packed_fields[i - 1])))
+            .find(|(i, (offset, _))| offset + size <= packed_fields[*i].offset)
+            .unwrap_or_else(|| match packed_fields.last() {
+                Some(last_field) => (packed_fields.len(), place_after(last_field)),
+                None => (0, (0, is_bool.then_some(0))),
+            });
+        let packed_field = PackedField { name: field.name.clone(), ordinal, offset, bit, size };
+        packed_fields.insert(index, packed_field);
+    }
+    packed_fields.sort_by_key(|packed_field| packed_field.ordinal);
+    packed_fields
+}
+
+/// Compute where each field of `s` goes in its serialized form, following the
+/// same algorithm as mojo/public/tools/mojom/mojom/generate/pack.py. The
+/// fields are packed and returned in ordinal order, which is declaration
+/// order unless some have explicit ordinals. It's an error if the ordinals
+/// are invalid; see assign_ordinals.
+///
+/// User-defined types are assumed to be structs. Use pack_struct_in_module if
+/// the struct may contain enums, unions, or interfaces, which are laid out
+/// differently. `[Native]` structs can't be packed, since their layout is up
+/// to their typemap.
+pub fn pack_struct(s: &Struct) -> Result<Vec<PackedField>> {
+    Ok(pack_fields(&fields_in_ordinal_order(s)?, None))
+}
+
+/// Like pack_struct, but looks up user-defined types in `module` to decide how
+/// to lay them out.
+pub fn pack_struct_in_module(s: &Struct, module: &Module) -> Result<Vec<PackedField>> {
+    Ok(pack_fields(&fields_in_ordinal_order(s)?, Some(module)))
+}
+
+/// Like pack_struct, but return the fields in the order they appear in the
+/// serialized struct: by offset, and then by bit for bools sharing a byte. This
+/// is the order to emit serialization code in, and differs from ordinal order
+/// whenever a later field fills padding left by an earlier one.
+pub fn packed_in_offset_order(s: &Struct) -> Result<Vec<PackedField>> {
+    Ok(sort_by_offset(pack_struct(s)?))
+}
+
+/// Like packed_in_offset_order, but looks up user-defined types in `module` as
+/// in pack_struct_in_module.
+pub fn packed_in_offset_order_in_module(s: &Struct, module: &Module) -> Result<Vec<PackedField>> {
+    Ok(sort_by_offset(pack_struct_in_module(s, module)?))
+}
+
+fn sort_by_offset(mut packed_fields: Vec<PackedField>) -> Vec<PackedField> {
+    // No two fields share an offset and bit, so the order is fully determined.
+    packed_fields.sort_by_key(|field| (field.offset, field.bit));
+    packed_fields
+}
+
+/// Something in a struct which is valid, and doesn't stop it from being
+/// packed, but is probably a mistake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub message: String,
+    pub span: Span,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Like pack_struct_in_module, but also return warnings about the struct. For
+/// now that's a warning listing any gaps in its ordinals, which are usually a
+/// typo and leave unused slots in the layout.
+pub fn pack_struct_with_warnings(
+    s: &Struct,
+    module: &Module,
+) -> Result<(Vec<PackedField>, Vec<Warning>)> {
+    let packed_fields = pack_struct_in_module(s, module)?;
+    let gaps = ordinal_gaps(s)?;
+    let mut warnings = vec![];
+    if !gaps.is_empty() {
+        let gaps: Vec<String> = gaps
+            .iter()
+            .map(|gap| {
+                if gap.start() == gap.end() {
+                    gap.start().to_string()
+                } else {
+                    format!("{}..={}", gap.start(), gap.end())
+                }
+            })
+            .collect();
+        warnings.push(Warning {
+            message: format!("Struct `{}` has no fields with ordinals {}", s.name, gaps.join(", ")),
+            span: s.span,
+        });
+    }
+    Ok((packed_fields, warnings))
+}
+
+/// Compute the serialized size of each version of a struct, from the packed
+/// layout of its fields.
+fn version_sizes_of(s: &Struct, module: Option<&Module>) -> Result<Vec<(u32, usize)>> {
+    let fields = fields_in_ordinal_order(s)?;
+    let packed_fields = pack_fields(&fields, module);
+    let mut versions = vec![];
+    let mut last_version = 0;
+    let mut last_payload_size = 0;
+    for ((_, field), packed_field) in fields.iter().zip(&packed_fields) {
+        let version = field.min_version.unwrap_or(0);
+        if version < last_version {
+            bail!(
+                "Field `{}` of struct `{}` has MinVersion {}, but comes after a field with \
+                 MinVersion {}",
+                field.name,
+                s.name,
+                version,
+                last_version
+            )
+        }
+        if version != last_version {
+            versions.push((last_version, last_payload_size + STRUCT_HEADER_SIZE));
+            last_version = version;
+        }
+        // A newer field may have been packed into padding between older ones,
+        // so the largest end offset so far is what counts, not the latest.
+        let end_of_field = packed_field.offset + packed_field.size;
+        let payload_size = end_of_field + bytes_to_align(end_of_field, 8);
+        last_payload_size = last_payload_size.max(payload_size);
+    }
+    versions.push((last_version, last_payload_size + STRUCT_HEADER_SIZE));
+    Ok(versions)
+}
+
+/// Return the serialized size of `s`, including its header, at each version
+/// of the struct. Each version includes the fields whose `[MinVersion]` is at
+/// most that version; fields without one are in version 0.
+///
+/// Fields must be in order of increasing version, by ordinal. Like
+/// pack_struct, this assumes all user-defined types are structs.
+pub fn version_sizes(s: &Struct) -> Result<Vec<(u32, usize)>> {
+    version_sizes_of(s, None)
+}
+
+/// Like version_sizes, but looks up user-defined types in `module` to decide
+/// how to lay them out.
+pub fn version_sizes_in_module(s: &Struct, module: &Module) -> Result<Vec<(u32, usize)>> {
+    version_sizes_of(s, Some(module))
+}
+
+fn request_min_size_of(method: &Method, module: Option<&Module>) -> Result<usize> {
+    let versions = version_sizes_of(&method.request_struct(), module)?;
+    let (_, struct_size) = versions.last().expect("Every struct has a version 0");
+    let sends_associated_interfaces = method.parameters.iter().any(|parameter| {
+        matches!(
+            parameter.ty.kind,
+            TypeKind::PendingAssociatedRemote(_) | TypeKind::PendingAssociatedReceiver(_)
+        )
+    });
+    let header_size = if sends_associated_interfaces {
+        MESSAGE_HEADER_WITH_INTERFACE_IDS_SIZE
+    } else if method.response_parameters.is_some() {
+        MESSAGE_HEADER_WITH_REQUEST_ID_SIZE
+    } else {
+        MESSAGE_HEADER_SIZE
+    };
+    Ok(header_size + struct_size)
+}
+
+// This lives here rather than in ast.rs since it's built on packing.
+impl Method {
+    /// Return the smallest possible size of a request message for this method,
+    /// at the latest version of its parameters: the message header followed by
+    /// the packed request struct. Strings, arrays and other out-of-line values
+    /// count only their pointer, since their size isn't known until they're
+    /// sent.
+    ///
+    /// Only parameters which are associated interfaces themselves are
+    /// recognized as needing the larger header, so the size may be too small
+    /// for a method which sends them inside a struct. Like pack_struct, this
+    /// assumes all user-defined types are structs.
+    pub fn request_min_size(&self) -> Result<usize> {
+        request_min_size_of(self, None)
+    }
+
+    /// Like request_min_size, but looks up user-defined types in `module` to
+    /// decide how to lay them out.
+    pub fn request_min_size_in_module(&self, module: &Module) -> Result<usize> {
+        request_min_size_of(self, Some(module))
+    }
+}
+
+/// A 64-bit FNV-1a hash. Unlike std's hashers, this is guaranteed to give the
+/// same result everywhere, so fingerprints can be checked in.
+struct Fingerprinter(u64);
+
+impl Fingerprinter {
+    fn new() -> Self {
+        Fingerprinter(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Add the ordinal, type and version of each field, in ordinal order.
+    fn write_fields(&mut self, s: &Struct) -> Result<()> {
+        let fields = fields_in_ordinal_order(s)?;
+        self.write_u64(fields.len() as u64);
+        for (ordinal, field) in fields {
+            self.write_u64(ordinal as u64);
+            let ty = deparse_type(&field.ty);
+            self.write_u64(ty.len() as u64);
+            self.write(ty.as_bytes());
+            self.write_u64(u64::from(field.min_version.unwrap_or(0)));
+        }
+        Ok(())
+    }
+}
+
+/// Return a hash of everything about `s` which affects its wire format: the
+/// ordinal, type and `[MinVersion]` of each field. Names of the struct and its
+/// fields don't count, but the names of user-defined types do.
+///
+/// The result is the same on every platform and run, so it can be compared
+/// against a known value to catch incompatible changes.
+pub fn fingerprint(s: &Struct) -> Result<u64> {
+    let mut fingerprinter = Fingerprinter::new();
+    fingerprinter.write_fields(s)?;
+    Ok(fingerprinter.0)
+}
+
+/// Like fingerprint, but for an interface: a hash of the ordinal, request and
+/// response parameters and `[MinVersion]` of each method, in order.
+pub fn interface_fingerprint(interface: &Interface) -> Result<u64> {
+    let mut fingerprinter = Fingerprinter::new();
+    fingerprinter.write_u64(interface.methods.len() as u64);
+    for (ordinal, method) in assign_method_ordinals(interface)?.into_iter().zip(&interface.methods)
+    {
+        fingerprinter.write_u64(ordinal as u64);
+        fingerprinter.write_u64(u64::from(method.min_version.unwrap_or(0)));
+        fingerprinter.write_fields(&method.request_struct())?;
+        match method.response_struct() {
+            Some(response) => {
+                fingerprinter.write_u64(1);
+                fingerprinter.write_fields(&response)?;
+            }
+            None => fingerprinter.write_u64(0),
+        }
+    }
+    Ok(fingerprinter.0)
+}
@@ -0,0 +1,399 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use crate::without_field_spans;
+use mojom_parser::*;
+
+/// Return the struct named `name` in `module`.
+fn find_struct<'a>(module: &'a Module, name: &str) -> &'a Struct {
+    module
+        .declarations
+        .iter()
+        .find_map(|declaration| match declaration {
+            Declaration::Struct(s) if s.name == name => Some(s),
+            _ => None,
+        })
+        .unwrap()
+}
+
+/// Return (name, offset, bit) for each packed field, for easier comparison.
+fn layout(packed_fields: &[PackedField]) -> Vec<(&str, usize, Option<u8>)> {
+    packed_fields.iter().map(|field| (field.name.as_str(), field.offset, field.bit)).collect()
+}
+
+#[gtest(MojomPackTest, NativeStructNotPacked)]
+fn test_native_struct_not_packed() {
+    let module = parse_module("[Native] struct Rect; struct S { Rect rect; };").unwrap();
+    let err = pack_struct(find_struct(&module, "Rect")).unwrap_err().to_string();
+    expect_true!(err.contains("is Native"), "{}", err);
+    expect_true!(version_sizes(find_struct(&module, "Rect")).is_err());
+    // Fields of a native struct type are still pointers.
+    let packed = pack_struct_in_module(find_struct(&module, "S"), &module).unwrap();
+    expect_eq!(layout(&packed), vec![("rect", 0, None)]);
+}
+
+#[gtest(MojomPackTest, BoolInt32Int64)]
+fn test_bool_int32_int64() {
+    let module = parse_module("struct S { bool a; int32 b; int64 c; };").unwrap();
+    let packed = pack_struct(find_struct(&module, "S")).unwrap();
+    expect_eq!(layout(&packed), vec![("a", 0, Some(0)), ("b", 4, None), ("c", 8, None)]);
+    let sizes: Vec<usize> = packed.iter().map(|field| field.size).collect();
+    expect_eq!(sizes, vec![1, 4, 8]);
+    let ordinals: Vec<usize> = packed.iter().map(|field| field.ordinal).collect();
+    expect_eq!(ordinals, vec![0, 1, 2]);
+}
+
+#[gtest(MojomPackTest, FillPadding)]
+fn test_fill_padding() {
+    let module = parse_module(
+        "struct S { int8 a; int64 b; int16 c; bool d; bool e; int32 f; string g; uint8 h; };",
+    )
+    .unwrap();
+    let packed = pack_struct(find_struct(&module, "S")).unwrap();
+    expect_eq!(
+        layout(&packed),
+        vec![
+            ("a", 0, None),
+            ("b", 8, None),
+            ("c", 2, None),
+            ("d", 1, Some(0)),
+            ("e", 1, Some(1)),
+            ("f", 4, None),
+            ("g", 16, None),
+            ("h", 24, None),
+        ]
+    );
+}
+
+#[gtest(MojomPackTest, OffsetOrder)]
+fn test_offset_order() {
+    let source = "enum E { A }; \
+                  struct S { int8 a; int64 b; int16 c; bool d; bool e; E f; string g; uint8 h; };";
+    let module = parse_module(source).unwrap();
+    let s = find_struct(&module, "S");
+    let in_order = |packed: &[PackedField]| {
+        layout(packed).iter().map(|(name, _, _)| name.to_string()).collect::<Vec<_>>().concat()
+    };
+    let packed = packed_in_offset_order(s).unwrap();
+    // Without the module, `f` is taken to be a struct pointer.
+    expect_eq!(in_order(&packed), "adechbfg");
+    expect_true!(packed.windows(2).all(|w| (w[0].offset, w[0].bit) < (w[1].offset, w[1].bit)));
+    expect_eq!(in_order(&packed_in_offset_order_in_module(s, &module).unwrap()), "adecfbgh");
+    // The fields are the same as in ordinal order.
+    let mut by_ordinal = packed.clone();
+    by_ordinal.sort_by_key(|field| field.ordinal);
+    expect_eq!(by_ordinal, pack_struct(s).unwrap());
+}
+
+#[gtest(MojomPackTest, UserDefinedTypes)]
+fn test_user_defined_types() {
+    let module = parse_module(
+        "enum E { A };
+         union U { int32 i; };
+         interface I {};
+         struct Other {};
+         struct S { E e; I i; U u; Other other; E e2; };",
+    )
+    .unwrap();
+    let s = find_struct(&module, "S");
+    expect_eq!(
+        layout(&pack_struct_in_module(s, &module).unwrap()),
+        vec![
+            ("e", 0, None),
+            ("i", 4, None),
+            ("u", 16, None),
+            ("other", 32, None),
+            ("e2", 12, None)
+        ]
+    );
+    // Without the module, everything is assumed to be a struct pointer.
+    expect_eq!(
+        layout(&pack_struct(s).unwrap()),
+        vec![
+            ("e", 0, None),
+            ("i", 8, None),
+            ("u", 16, None),
+            ("other", 24, None),
+            ("e2", 32, None)
+        ]
+    );
+}
+
+#[gtest(MojomPackTest, VersionSizes)]
+fn test_version_sizes() {
+    let module = parse_module(
+        "struct Grows {
+           int32 a;
+           int64 b;
+           [MinVersion=1] int32 c;
+           [MinVersion=1] bool d;
+           [MinVersion=3] string e;
+           [MinVersion=3] int8 f;
+         };",
+    )
+    .unwrap();
+    let s = find_struct(&module, "Grows");
+    // c fits in the padding after a, but d has to go at the end. Version 3 adds
+    // e at the end too, while f fits in the padding after d.
+    expect_eq!(
+        layout(&pack_struct(s).unwrap()),
+        vec![
+            ("a", 0, None),
+            ("b", 8, None),
+            ("c", 4, None),
+            ("d", 16, Some(0)),
+            ("e", 24, None),
+            ("f", 17, None),
+        ]
+    );
+    expect_eq!(version_sizes(s).unwrap(), vec![(0, 24), (1, 32), (3, 40)]);
+}
+
+#[gtest(MojomPackTest, VersionSizesUnversioned)]
+fn test_version_sizes_unversioned() {
+    let module = parse_module("struct Empty {}; struct One { bool a; };").unwrap();
+    expect_eq!(version_sizes(find_struct(&module, "Empty")).unwrap(), vec![(0, 8)]);
+    expect_eq!(version_sizes(find_struct(&module, "One")).unwrap(), vec![(0, 16)]);
+}
+
+#[gtest(MojomPackTest, VersionSizesOutOfOrder)]
+fn test_version_sizes_out_of_order() {
+    let module =
+        parse_module("struct S { [MinVersion=2] int32 a; [MinVersion=1] int32 b; };").unwrap();
+    let err = version_sizes(find_struct(&module, "S")).unwrap_err().to_string();
+    expect_true!(err.contains("`b`"), "{}", err);
+}
+
+#[gtest(MojomPackTest, Handles)]
+fn test_handles() {
+    let module = parse_module(
+        "struct S {
+           handle a;
+           pending_remote<I> b;
+           pending_receiver<I> c;
+           pending_associated_remote<I> d;
+           pending_associated_receiver<I> e;
+         };",
+    )
+    .unwrap();
+    expect_eq!(
+        layout(&pack_struct(find_struct(&module, "S")).unwrap()),
+        vec![("a", 0, None), ("b", 4, None), ("c", 12, None), ("d", 16, None), ("e", 24, None)]
+    );
+}
+
+#[gtest(MojomPackTest, ExplicitOrdinals)]
+fn test_explicit_ordinals() {
+    // `c` comes after `b`'s explicit ordinal, and `a` fills the remaining gap.
+    let module = parse_module("struct S { int8 a@3; int64 b@1; int32 c; int8 d@0; };").unwrap();
+    let s = find_struct(&module, "S");
+    expect_eq!(assign_ordinals(s).unwrap(), vec![3, 1, 2, 0]);
+    // Fields are packed in ordinal order, so `d` goes first.
+    let packed = pack_struct(s).unwrap();
+    expect_eq!(
+        layout(&packed),
+        vec![("d", 0, None), ("b", 8, None), ("c", 4, None), ("a", 1, None)]
+    );
+    let ordinals: Vec<usize> = packed.iter().map(|field| field.ordinal).collect();
+    expect_eq!(ordinals, vec![0, 1, 2, 3]);
+}
+
+#[gtest(MojomPackTest, OrdinalGaps)]
+fn test_ordinal_gaps() {
+    let module = parse_module("struct S { int32 a@0; int32 b@1; int32 c@5; };").unwrap();
+    let s = find_struct(&module, "S");
+    expect_eq!(ordinal_gaps(s).unwrap(), vec![2..=4]);
+    let (packed, warnings) = pack_struct_with_warnings(s, &module).unwrap();
+    expect_eq!(packed, pack_struct(s).unwrap());
+    expect_eq!(warnings.len(), 1);
+    expect_eq!(warnings[0].message, "Struct `S` has no fields with ordinals 2..=4");
+    expect_eq!(warnings[0].span, s.span);
+
+    let module = parse_module("struct S { int32 a@1; int32 b@3; int32 c; };").unwrap();
+    let (_, warnings) = pack_struct_with_warnings(find_struct(&module, "S"), &module).unwrap();
+    expect_eq!(warnings[0].message, "Struct `S` has no fields with ordinals 0, 2");
+
+    let module = parse_module("struct S { int32 a@1; int32 b@0; };").unwrap();
+    let (_, warnings) = pack_struct_with_warnings(find_struct(&module, "S"), &module).unwrap();
+    expect_eq!(warnings, vec![]);
+}
+
+#[gtest(MojomPackTest, InvalidOrdinals)]
+fn test_invalid_ordinals() {
+    let module = parse_module("struct S { int32 a@1; int32 b@0; int32 c; };").unwrap();
+    let err = assign_ordinals(find_struct(&module, "S")).unwrap_err().to_string();
+    expect_eq!(err, "Fields `a` and `c` of struct `S` both have ordinal 1");
+    expect_true!(pack_struct(find_struct(&module, "S")).is_err());
+
+    for source in
+        ["struct S { int32 a@; };", "struct S { int32 a@-1; };", "struct S { int32 a@x; };"]
+    {
+        expect_true!(parse_module(source).is_err(), "{}", source);
+    }
+}
+
+#[gtest(MojomPackTest, MethodParamsStructs)]
+fn test_method_params_structs() {
+    let module = parse_module(
+        "interface I { F(bool a, [MinVersion=1] string? b@2, int32 c@1) => (uint8 d); G(); };
+         struct Expected { bool a; [MinVersion=1] string? b@2; int32 c@1; };",
+    )
+    .unwrap();
+    let Declaration::Interface(interface) = &module.declarations[0] else {
+        panic!("Expected an interface, got {:?}", module.declarations[0])
+    };
+    let request = interface.methods[0].request_struct();
+    let expected = find_struct(&module, "Expected");
+    expect_eq!(request.name, "F_Params");
+    expect_eq!(without_field_spans(&request.fields), without_field_spans(&expected.fields));
+    expect_eq!(pack_struct(&request).unwrap(), pack_struct(expected).unwrap());
+    expect_eq!(
+        layout(&pack_struct(&request).unwrap()),
+        vec![("a", 0, Some(0)), ("c", 4, None), ("b", 8, None)]
+    );
+
+    let response = interface.methods[0].response_struct().unwrap();
+    expect_eq!(response.name, "F_ResponseParams");
+    expect_eq!(layout(&pack_struct(&response).unwrap()), vec![("d", 0, None)]);
+    expect_eq!(interface.methods[1].response_struct(), None);
+}
+
+#[gtest(MojomPackTest, RequestMinSize)]
+fn test_request_min_size() {
+    let module = parse_module(
+        "enum E { A };
+         interface I {
+           F(int32 a, int64 b, string c) => ();
+           G(int32 a, bool b, string c);
+           H(int32 a, [MinVersion=1] int64 b);
+           J(pending_associated_remote<I> i);
+           K(E e, int32 a);
+         };",
+    )
+    .unwrap();
+    let Declaration::Interface(interface) = &module.declarations[1] else { panic!() };
+    let sizes: Vec<usize> =
+        interface.methods.iter().map(|method| method.request_min_size().unwrap()).collect();
+    expect_eq!(
+        sizes,
+        vec![
+            // `a` at 0, `b` at 8 and the pointer to `c` at 16, after a header
+            // with a request ID since F replies.
+            32 + 8 + 24,
+            // `a` at 0, `b` at 4 and `c` at 8.
+            24 + 8 + 16,
+            // The latest version includes `b`.
+            24 + 8 + 16,
+            // The endpoint takes 8 bytes, and needs the largest header.
+            48 + 8 + 8,
+            // Without the module, `e` is taken to be a pointer to a struct.
+            24 + 8 + 16,
+        ]
+    );
+    expect_eq!(interface.methods[4].request_min_size_in_module(&module).unwrap(), 24 + 8 + 8);
+}
+
+#[gtest(MojomPackTest, BoolBits)]
+fn test_bool_bits() {
+    let module = parse_module("struct S { bool a; bool b; bool c; bool d; bool e; };").unwrap();
+    let s = find_struct(&module, "S");
+    expect_eq!(
+        layout(&pack_struct(s).unwrap()),
+        vec![
+            ("a", 0, Some(0)),
+            ("b", 0, Some(1)),
+            ("c", 0, Some(2)),
+            ("d", 0, Some(3)),
+            ("e", 0, Some(4)),
+        ]
+    );
+    expect_eq!(version_sizes(s).unwrap(), vec![(0, 16)]);
+
+    // Bools after a larger field still share the first bool's byte, and a ninth
+    // bool starts a new byte.
+    let module = parse_module(
+        "struct S { bool a; int32 b; bool c; bool d; bool e; bool f; bool g; bool h; bool i; bool j; };",
+    )
+    .unwrap();
+    let bits: Vec<(usize, Option<u8>)> = pack_struct(find_struct(&module, "S"))
+        .unwrap()
+        .iter()
+        .map(|field| (field.offset, field.bit))
+        .collect();
+    expect_eq!(
+        bits,
+        vec![
+            (0, Some(0)),
+            (4, None),
+            (0, Some(1)),
+            (0, Some(2)),
+            (0, Some(3)),
+            (0, Some(4)),
+            (0, Some(5)),
+            (0, Some(6)),
+            (0, Some(7)),
+            (1, Some(0)),
+        ]
+    );
+}
+
+/// Return the fingerprint of the only struct in `source`.
+fn struct_fingerprint(source: &str) -> u64 {
+    fingerprint(find_struct(&parse_module(source).unwrap(), "S")).unwrap()
+}
+
+/// Return the fingerprint of the only interface in `source`.
+fn only_interface_fingerprint(source: &str) -> u64 {
+    match parse_module(source).unwrap().declarations.as_slice() {
+        [Declaration::Interface(interface)] => interface_fingerprint(interface).unwrap(),
+        declarations => panic!("Expected a single interface, got {:?}", declarations),
+    }
+}
+
+#[gtest(MojomPackTest, Fingerprint)]
+fn test_fingerprint() {
+    let original = struct_fingerprint("struct S { int32 a; [MinVersion=1] string? b; };");
+    // The fingerprint is fixed, not just consistent within a run.
+    expect_eq!(original, 0x6c8804fa3e4245fb);
+    expect_eq!(original, struct_fingerprint("struct S { int32 x; [MinVersion=1] string? y; };"));
+    expect_eq!(
+        original,
+        struct_fingerprint("struct S { int32 a@0; [MinVersion=1] string? b@1; };")
+    );
+    for changed in [
+        "struct S { int64 a; [MinVersion=1] string? b; };",
+        "struct S { int32 a; [MinVersion=1] string b; };",
+        "struct S { int32 a; [MinVersion=2] string? b; };",
+        "struct S { int32 a@1; [MinVersion=1] string? b@0; };",
+        "struct S { int32 a; };",
+    ] {
+        expect_ne!(original, struct_fingerprint(changed), "{}", changed);
+    }
+
+    let original = only_interface_fingerprint("interface I { F(int32 a) => (bool b); G(); };");
+    expect_eq!(
+        original,
+        only_interface_fingerprint("interface J { X(int32 c) => (bool d); Y(); };")
+    );
+    expect_eq!(
+        original,
+        only_interface_fingerprint("interface I { F@0(int32 a) => (bool b); G@1(); };")
+    );
+    for changed in [
+        "interface I { F(uint32 a) => (bool b); G(); };",
+        "interface I { F(int32 a) => (bool b); G() => (); };",
+        "interface I { F(int32 a); G(); };",
+        "interface I { G(); F(int32 a) => (bool b); };",
+        "interface I { F(int32 a) => (bool b); [MinVersion=1] G(); };",
+        "interface I { F(int32 a) => (bool b); G@2(); };",
+    ] {
+        expect_ne!(original, only_interface_fingerprint(changed), "{}", changed);
+    }
+}
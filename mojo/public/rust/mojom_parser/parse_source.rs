@@ -0,0 +1,1306 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Defines functions for parsing the text of .mojom files.
+//!
+//! Everything else in this crate deals with Mojom values as they appear on the
+//! wire. This module instead reads the declarations themselves, using a simple
+//! recursive descent parser over the tokens produced by lexer.rs.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::ast::*;
+use crate::lexer::{tokenize, Token, TokenKind, KEYWORDS};
+
+use anyhow::{bail, Result};
+
+/// An error found while parsing a .mojom file, along with where it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// Display the error in the usual `path:line:column: message` form, given
+    /// the file it came from, followed by the offending line of `source` with
+    /// carets under the span.
+    pub fn display<'a>(&'a self, path: &'a Path, source: &'a str) -> DisplayParseError<'a> {
+        DisplayParseError { error: self, path, source }
+    }
+}
+
+/// Helper for displaying a ParseError along with where it was found; see
+/// ParseError::display.
+pub struct DisplayParseError<'a> {
+    error: &'a ParseError,
+    path: &'a Path,
+    source: &'a str,
+}
+
+impl std::fmt::Display for DisplayParseError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.path.display(), format_error(self.error, self.source))
+    }
+}
+
+/// Render `error` for display, given the source it came from: a first line
+/// `line:column: message`, followed by the offending line of `source` with
+/// carets under the span.
+///
+/// ParseError::display adds the path of the file in front, for the usual
+/// `path:line:column: message` form.
+pub fn format_error(error: &ParseError, source: &str) -> String {
+    let (line, column) = line_and_column(source, error.span.start);
+    let line_start = source[..error.span.start].rfind('\n').map_or(0, |newline| newline + 1);
+    let line_text = source[line_start..].lines().next().unwrap_or("");
+    // Keep any tabs before the error, so that the carets line up.
+    let indent: String = source[line_start..error.span.start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let span_end = error.span.end.min(line_start + line_text.len());
+    let width = source.get(error.span.start..span_end).map_or(0, |text| text.chars().count());
+    format!(
+        "{}:{}: {}\n{}\n{}{}",
+        line,
+        column,
+        error.message,
+        line_text,
+        indent,
+        "^".repeat(width.max(1))
+    )
+}
+
+/// An attribute from a list like `[Sync, MinVersion=2]`, before we've
+/// interpreted it.
+struct Attribute<'a> {
+    name: &'a str,
+    /// The value after the `=`, if there was one.
+    value: Option<AttributeValue>,
+}
+
+/// The value of an attribute, exactly as written.
+struct AttributeValue {
+    text: String,
+    /// The kind of the first token of the value. A negative integer counts as
+    /// an Integer.
+    kind: TokenKind,
+    /// Offset in bytes from the start of the source to the start of the value.
+    start: usize,
+    /// Offset in bytes from the start of the source to the end of the value.
+    end: usize,
+}
+
+/// Interpret an integer literal, in either decimal or hexadecimal.
+fn parse_integer(text: &str) -> Option<u64> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex_digits) => u64::from_str_radix(hex_digits, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Interpret an integer literal token as a value.
+fn parse_integer_token(token: &Token) -> Result<i128> {
+    match parse_integer(token.text) {
+        Some(value) => Ok(i128::from(value)),
+        None => Err(ParseError {
+            message: format!("Invalid integer literal `{}`", token.text),
+            span: token.span(),
+        }
+        .into()),
+    }
+}
+
+/// Interpret a string literal token, returning its contents without quotes.
+///
+/// The escapes `\n`, `\r`, `\t`, `\"`, `\\`, `\xNN` and `\uNNNN` are
+/// replaced with the characters they stand for.
+fn parse_string_token(token: &Token) -> Result<String> {
+    let contents = &token.text[1..token.text.len() - 1];
+    let mut ret = String::with_capacity(contents.len());
+    let mut chars = contents.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        // Offset of the backslash in the source.
+        let start = token.start + 1 + i;
+        let invalid_escape = |len: usize| -> anyhow::Error {
+            let end = (i + len).min(contents.len());
+            ParseError {
+                message: format!(
+                    "Invalid escape sequence `{}`",
+                    contents.get(i..end).unwrap_or(&contents[i..])
+                ),
+                span: Span { start, end: token.start + 1 + end },
+            }
+            .into()
+        };
+        let Some((_, kind)) = chars.next() else { return Err(invalid_escape(1)) };
+        let escaped = match kind {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '"' => '"',
+            '\\' => '\\',
+            'x' | 'u' => {
+                let digits = if kind == 'x' { 2 } else { 4 };
+                let rest = chars.as_str();
+                let hex = rest
+                    .get(..digits)
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                let Some(c) = hex.and_then(char::from_u32) else {
+                    // Point at as much of the escape as there is.
+                    let len =
+                        rest.bytes().take(digits).take_while(u8::is_ascii_alphanumeric).count();
+                    return Err(invalid_escape(2 + len));
+                };
+                chars.nth(digits - 1);
+                c
+            }
+            other => return Err(invalid_escape(1 + other.len_utf8())),
+        };
+        ret.push(escaped);
+    }
+    Ok(ret)
+}
+
+/// Return whether an attribute with no value, like `[Sync]`, is present.
+fn flag_attribute(attributes: &[Attribute], name: &str) -> Result<bool> {
+    match attributes.iter().find(|attribute| attribute.name == name) {
+        Some(Attribute { value: Some(value), .. }) => Err(ParseError {
+            message: format!(
+                "{} attribute does not take a value, but found `{}`",
+                name, value.text
+            ),
+            span: Span { start: value.start, end: value.end },
+        }
+        .into()),
+        Some(_) => Ok(true),
+        None => Ok(false),
+    }
+}
+
+/// Interpret the `[EnableIf=feature]` and `[EnableIfNot=feature]` attributes,
+/// if present. At most one of them is allowed.
+fn feature_condition(attributes: &[Attribute]) -> Result<Option<FeatureCondition>> {
+    let mut condition = None;
+    for attribute in attributes {
+        let make_condition: fn(String) -> FeatureCondition = match attribute.name {
+            "EnableIf" => FeatureCondition::EnableIf,
+            "EnableIfNot" => FeatureCondition::EnableIfNot,
+            _ => continue,
+        };
+        let Some(value) = &attribute.value else {
+            bail!("{} attribute requires a feature name", attribute.name)
+        };
+        if !value.text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return Err(ParseError {
+                message: format!(
+                    "{} requires a feature name, but found `{}`",
+                    attribute.name, value.text
+                ),
+                span: Span { start: value.start, end: value.end },
+            }
+            .into());
+        }
+        if condition.is_some() {
+            bail!("EnableIf and EnableIfNot cannot both be used on the same declaration")
+        }
+        condition = Some(make_condition(value.text.clone()));
+    }
+    Ok(condition)
+}
+
+/// The attributes every declaration and member understands.
+const CONDITION_ATTRIBUTES: [&str; 2] = ["EnableIf", "EnableIfNot"];
+
+/// Collect the attributes which aren't in `known`, or CONDITION_ATTRIBUTES, for
+/// a node's extra_attributes.
+fn extra_attributes(
+    attributes: &[Attribute],
+    known: &[&str],
+) -> Result<Vec<(String, Option<AttrValue>)>> {
+    let mut extra = vec![];
+    for attribute in attributes {
+        if known.contains(&attribute.name) || CONDITION_ATTRIBUTES.contains(&attribute.name) {
+            continue;
+        }
+        let value = match &attribute.value {
+            None => None,
+            Some(AttributeValue { text, kind: TokenKind::String, .. }) => {
+                Some(AttrValue::String(text.clone()))
+            }
+            Some(AttributeValue { text, kind: TokenKind::Integer, .. }) => {
+                let magnitude = text.strip_prefix('-').unwrap_or(text);
+                Some(match parse_integer(magnitude) {
+                    Some(value) if text.starts_with('-') => AttrValue::Integer(-i128::from(value)),
+                    Some(value) => AttrValue::Integer(value.into()),
+                    // Words joined by `-`, like a UUID with a leading digit.
+                    None => AttrValue::Identifier(text.clone()),
+                })
+            }
+            Some(AttributeValue { text, kind: TokenKind::Identifier, .. }) => {
+                Some(AttrValue::Identifier(text.clone()))
+            }
+            Some(value) => bail!(
+                "Attribute `{}` must be an identifier, string or integer, but found `{}` at \
+                 byte {}",
+                attribute.name,
+                value.text,
+                value.start
+            ),
+        };
+        extra.push((attribute.name.to_string(), value));
+    }
+    Ok(extra)
+}
+
+/// Interpret the `[MinVersion=N]` attribute, if present.
+fn min_version(attributes: &[Attribute]) -> Result<Option<u32>> {
+    let Some(attribute) = attributes.iter().find(|attribute| attribute.name == "MinVersion") else {
+        return Ok(None);
+    };
+    let Some(value) = &attribute.value else { bail!("MinVersion attribute requires a value") };
+    match parse_integer(&value.text).and_then(|version| u32::try_from(version).ok()) {
+        Some(version) => Ok(Some(version)),
+        None => Err(ParseError {
+            message: format!(
+                "MinVersion must be a non-negative integer, but found `{}`",
+                value.text
+            ),
+            span: Span { start: value.start, end: value.end },
+        }
+        .into()),
+    }
+}
+
+/// Interpret the `[Uuid=...]` attribute, if present, in the canonical form
+/// `01234567-89ab-cdef-0123-456789abcdef`.
+fn uuid(attributes: &[Attribute]) -> Result<Option<u128>> {
+    let Some(attribute) = attributes.iter().find(|attribute| attribute.name == "Uuid") else {
+        return Ok(None);
+    };
+    let Some(value) = &attribute.value else { bail!("Uuid attribute requires a value") };
+    let is_canonical = value.text.len() == 36
+        && value.text.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        });
+    if !is_canonical {
+        return Err(ParseError {
+            message: format!(
+                "Uuid must be of the form 01234567-89ab-cdef-0123-456789abcdef, but found `{}`",
+                value.text
+            ),
+            span: Span { start: value.start, end: value.end },
+        }
+        .into());
+    }
+    Ok(Some(u128::from_str_radix(&value.text.replace('-', ""), 16).unwrap()))
+}
+
+/// The state of the parser: a list of tokens, and how far we've gotten.
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    /// The length of the source, which is where a span at the end starts.
+    source_len: usize,
+    /// If set, declarations and members whose FeatureCondition doesn't hold
+    /// for these features are dropped. Otherwise everything is kept.
+    enabled_features: Option<&'a HashSet<String>>,
+    /// How many types enclose the one being parsed, e.g. 1 for the `int32` in
+    /// `array<int32>`, or likewise how many values enclose a value.
+    depth: usize,
+    /// The largest `depth` allowed.
+    max_depth: usize,
+}
+
+/// The default for how many types may enclose another, e.g. in
+/// `array<array<int32>>`, or values another. This applies to every type with
+/// type parameters, including maps and interface endpoints, and to values in
+/// parentheses, unary operators, array literals and chains of binary
+/// operators.
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 32;
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Parser<'a>> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            source_len: source.len(),
+            enabled_features: None,
+            depth: 0,
+            max_depth: DEFAULT_MAX_NESTING_DEPTH,
+        })
+    }
+
+    /// Whether to keep something with the given condition.
+    fn is_enabled(&self, condition: &Option<FeatureCondition>) -> bool {
+        match (self.enabled_features, condition) {
+            (Some(features), Some(FeatureCondition::EnableIf(feature))) => {
+                features.contains(feature)
+            }
+            (Some(features), Some(FeatureCondition::EnableIfNot(feature))) => {
+                !features.contains(feature)
+            }
+            _ => true,
+        }
+    }
+
+    /// The offset of the next token, for starting a span.
+    fn next_start(&self) -> usize {
+        self.peek().map_or(self.source_len, |token| token.start)
+    }
+
+    /// The span from `start` to the end of the last token consumed.
+    fn span_from(&self, start: usize) -> Span {
+        let end = match self.pos.checked_sub(1).map(|pos| &self.tokens[pos]) {
+            Some(token) => token.start + token.text.len(),
+            None => start,
+        };
+        Span { start, end: end.max(start) }
+    }
+
+    /// The doc comment directly above the token starting at `start`, without
+    /// the `//` markers.
+    fn doc_comment(&self, start: usize) -> Option<String> {
+        let index = self.tokens.binary_search_by_key(&start, |token| token.start).ok()?;
+        let comment = self.tokens[index].leading_comment?;
+        let lines: Vec<&str> = comment
+            .lines()
+            .map(|line| {
+                let line = line.trim_start();
+                let line = line.strip_prefix("//").unwrap_or(line);
+                line.strip_prefix(' ').unwrap_or(line).trim_end()
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    /// Look at the next token without consuming it.
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Consume and return the next token, which must exist.
+    fn next(&mut self, context: &str) -> Result<Token<'a>> {
+        let Some(token) = self.tokens.get(self.pos) else {
+            return Err(ParseError {
+                message: format!("Unexpected end of input while parsing {}", context),
+                span: Span { start: self.source_len, end: self.source_len },
+            }
+            .into());
+        };
+        self.pos += 1;
+        Ok(*token)
+    }
+
+    /// Consume the next token if it is the punctuation `punct`.
+    fn eat(&mut self, punct: &str) -> bool {
+        let is_match = self.peek_is(punct);
+        if is_match {
+            self.pos += 1;
+        }
+        is_match
+    }
+
+    /// Consume the next token, which must be the punctuation `punct`.
+    fn expect(&mut self, punct: &str, context: &str) -> Result<()> {
+        let token = self.next(context)?;
+        if token.kind != TokenKind::Punct || token.text != punct {
+            bail!("Expected `{}` while parsing {}, but found `{}`", punct, context, token.text)
+        }
+        Ok(())
+    }
+
+    /// Whether the next token is the punctuation `punct`.
+    fn peek_is(&self, punct: &str) -> bool {
+        self.peek().is_some_and(|token| token.kind == TokenKind::Punct && token.text == punct)
+    }
+
+    /// Consume the next token, which must be an identifier, and return its text.
+    fn expect_identifier(&mut self, context: &str) -> Result<&'a str> {
+        let token = self.next(context)?;
+        if token.kind != TokenKind::Identifier {
+            bail!("Expected an identifier while parsing {}, but found `{}`", context, token.text)
+        }
+        Ok(token.text)
+    }
+
+    /// Consume the name of a new declaration or member, which must be an
+    /// identifier other than a reserved word, and return it.
+    fn expect_declared_name(&mut self, context: &str) -> Result<&'a str> {
+        let name = self.expect_identifier(context)?;
+        if KEYWORDS.contains(&name) {
+            bail!(
+                "Expected a name while parsing {}, but found the reserved word `{}` at byte {}",
+                context,
+                name,
+                self.tokens[self.pos - 1].start
+            )
+        }
+        Ok(name)
+    }
+
+    /// Fail if there are any tokens left.
+    fn expect_end(&self) -> Result<()> {
+        match self.peek() {
+            Some(token) => bail!("Unexpected trailing input `{}`", token.text),
+            None => Ok(()),
+        }
+    }
+
+    /// Parse a possibly-qualified name, e.g. `Foo` or `foo.mojom.Foo`.
+    fn parse_name(&mut self, context: &str) -> Result<String> {
+        let mut name = self.expect_identifier(context)?.to_string();
+        while self.eat(".") {
+            name.push('.');
+            name.push_str(self.expect_identifier(context)?);
+        }
+        Ok(name)
+    }
+
+    /// Run `parse` one level deeper, where `what` names the things being
+    /// nested for the error if that's too deep.
+    ///
+    /// Types and values are parsed recursively, so bound the nesting depth to
+    /// keep adversarial input from overflowing the stack.
+    fn nested<T>(&mut self, what: &str, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.check_depth(what)?;
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Fail if going one level deeper would pass `max_depth`.
+    fn check_depth(&self, what: &str) -> Result<()> {
+        if self.depth > self.max_depth {
+            bail!(
+                "{} may be nested at most {} deep, but found a deeper one at byte {}",
+                what,
+                self.max_depth,
+                self.next_start()
+            )
+        }
+        Ok(())
+    }
+
+    /// Parse a type, including a trailing `?` if present.
+    fn parse_type(&mut self) -> Result<Type> {
+        self.nested("Types", Self::parse_type_inner)
+    }
+
+    fn parse_type_inner(&mut self) -> Result<Type> {
+        let start = self.next_start();
+        let name = self.parse_name("a type")?;
+        let kind = match name.as_str() {
+            "bool" => TypeKind::Bool,
+            "int8" => TypeKind::Int8,
+            "uint8" => TypeKind::UInt8,
+            "int16" => TypeKind::Int16,
+            "uint16" => TypeKind::UInt16,
+            "int32" => TypeKind::Int32,
+            "uint32" => TypeKind::UInt32,
+            "int64" => TypeKind::Int64,
+            "uint64" => TypeKind::UInt64,
+            "float" => TypeKind::Float,
+            "double" => TypeKind::Double,
+            "string" => TypeKind::String,
+            "array" => {
+                self.expect("<", "an array type")?;
+                let element_type = self.parse_type()?;
+                let length = if self.eat(",") { Some(self.parse_array_length()?) } else { None };
+                self.expect(">", "an array type")?;
+                TypeKind::Array { element_type: Box::new(element_type), length }
+            }
+            "map" => {
+                self.expect("<", "a map type")?;
+                let key_start = self.next_start();
+                let key = self.parse_type()?;
+                let key_is_valid = !key.nullable
+                    && match key.kind {
+                        TypeKind::Map { .. }
+                        | TypeKind::Array { .. }
+                        | TypeKind::PendingRemote(_)
+                        | TypeKind::PendingReceiver(_)
+                        | TypeKind::PendingAssociatedRemote(_)
+                        | TypeKind::PendingAssociatedReceiver(_)
+                        | TypeKind::Handle(_) => false,
+                        // Named types should be enums, but we can't check that
+                        // without knowing what the name refers to.
+                        _ => true,
+                    };
+                if !key_is_valid {
+                    bail!(
+                        "Map keys must be non-nullable primitives, strings or enums, but found \
+                         `{}` at byte {}",
+                        crate::deparse_source::deparse_type(&key),
+                        key_start
+                    )
+                }
+                self.expect(",", "a map type")?;
+                let value = self.parse_type()?;
+                self.expect(">", "a map type")?;
+                TypeKind::Map { key: Box::new(key), value: Box::new(value) }
+            }
+            "pending_remote" => TypeKind::PendingRemote(self.parse_endpoint_interface()?),
+            "pending_receiver" => TypeKind::PendingReceiver(self.parse_endpoint_interface()?),
+            "pending_associated_remote" => {
+                TypeKind::PendingAssociatedRemote(self.parse_endpoint_interface()?)
+            }
+            "pending_associated_receiver" => {
+                TypeKind::PendingAssociatedReceiver(self.parse_endpoint_interface()?)
+            }
+            "handle" if self.eat("<") => {
+                let token = self.next("a handle type")?;
+                let Some((kind, _)) =
+                    HandleKind::NAMED.into_iter().find(|(_, name)| *name == token.text)
+                else {
+                    bail!("Unknown handle type `{}` at byte {}", token.text, token.start)
+                };
+                self.expect(">", "a handle type")?;
+                TypeKind::Handle(kind)
+            }
+            "handle" => TypeKind::Handle(HandleKind::Untyped),
+            _ => {
+                if let Some(word) = name.split('.').find(|part| KEYWORDS.contains(part)) {
+                    bail!("Expected a type, but found the reserved word `{}` in `{}`", word, name)
+                }
+                TypeKind::Named(name)
+            }
+        };
+        let nullable = self.eat("?");
+        if nullable && !kind.can_be_nullable() {
+            bail!(
+                "Type `{}` cannot be nullable, since it is stored inline on the wire",
+                crate::deparse_source::deparse_type(&Type {
+                    kind,
+                    nullable: false,
+                    span: Span::default()
+                })
+            )
+        }
+        Ok(Type { kind, nullable, span: self.span_from(start) })
+    }
+
+    /// Parse the N in `array<T, N>`. This must be a literal rather than an
+    /// expression, since `>>` would otherwise be read as a shift.
+    fn parse_array_length(&mut self) -> Result<u32> {
+        let token = self.next("an array length")?;
+        if token.kind != TokenKind::Integer {
+            bail!("Expected an array length, but found `{}` at byte {}", token.text, token.start)
+        }
+        match parse_integer(token.text).and_then(|length| u32::try_from(length).ok()) {
+            Some(0) => bail!("Array length must be greater than zero at byte {}", token.start),
+            Some(length) => Ok(length),
+            None => bail!("Invalid array length `{}` at byte {}", token.text, token.start),
+        }
+    }
+
+    /// Parse an explicit ordinal like the `@2` in `int32 x@2` or `Foo@2()`, if
+    /// there is one.
+    fn parse_ordinal(&mut self) -> Result<Option<u32>> {
+        if !self.eat("@") {
+            return Ok(None);
+        }
+        let token = self.next("an ordinal")?;
+        if token.kind != TokenKind::Integer {
+            bail!(
+                "Expected an ordinal after `@`, but found `{}` at byte {}",
+                token.text,
+                token.start
+            )
+        }
+        match parse_integer(token.text).and_then(|ordinal| u32::try_from(ordinal).ok()) {
+            Some(ordinal) => Ok(Some(ordinal)),
+            None => bail!("Invalid ordinal `{}` at byte {}", token.text, token.start),
+        }
+    }
+
+    /// Parse the `<Foo>` in a type like `pending_remote<Foo>`, returning the
+    /// interface name.
+    fn parse_endpoint_interface(&mut self) -> Result<String> {
+        self.expect("<", "an interface endpoint type")?;
+        let interface = self.parse_name("an interface name")?;
+        self.expect(">", "an interface endpoint type")?;
+        Ok(interface)
+    }
+
+    /// Parse an attribute list like `[Sync, MinVersion=2]`, if there is one.
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute<'a>>> {
+        let mut attributes: Vec<Attribute> = vec![];
+        if !self.eat("[") {
+            return Ok(attributes);
+        }
+        loop {
+            let name = self.expect_identifier("an attribute list")?;
+            if attributes.iter().any(|attribute| attribute.name == name) {
+                bail!("Duplicate attribute `{}`", name)
+            }
+            let value = if self.eat("=") { Some(self.parse_attribute_value()?) } else { None };
+            attributes.push(Attribute { name, value });
+            if self.eat("]") {
+                return Ok(attributes);
+            }
+            self.expect(",", "an attribute list")?;
+        }
+    }
+
+    /// Parse the value of an attribute, i.e. the part after the `=`.
+    fn parse_attribute_value(&mut self) -> Result<AttributeValue> {
+        let context = "an attribute value";
+        let Some(&first) = self.peek() else {
+            bail!("Unexpected end of input while parsing {}", context)
+        };
+        let mut kind = first.kind;
+        let mut text = match first.kind {
+            TokenKind::Identifier => self.parse_name(context)?,
+            TokenKind::Integer | TokenKind::Float => self.next(context)?.text.to_string(),
+            TokenKind::String => parse_string_token(&self.next(context)?)?,
+            TokenKind::Punct if first.text == "-" => {
+                self.pos += 1;
+                let token = self.next(context)?;
+                if token.kind != TokenKind::Integer {
+                    bail!("Expected an integer after `-`, but found `{}`", token.text)
+                }
+                kind = TokenKind::Integer;
+                format!("-{}", token.text)
+            }
+            // Punctuation, since tokenize doesn't produce the other kinds.
+            _ => {
+                // Consume the bad token, so that it's the one an error points to.
+                self.pos += 1;
+                bail!("Expected {}, but found `{}` at byte {}", context, first.text, first.start)
+            }
+        };
+        // Words joined by `-` with no spaces between, as in a UUID, make up a
+        // single value.
+        if matches!(first.kind, TokenKind::Identifier | TokenKind::Integer) {
+            while let [dash, word, ..] = &self.tokens[self.pos..] {
+                let end = self.span_from(first.start).end;
+                let is_word = matches!(word.kind, TokenKind::Identifier | TokenKind::Integer);
+                if dash.text != "-" || dash.start != end || word.start != end + 1 || !is_word {
+                    break;
+                }
+                text = format!("{}-{}", text, word.text);
+                self.pos += 2;
+            }
+        }
+        Ok(AttributeValue { text, kind, start: first.start, end: self.span_from(first.start).end })
+    }
+
+    /// Parse an expression giving the value of an enum value or constant.
+    fn parse_value_expr(&mut self) -> Result<ValueExpr> {
+        self.parse_binary_expr(0)
+    }
+
+    /// If the next tokens are a binary operator, return it along with the
+    /// number of tokens it takes up.
+    fn peek_binary_op(&self) -> Option<(BinaryOp, usize)> {
+        let token = self.peek().filter(|token| token.kind == TokenKind::Punct)?;
+        // `<<` and `>>` are two tokens, which must be adjacent.
+        let doubled = self
+            .tokens
+            .get(self.pos + 1)
+            .is_some_and(|next| next.text == token.text && next.start == token.start + 1);
+        let op = match token.text {
+            "|" => BinaryOp::Or,
+            "^" => BinaryOp::Xor,
+            "&" => BinaryOp::And,
+            "<" if doubled => return Some((BinaryOp::ShiftLeft, 2)),
+            ">" if doubled => return Some((BinaryOp::ShiftRight, 2)),
+            "+" => BinaryOp::Add,
+            "-" => BinaryOp::Subtract,
+            "*" => BinaryOp::Multiply,
+            "/" => BinaryOp::Divide,
+            "%" => BinaryOp::Remainder,
+            _ => return None,
+        };
+        Some((op, 1))
+    }
+
+    /// Parse an expression containing only operators that bind at least as
+    /// tightly as `min_precedence`. All binary operators are left-associative.
+    fn parse_binary_expr(&mut self, min_precedence: u8) -> Result<ValueExpr> {
+        // Each operator folded into `lhs` nests it one deeper, so the chain
+        // counts against the depth until it's done, like nested values do.
+        let depth = self.depth;
+        let result = self.parse_operator_chain(min_precedence);
+        self.depth = depth;
+        result
+    }
+
+    fn parse_operator_chain(&mut self, min_precedence: u8) -> Result<ValueExpr> {
+        let start = self.next_start();
+        let mut lhs = self.parse_unary_expr()?;
+        while let Some((op, len)) = self.peek_binary_op() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += len;
+            self.check_depth("Values")?;
+            self.depth += 1;
+            let rhs = self.parse_binary_expr(op.precedence() + 1)?;
+            let kind = ValueExprKind::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) };
+            lhs = ValueExpr { kind, span: self.span_from(start) };
+        }
+        Ok(lhs)
+    }
+
+    /// Parse a literal, name, parenthesized expression, array literal, or unary
+    /// operator applied to one of those.
+    ///
+    /// A `-` before a literal is folded into the literal. Since literals are
+    /// parsed into an i128, this works even for the most negative int64.
+    fn parse_unary_expr(&mut self) -> Result<ValueExpr> {
+        let start = self.next_start();
+        let kind = self.nested("Values", Self::parse_unary_expr_inner)?;
+        Ok(ValueExpr { kind, span: self.span_from(start) })
+    }
+
+    fn parse_unary_expr_inner(&mut self) -> Result<ValueExprKind> {
+        let context = "a value";
+        let Some(&token) = self.peek() else {
+            bail!("Unexpected end of input while parsing {}", context)
+        };
+        match token.kind {
+            TokenKind::Integer => {
+                self.pos += 1;
+                Ok(ValueExprKind::Integer(parse_integer_token(&token)?))
+            }
+            TokenKind::Float => {
+                self.pos += 1;
+                match token.text.parse::<f64>() {
+                    Ok(value) if value.is_finite() => Ok(ValueExprKind::Float(value)),
+                    _ => bail!("Invalid float literal `{}` at byte {}", token.text, token.start),
+                }
+            }
+            TokenKind::String => {
+                self.pos += 1;
+                Ok(ValueExprKind::String(parse_string_token(&token)?))
+            }
+            TokenKind::Identifier => Ok(ValueExprKind::Name(self.parse_name(context)?)),
+            TokenKind::Punct if token.text == "+" => {
+                // A leading `+` is allowed, but does nothing.
+                self.pos += 1;
+                Ok(self.parse_unary_expr()?.kind)
+            }
+            TokenKind::Punct if token.text == "(" => {
+                self.pos += 1;
+                let expr = self.parse_value_expr()?;
+                self.expect(")", "a parenthesized value")?;
+                Ok(expr.kind)
+            }
+            TokenKind::Punct if token.text == "[" => {
+                self.pos += 1;
+                let mut elements = vec![];
+                while !self.eat("]") {
+                    if !elements.is_empty() {
+                        self.expect(",", "an array literal")?;
+                    }
+                    elements.push(self.parse_value_expr()?);
+                }
+                Ok(ValueExprKind::Array(elements))
+            }
+            TokenKind::Punct if token.text == "-" || token.text == "~" => {
+                self.pos += 1;
+                let operand = self.parse_unary_expr()?;
+                Ok(match (token.text, &operand.kind) {
+                    ("-", ValueExprKind::Integer(value)) => ValueExprKind::Integer(-value),
+                    ("-", ValueExprKind::Float(value)) => ValueExprKind::Float(-value),
+                    ("-", _) => {
+                        ValueExprKind::Unary { op: UnaryOp::Negate, operand: Box::new(operand) }
+                    }
+                    _ => ValueExprKind::Unary { op: UnaryOp::Not, operand: Box::new(operand) },
+                })
+            }
+            // Punctuation, since tokenize doesn't produce the other kinds.
+            _ => {
+                // Consume the bad token, so that it's the one an error points to.
+                self.pos += 1;
+                bail!("Expected {}, but found `{}` at byte {}", context, token.text, token.start)
+            }
+        }
+    }
+
+    /// Parse the body of a constant, after the `const` keyword.
+    fn parse_const(&mut self, start: usize, attributes: &[Attribute]) -> Result<Const> {
+        let ty = self.parse_type()?;
+        let name = self.expect_declared_name("a constant name")?.to_string();
+        self.expect("=", "a constant")?;
+        let value = self.parse_value_expr()?;
+        let condition = feature_condition(attributes)?;
+        let extra_attributes = extra_attributes(attributes, &[])?;
+        Ok(Const { name, span: self.span_from(start), condition, ty, value, extra_attributes })
+    }
+
+    /// Parse a struct field or method parameter, not including the terminating
+    /// `;` or `,`. Only struct fields may have a default, but that's left to
+    /// the callers to check.
+    fn parse_field(&mut self, context: &str) -> Result<Field> {
+        let start = self.next_start();
+        let attributes = self.parse_attributes()?;
+        let ty = self.parse_type()?;
+        let name = self.expect_declared_name(context)?.to_string();
+        let ordinal = self.parse_ordinal()?;
+        let default = if self.eat("=") { Some(self.parse_value_expr()?) } else { None };
+        Ok(Field {
+            name,
+            span: self.span_from(start),
+            doc: self.doc_comment(start),
+            condition: feature_condition(&attributes)?,
+            ty,
+            ordinal,
+            min_version: min_version(&attributes)?,
+            default,
+            extra_attributes: extra_attributes(&attributes, &["MinVersion"])?,
+        })
+    }
+
+    /// Parse struct or union fields up to and including the closing `}`,
+    /// dropping any that aren't enabled.
+    fn parse_fields(&mut self, context: &str) -> Result<Vec<Field>> {
+        let mut fields = vec![];
+        while !self.eat("}") {
+            let field = self.parse_field(context)?;
+            self.expect(";", context)?;
+            if self.is_enabled(&field.condition) {
+                fields.push(field);
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Parse the body of a struct, after the `struct` keyword. `[Native]`
+    /// structs have no body, so they end right after the name.
+    fn parse_struct(&mut self, start: usize, attributes: &[Attribute]) -> Result<Struct> {
+        let name = self.expect_declared_name("a struct name")?.to_string();
+        let is_native = flag_attribute(attributes, "Native")?;
+        let fields = match (is_native, self.peek_is(";")) {
+            (true, true) => vec![],
+            (true, false) => bail!("Native struct `{}` must not have a body", name),
+            (false, true) => bail!("Struct `{}` must have a body unless it is Native", name),
+            (false, false) => {
+                self.expect("{", "a struct")?;
+                self.parse_fields("a struct field")?
+            }
+        };
+        let condition = feature_condition(attributes)?;
+        let doc = self.doc_comment(start);
+        Ok(Struct {
+            name,
+            span: self.span_from(start),
+            doc,
+            condition,
+            fields,
+            is_native,
+            extra_attributes: extra_attributes(attributes, &["Native"])?,
+        })
+    }
+
+    /// Parse the body of a union, after the `union` keyword.
+    fn parse_union(&mut self, start: usize, attributes: &[Attribute]) -> Result<Union> {
+        let name = self.expect_declared_name("a union name")?.to_string();
+        self.expect("{", "a union")?;
+        let fields = self.parse_fields("a union field")?;
+        if fields.is_empty() {
+            bail!("Union `{}` must have at least one field", name)
+        }
+        if let Some(field) = fields.iter().find(|field| field.default.is_some()) {
+            bail!("Field `{}` of union `{}` must not have a default value", field.name, name)
+        }
+        let condition = feature_condition(attributes)?;
+        let extra_attributes = extra_attributes(attributes, &[])?;
+        Ok(Union { name, span: self.span_from(start), condition, fields, extra_attributes })
+    }
+
+    /// Parse a parenthesized list of method parameters.
+    fn parse_parameters(&mut self) -> Result<Vec<Field>> {
+        let mut parameters = vec![];
+        self.expect("(", "a parameter list")?;
+        if self.eat(")") {
+            return Ok(parameters);
+        }
+        loop {
+            let parameter = self.parse_field("a method parameter")?;
+            if parameter.default.is_some() {
+                bail!("Parameter `{}` must not have a default value", parameter.name)
+            }
+            if self.is_enabled(&parameter.condition) {
+                parameters.push(parameter);
+            }
+            if self.eat(")") {
+                return Ok(parameters);
+            }
+            self.expect(",", "a parameter list")?;
+        }
+    }
+
+    /// Parse a method declaration, whose attributes have already been parsed
+    /// starting at `start`.
+    fn parse_method(&mut self, start: usize, attributes: &[Attribute]) -> Result<Method> {
+        let name = self.expect_declared_name("a method name")?.to_string();
+        let ordinal = self.parse_ordinal()?;
+        let parameters = self.parse_parameters()?;
+        let response_parameters =
+            if self.eat("=>") { Some(self.parse_parameters()?) } else { None };
+        let span = self.span_from(start);
+        self.expect(";", "a method")?;
+        let is_sync = flag_attribute(attributes, "Sync")?;
+        if is_sync && response_parameters.is_none() {
+            bail!("Sync method `{}` must have a response", name)
+        }
+        Ok(Method {
+            name,
+            span,
+            doc: self.doc_comment(start),
+            condition: feature_condition(attributes)?,
+            ordinal,
+            parameters,
+            response_parameters,
+            min_version: min_version(attributes)?,
+            is_sync,
+            no_interrupt: flag_attribute(attributes, "NoInterrupt")?,
+            extra_attributes: extra_attributes(attributes, &["MinVersion", "Sync", "NoInterrupt"])?,
+        })
+    }
+
+    /// Parse the body of an interface, after the `interface` keyword.
+    fn parse_interface(&mut self, start: usize, attributes: &[Attribute]) -> Result<Interface> {
+        let name = self.expect_declared_name("an interface name")?.to_string();
+        let stable = flag_attribute(attributes, "Stable")?;
+        let uuid = uuid(attributes)?;
+        if uuid.is_some() && !stable {
+            bail!("Interface `{}` has a Uuid attribute, so it must also be Stable", name)
+        }
+        self.expect("{", "an interface")?;
+        let mut methods = vec![];
+        while !self.eat("}") {
+            let method_start = self.next_start();
+            let method_attributes = self.parse_attributes()?;
+            let method = self.parse_method(method_start, &method_attributes)?;
+            if self.is_enabled(&method.condition) {
+                methods.push(method);
+            }
+        }
+        let condition = feature_condition(attributes)?;
+        let doc = self.doc_comment(start);
+        Ok(Interface {
+            name,
+            span: self.span_from(start),
+            doc,
+            condition,
+            methods,
+            stable,
+            uuid,
+            extra_attributes: extra_attributes(attributes, &["Stable", "Uuid"])?,
+        })
+    }
+
+    /// Parse the body of an enum, after the `enum` keyword.
+    fn parse_enum(&mut self, start: usize, attributes: &[Attribute]) -> Result<Enum> {
+        let name = self.expect_declared_name("an enum name")?.to_string();
+        self.expect("{", "an enum")?;
+        let mut values = vec![];
+        // Enum values are separated by commas, with an optional trailing comma.
+        while !self.eat("}") {
+            let value_start = self.next_start();
+            let value_attributes = self.parse_attributes()?;
+            let name = self.expect_declared_name("an enum value")?.to_string();
+            let value = if self.eat("=") { Some(self.parse_value_expr()?) } else { None };
+            let enum_value = EnumValue {
+                name,
+                span: self.span_from(value_start),
+                condition: feature_condition(&value_attributes)?,
+                value,
+                min_version: min_version(&value_attributes)?,
+                is_default: flag_attribute(&value_attributes, "Default")?,
+                extra_attributes: extra_attributes(&value_attributes, &["MinVersion", "Default"])?,
+            };
+            if self.is_enabled(&enum_value.condition) {
+                values.push(enum_value);
+            }
+            if !self.peek_is("}") {
+                self.expect(",", "an enum")?;
+            }
+        }
+        Ok(Enum {
+            name,
+            span: self.span_from(start),
+            doc: self.doc_comment(start),
+            condition: feature_condition(attributes)?,
+            values,
+            extensible: flag_attribute(attributes, "Extensible")?,
+            extra_attributes: extra_attributes(attributes, &["Extensible"])?,
+        })
+    }
+
+    /// Parse the body of a feature, after the `feature` keyword. It must contain
+    /// exactly the `name` and `default_state` constants.
+    fn parse_feature(&mut self, start: usize, attributes: &[Attribute]) -> Result<Feature> {
+        let name = self.expect_declared_name("a feature name")?.to_string();
+        self.expect("{", "a feature")?;
+        let mut constants: Vec<Const> = vec![];
+        let requirement = "must have `const string name = \"...\"` and \
+                           `const bool default_state = true` or `false`";
+        while !self.eat("}") {
+            let const_start = self.next_start();
+            let const_attributes = self.parse_attributes()?;
+            let keyword = self.expect_identifier("a feature")?;
+            if keyword != "const" {
+                bail!("Expected a constant in feature `{}`, but found `{}`", name, keyword)
+            }
+            let constant = self.parse_const(const_start, &const_attributes)?;
+            self.expect(";", "a feature")?;
+            let is_valid = match (constant.name.as_str(), &constant.ty.kind, &constant.value.kind) {
+                ("name", TypeKind::String, ValueExprKind::String(_)) => true,
+                ("default_state", TypeKind::Bool, ValueExprKind::Name(value)) => {
+                    value == "true" || value == "false"
+                }
+                ("name" | "default_state", _, _) => false,
+                _ => bail!(
+                    "Unexpected constant `{}` in feature `{}`; only `name` and \
+                     `default_state` are allowed",
+                    constant.name,
+                    name
+                ),
+            };
+            if !is_valid {
+                bail!("Feature `{}` {}", name, requirement)
+            }
+            if constants.iter().any(|other| other.name == constant.name) {
+                bail!("Duplicate constant `{}` in feature `{}`", constant.name, name)
+            }
+            if self.is_enabled(&constant.condition) {
+                constants.push(constant);
+            }
+        }
+        let condition = feature_condition(attributes)?;
+        let extra_attributes = extra_attributes(attributes, &[])?;
+        let feature =
+            Feature { name, span: self.span_from(start), condition, constants, extra_attributes };
+        if feature.feature_name().is_none() || feature.default_state().is_none() {
+            bail!("Feature `{}` {}", feature.name, requirement)
+        }
+        Ok(feature)
+    }
+
+    /// Parse an import statement, after the `import` keyword.
+    fn parse_import(&mut self, start: usize) -> Result<Import> {
+        let token = self.next("an import")?;
+        if token.kind != TokenKind::String {
+            bail!("Expected a quoted path after `import`, but found `{}`", token.text)
+        }
+        let path = parse_string_token(&token)?;
+        let span = self.span_from(start);
+        self.expect(";", "an import")?;
+        Ok(Import { path, span })
+    }
+
+    /// Parse a module statement, import or declaration, whichever comes next,
+    /// and add it to `module`.
+    fn parse_top_level(&mut self, module: &mut Module) -> Result<()> {
+        if self.peek().is_some_and(|token| token.text == "module") {
+            self.pos += 1;
+            if module.name.is_some() {
+                bail!("A file may have only one `module` statement")
+            }
+            if !module.imports.is_empty() || !module.declarations.is_empty() {
+                bail!("The `module` statement must come before all imports and declarations")
+            }
+            let name = self.parse_name("a module name")?;
+            self.expect(";", "a module statement")?;
+            module.name = Some(name);
+        } else if self.peek().is_some_and(|token| token.text == "import") {
+            let start = self.next_start();
+            self.pos += 1;
+            if !module.declarations.is_empty() {
+                bail!("Imports must come before all declarations")
+            }
+            module.imports.push(self.parse_import(start)?);
+        } else {
+            let declaration = self.parse_declaration()?;
+            if self.is_enabled(declaration.condition()) {
+                module.declarations.push(declaration);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a single top-level declaration, including its terminating `;`.
+    fn parse_declaration(&mut self) -> Result<Declaration> {
+        // The span of a declaration includes its attributes. The `start` passed
+        // to each parse_* function below is where that span begins.
+        let start = self.next_start();
+        let attributes = self.parse_attributes()?;
+        let keyword = self.expect_identifier("a declaration")?;
+        let declaration = match keyword {
+            "struct" => Declaration::Struct(self.parse_struct(start, &attributes)?),
+            "union" => Declaration::Union(self.parse_union(start, &attributes)?),
+            "interface" => Declaration::Interface(self.parse_interface(start, &attributes)?),
+            "enum" => Declaration::Enum(self.parse_enum(start, &attributes)?),
+            "const" => Declaration::Const(self.parse_const(start, &attributes)?),
+            "feature" => Declaration::Feature(self.parse_feature(start, &attributes)?),
+            _ => bail!("Expected a declaration, but found `{}`", keyword),
+        };
+        self.expect(";", "a declaration")?;
+        Ok(declaration)
+    }
+
+    /// Turn an error from parsing into a ParseError. Errors are almost always
+    /// found just after consuming the offending token, so that's where the
+    /// error points unless it already says otherwise.
+    fn to_parse_error(&self, error: anyhow::Error) -> ParseError {
+        match error.downcast::<ParseError>() {
+            Ok(parse_error) => parse_error,
+            Err(error) => {
+                let token = &self.tokens[self.pos.saturating_sub(1)];
+                ParseError {
+                    message: error.to_string(),
+                    span: Span { start: token.start, end: token.start + token.text.len() },
+                }
+            }
+        }
+    }
+
+    /// After an error in the declaration starting at token `start_pos`, skip to
+    /// the end of that declaration: the next `;` or closing `}` outside of any
+    /// braces. A `;` directly after the `}` is skipped too.
+    fn skip_declaration(&mut self, start_pos: usize) {
+        self.pos = start_pos;
+        let mut depth = 0usize;
+        while let Ok(token) = self.next("a declaration") {
+            match token.text {
+                "{" if token.kind == TokenKind::Punct => depth += 1,
+                "}" if token.kind == TokenKind::Punct => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        self.eat(";");
+                        return;
+                    }
+                }
+                ";" if depth == 0 => return,
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Parse a single Mojom type, e.g. `array<string?>?`.
+pub fn parse_type(source: &str) -> Result<Type> {
+    let mut parser = Parser::new(source)?;
+    let ty = parser.parse_type()?;
+    parser.expect_end()?;
+    Ok(ty)
+}
+
+/// Parse exactly one top-level declaration, such as a struct or an interface,
+/// including its terminating `;`. This lets editors reparse just the
+/// declaration being edited, rather than the whole file.
+///
+/// Only comments and whitespace may follow the declaration. As with
+/// parse_module, `[EnableIf]` and `[EnableIfNot]` attributes are kept but not
+/// applied. The errors are in the same form as from parse_module_with_recovery,
+/// though parsing stops at the first one.
+pub fn parse_declaration(source: &str) -> std::result::Result<Declaration, Vec<ParseError>> {
+    let mut parser = Parser::new(source).map_err(|error| {
+        vec![error.downcast::<ParseError>().expect("Lexer errors are ParseErrors")]
+    })?;
+    let declaration =
+        parser.parse_declaration().map_err(|error| vec![parser.to_parse_error(error)])?;
+    if let Some(token) = parser.peek() {
+        return Err(vec![ParseError {
+            message: format!("Unexpected trailing input `{}` after the declaration", token.text),
+            span: Span { start: token.start, end: token.start + token.text.len() },
+        }]);
+    }
+    Ok(declaration)
+}
+
+/// Parse the contents of an entire .mojom file, after the parser has been set
+/// up.
+fn parse_module_with(mut parser: Parser) -> Result<Module> {
+    let mut module = Module::default();
+    while parser.peek().is_some() {
+        // Always return a ParseError, so that callers can find where the error
+        // is with ParseError::display.
+        parser.parse_top_level(&mut module).map_err(|error| parser.to_parse_error(error))?;
+    }
+    Ok(module)
+}
+
+/// Parse the contents of an entire .mojom file. Errors are ParseErrors.
+///
+/// Everything is kept regardless of any `[EnableIf]` or `[EnableIfNot]`
+/// attributes; use parse_module_with_features to apply them.
+pub fn parse_module(source: &str) -> Result<Module> {
+    parse_module_with(Parser::new(source)?)
+}
+
+/// Like parse_module, but allowing types and values to be nested
+/// `max_nesting_depth` deep rather than DEFAULT_MAX_NESTING_DEPTH.
+pub fn parse_module_with_max_nesting_depth(
+    source: &str,
+    max_nesting_depth: usize,
+) -> Result<Module> {
+    let mut parser = Parser::new(source)?;
+    parser.max_depth = max_nesting_depth;
+    parse_module_with(parser)
+}
+
+/// Parse the contents of an entire .mojom file, dropping declarations and
+/// members which aren't enabled for `enabled_features`. Those with
+/// `[EnableIf=feature]` are dropped unless `feature` is enabled, and those
+/// with `[EnableIfNot=feature]` are dropped if it is.
+///
+/// Dropped declarations and members must still be syntactically valid.
+pub fn parse_module_with_features(
+    source: &str,
+    enabled_features: &HashSet<String>,
+) -> Result<Module> {
+    let mut parser = Parser::new(source)?;
+    parser.enabled_features = Some(enabled_features);
+    parse_module_with(parser)
+}
+
+/// Parse the contents of an entire .mojom file, continuing past errors.
+///
+/// After a syntax error, the parser skips to the end of the declaration
+/// containing it and carries on with the next one. The returned module holds
+/// every declaration that parsed successfully, along with all the errors found
+/// in the others.
+pub fn parse_module_with_recovery(source: &str) -> (Module, Vec<ParseError>) {
+    let mut parser = match Parser::new(source) {
+        Ok(parser) => parser,
+        // Without tokens there's nothing to recover with.
+        Err(error) => {
+            let error = error.downcast::<ParseError>().expect("Lexer errors are ParseErrors");
+            return (Module::default(), vec![error]);
+        }
+    };
+    let mut module = Module::default();
+    let mut errors = vec![];
+    while parser.peek().is_some() {
+        let start_pos = parser.pos;
+        if let Err(error) = parser.parse_top_level(&mut module) {
+            errors.push(parser.to_parse_error(error));
+            parser.skip_declaration(start_pos);
+        }
+    }
+    (module, errors)
+}
@@ -0,0 +1,1138 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use crate::{unspanned, without_spans, without_type_spans, without_value_spans};
+use mojom_parser::*;
+
+use std::path::Path;
+
+#[gtest(MojomParserTest, NullableString)]
+fn test_nullable_string() {
+    let ty = parse_type("string?").unwrap();
+    expect_eq!(
+        ty,
+        Type { kind: TypeKind::String, nullable: true, span: Span { start: 0, end: 7 } }
+    );
+    expect_eq!(deparse_type(&ty), "string?");
+
+    let ty = parse_type("string").unwrap();
+    expect_eq!(
+        ty,
+        Type { kind: TypeKind::String, nullable: false, span: Span { start: 0, end: 6 } }
+    );
+    expect_eq!(deparse_type(&ty), "string");
+}
+
+#[gtest(MojomParserTest, NullableArray)]
+fn test_nullable_array() {
+    let ty = parse_type("array<int32>?").unwrap();
+    let expected_element = Type { kind: TypeKind::Int32, nullable: false, span: Span::default() };
+    expect_eq!(
+        without_type_spans(&ty),
+        Type {
+            kind: TypeKind::Array { element_type: Box::new(expected_element), length: None },
+            nullable: true,
+            span: Span::default()
+        }
+    );
+    expect_eq!(deparse_type(&ty), "array<int32>?");
+
+    // Nullability applies separately to the array and its elements.
+    for source in ["array<string?>", "array<string?>?", "array<array<Foo?>?>"] {
+        expect_eq!(deparse_type(&parse_type(source).unwrap()), source);
+    }
+}
+
+#[gtest(MojomParserTest, NullablePrimitiveRejected)]
+fn test_nullable_primitive_rejected() {
+    for source in ["int32?", "bool?", "uint8?", "double?", "array<int64?>"] {
+        let err = parse_type(source).unwrap_err().to_string();
+        expect_true!(err.contains("cannot be nullable"), "{}: {}", source, err);
+    }
+}
+
+#[gtest(MojomParserTest, TypeNestingDepth)]
+fn test_type_nesting_depth() {
+    let nested =
+        |depth: usize, open: &str| format!("{}int32{}", open.repeat(depth), ">".repeat(depth));
+    expect_true!(parse_type(&nested(DEFAULT_MAX_NESTING_DEPTH, "array<")).is_ok());
+    for open in ["array<", "map<string, "] {
+        let err = parse_type(&nested(DEFAULT_MAX_NESTING_DEPTH + 1, open)).unwrap_err().to_string();
+        expect_true!(err.contains("nested at most 32 deep"), "{}", err);
+    }
+    // Far past the limit, the error comes before the stack runs out.
+    expect_true!(parse_type(&nested(100_000, "array<")).is_err());
+
+    let source = format!("struct A {{ {} a; }};", nested(3, "array<"));
+    expect_true!(parse_module_with_max_nesting_depth(&source, 3).is_ok());
+    let err = parse_module_with_max_nesting_depth(&source, 2).unwrap_err();
+    let err = err.downcast::<ParseError>().unwrap();
+    expect_eq!(err.span.start, source.find("int32").unwrap() - 1);
+
+    // The depth is reset after an error, so later declarations still parse.
+    let source =
+        format!("struct A {{ {} a; }}; struct B {{ array<int32> b; }};", nested(40, "array<"));
+    let (module, errors) = parse_module_with_recovery(&source);
+    expect_eq!(errors.len(), 1);
+    expect_eq!(module.declarations.len(), 1);
+}
+
+#[gtest(MojomParserTest, ValueNestingDepth)]
+fn test_value_nesting_depth() {
+    let nested = |depth: usize, open: &str, close: &str| {
+        format!("const int32 kA = {}1{};", open.repeat(depth), close.repeat(depth))
+    };
+    expect_true!(parse_module(&nested(DEFAULT_MAX_NESTING_DEPTH - 1, "(", ")")).is_ok());
+    for (open, close) in [("(", ")"), ("-", ""), ("~", ""), ("[", "]")] {
+        let source = nested(DEFAULT_MAX_NESTING_DEPTH + 1, open, close);
+        let err = parse_module(&source).unwrap_err().to_string();
+        expect_true!(err.contains("nested at most 32 deep"), "{}", err);
+        // Far past the limit, the error comes before the stack runs out.
+        let source = nested(10_000, open, close);
+        let err = parse_module(&source).unwrap_err().downcast::<ParseError>().unwrap();
+        let deepest = source.find('1').unwrap();
+        expect_true!(err.span.start < deepest, "{:?}", err.span);
+    }
+
+    // A chain of left-associative operators nests its left side one deeper for
+    // each operator, so it counts against the same limit.
+    let chain = |terms: usize| format!("const int32 kA = {};", vec!["1"; terms].join(" + "));
+    expect_true!(parse_module(&chain(DEFAULT_MAX_NESTING_DEPTH + 1)).is_ok());
+    let err = parse_module(&chain(DEFAULT_MAX_NESTING_DEPTH + 2)).unwrap_err().to_string();
+    expect_true!(err.contains("nested at most 32 deep"), "{}", err);
+    expect_true!(parse_module(&chain(100_000)).is_err());
+}
+
+/// Return the only declaration in `module`, which must be a struct.
+fn only_struct(module: &Module) -> &Struct {
+    match module.declarations.as_slice() {
+        [Declaration::Struct(s)] => s,
+        _ => panic!("Expected a single struct, got {:?}", module.declarations),
+    }
+}
+
+#[gtest(MojomParserTest, MinVersionOnFields)]
+fn test_min_version_on_fields() {
+    let module = parse_module(
+        "struct Versioned {
+           int32 original;
+           [MinVersion=1] string? added_in_one;
+           bool also_original;
+           [MinVersion=3] array<uint8>? added_in_three;
+         };",
+    )
+    .unwrap();
+    let versions: Vec<(&str, Option<u32>)> = only_struct(&module)
+        .fields
+        .iter()
+        .map(|field| (field.name.as_str(), field.min_version))
+        .collect();
+    expect_eq!(
+        versions,
+        vec![
+            ("original", None),
+            ("added_in_one", Some(1)),
+            ("also_original", None),
+            ("added_in_three", Some(3)),
+        ]
+    );
+}
+
+#[gtest(MojomParserTest, MinVersionOnMethodsAndEnums)]
+fn test_min_version_on_methods_and_enums() {
+    let module = parse_module(
+        "interface Widget {
+           Frob(int32 a, [MinVersion=2] string? b);
+           [Sync, MinVersion=2] Query() => ([MinVersion=4] bool result);
+         };
+         enum Color { RED, [MinVersion=1] GREEN, };",
+    )
+    .unwrap();
+    let [Declaration::Interface(interface), Declaration::Enum(color)] =
+        module.declarations.as_slice()
+    else {
+        panic!("Unexpected declarations {:?}", module.declarations)
+    };
+
+    let frob = &interface.methods[0];
+    expect_eq!(frob.min_version, None);
+    expect_eq!(frob.parameters[0].min_version, None);
+    expect_eq!(frob.parameters[1].min_version, Some(2));
+    expect_eq!(frob.response_parameters, None);
+
+    let query = &interface.methods[1];
+    expect_eq!(query.min_version, Some(2));
+    expect_eq!(query.parameters, vec![]);
+    expect_eq!(query.response_parameters.as_ref().unwrap()[0].min_version, Some(4));
+
+    expect_eq!(color.values[0].min_version, None);
+    expect_eq!(color.values[1].min_version, Some(1));
+}
+
+#[gtest(MojomParserTest, MinVersionInvalid)]
+fn test_min_version_invalid() {
+    for (source, bad_value, offset) in [
+        ("struct S { [MinVersion=-1] int32 x; };", "-1", 23),
+        ("struct S { [MinVersion=Two] int32 x; };", "Two", 23),
+        ("struct S { [MinVersion=4294967296] int32 x; };", "4294967296", 23),
+        ("struct S { [MinVersion=abc] int32 a; };", "abc", 23),
+    ] {
+        let err = parse_module(source).unwrap_err().downcast::<ParseError>().unwrap();
+        expect_true!(err.message.contains(&format!("found `{}`", bad_value)), "{:?}", err);
+        expect_eq!(err.span.start, offset);
+        expect_eq!(err.span.text(source), bad_value);
+    }
+
+    let err = parse_module("struct S { [MinVersion] int32 x; };").unwrap_err().to_string();
+    expect_true!(err.contains("requires a value"), "{}", err);
+}
+
+#[gtest(MojomParserTest, Union)]
+fn test_union() {
+    let module = parse_module(
+        "union Value {
+           int32 number;
+           string? text;
+           [MinVersion=1] Nested nested;
+         };",
+    )
+    .unwrap();
+    let [Declaration::Union(value)] = module.declarations.as_slice() else {
+        panic!("Expected a single union, got {:?}", module.declarations)
+    };
+    expect_eq!(value.name, "Value");
+    let fields: Vec<(&str, String, Option<u32>)> = value
+        .fields
+        .iter()
+        .map(|field| (field.name.as_str(), deparse_type(&field.ty), field.min_version))
+        .collect();
+    expect_eq!(
+        fields,
+        vec![
+            ("number", "int32".to_string(), None),
+            ("text", "string?".to_string(), None),
+            ("nested", "Nested".to_string(), Some(1)),
+        ]
+    );
+}
+
+#[gtest(MojomParserTest, EmptyUnionRejected)]
+fn test_empty_union_rejected() {
+    let err = parse_module("union Nothing {};").unwrap_err().to_string();
+    expect_true!(err.contains("at least one field"), "{}", err);
+}
+
+#[gtest(MojomParserTest, ReservedWordsRejected)]
+fn test_reserved_words_rejected() {
+    for (source, word, context) in [
+        ("struct S { int32 interface; };", "interface", "a struct field"),
+        ("struct enum { int32 a; };", "enum", "a struct name"),
+        ("interface I { F(int32 handle); };", "handle", "a method parameter"),
+        ("interface I { array(); };", "array", "a method name"),
+        ("enum E { A, struct };", "struct", "an enum value"),
+        ("const int32 map = 1;", "map", "a constant name"),
+    ] {
+        let (_, errors) = parse_module_with_recovery(source);
+        expect_eq!(errors.len(), 1, "{}", source);
+        expect_eq!(
+            errors[0].message,
+            format!(
+                "Expected a name while parsing {}, but found the reserved word `{}` at byte {}",
+                context, word, errors[0].span.start
+            )
+        );
+        expect_eq!(errors[0].span.text(source), word);
+    }
+
+    let err = parse_module("struct S { union u; };").unwrap_err().to_string();
+    expect_eq!(err, "Expected a type, but found the reserved word `union` in `union`");
+    let err = parse_module("struct S { foo.const.Bar b; };").unwrap_err().to_string();
+    expect_eq!(err, "Expected a type, but found the reserved word `const` in `foo.const.Bar`");
+}
+
+#[gtest(MojomParserTest, ReservedWordsAsSubstrings)]
+fn test_reserved_words_as_substrings() {
+    // Only whole words are reserved.
+    let module = parse_module(
+        "struct interfaces { int32 structure; handles.Handle mapping; };
+         enum Enums { arrays, FALSE };
+         const bool default_state = true;",
+    )
+    .unwrap();
+    let Declaration::Struct(s) = &module.declarations[0] else { panic!() };
+    expect_eq!(s.name, "interfaces");
+    expect_eq!(field_names(s), vec!["structure", "mapping"]);
+    expect_eq!(s.fields[1].ty.kind, TypeKind::Named("handles.Handle".to_string()));
+    expect_eq!(module.declarations[1].name(), "Enums");
+}
+
+#[gtest(MojomParserTest, EmptyBodies)]
+fn test_empty_bodies() {
+    let module =
+        parse_module("interface Foo {}; struct Bar { }; interface Baz { Ping(); };").unwrap();
+    let [Declaration::Interface(foo), Declaration::Struct(bar), Declaration::Interface(baz)] =
+        module.declarations.as_slice()
+    else {
+        panic!("Unexpected declarations {:?}", module.declarations)
+    };
+    expect_true!(foo.methods.is_empty());
+    expect_true!(bar.fields.is_empty());
+    expect_eq!(
+        deparse_module(&module),
+        "interface Foo {\n};\n\nstruct Bar {\n};\n\ninterface Baz {\n  Ping();\n};\n"
+    );
+
+    // An empty struct, like the parameters of a method without any, is just
+    // the header.
+    expect_true!(pack_struct(bar).unwrap().is_empty());
+    expect_eq!(version_sizes(bar).unwrap(), vec![(0, STRUCT_HEADER_SIZE)]);
+    let params = baz.methods[0].request_struct();
+    expect_eq!(version_sizes(&params).unwrap(), vec![(0, STRUCT_HEADER_SIZE)]);
+}
+
+#[gtest(MojomParserTest, NativeStruct)]
+fn test_native_struct() {
+    let module = parse_module("[Native] struct Rect;").unwrap();
+    let s = only_struct(&module);
+    expect_true!(s.is_native);
+    expect_eq!(s.fields, vec![]);
+    expect_eq!(deparse_module(&module), "[Native] struct Rect;\n");
+    expect_false!(only_struct(&parse_module("struct Rect {};").unwrap()).is_native);
+}
+
+#[gtest(MojomParserTest, BodylessStructRejected)]
+fn test_bodyless_struct_rejected() {
+    let err = parse_module("struct Rect;").unwrap_err().to_string();
+    expect_true!(err.contains("must have a body unless it is Native"), "{}", err);
+    let err = parse_module("[Native] struct Rect { int32 x; };").unwrap_err().to_string();
+    expect_true!(err.contains("must not have a body"), "{}", err);
+}
+
+#[gtest(MojomParserTest, MethodOrdinals)]
+fn test_method_ordinals() {
+    let module = parse_module("interface I { A(); B@5(int32 x) => (); C(); };").unwrap();
+    let [Declaration::Interface(interface)] = module.declarations.as_slice() else {
+        panic!("Expected a single interface, got {:?}", module.declarations)
+    };
+    let ordinals: Vec<Option<u32>> =
+        interface.methods.iter().map(|method| method.ordinal).collect();
+    expect_eq!(ordinals, vec![None, Some(5), None]);
+    expect_eq!(assign_method_ordinals(interface).unwrap(), vec![0, 5, 6]);
+    expect_eq!(
+        deparse_module(&module),
+        "interface I {\n  A();\n  B@5(int32 x) => ();\n  C();\n};\n"
+    );
+    expect_true!(parse_module("interface I { A@(); };").is_err());
+}
+
+#[gtest(MojomParserTest, DuplicateMethodOrdinals)]
+fn test_duplicate_method_ordinals() {
+    let source = "interface I { A@1(); B@0(); C(); };";
+    let module = parse_module(source).unwrap();
+    let [Declaration::Interface(interface)] = module.declarations.as_slice() else {
+        panic!("Expected a single interface, got {:?}", module.declarations)
+    };
+    let err = assign_method_ordinals(interface).unwrap_err().downcast::<SemanticError>().unwrap();
+    expect_eq!(err.message, "Methods `A` and `C` of interface `I` both have ordinal 1");
+    expect_eq!(err.span.text(source), "C()");
+    expect_true!(interface_fingerprint(interface).is_err());
+}
+
+/// A file using most of what the parser supports, in no particular format.
+const REPRESENTATIVE_MOJOM: &str = "
+    module drawing.mojom;
+    const int32 kMax = 1 << 20;
+    const uint32 kMask = ~(kMax - 1) & (0xff | 0x100) ;
+    const int32 kNested = (1 - 2) - (3 - 4) * -kMax;
+    enum Color { RED, GREEN = 0x10, [MinVersion=1] BLUE = GREEN + 1 };
+    [Extensible] enum Mode { [Default] OFF, ON, };
+    struct Point { int32 x; int32 y; [MinVersion=2] array<string?>? labels; };
+    struct Index { map<string, int32> counts; map<Color, array<Point?>?>? by_color; };
+    struct Hash { array<uint8, 32> bytes@1; array<array<int8, 2>, 0x10>? nested@0; };
+    [EnableIf=is_win] struct WinOnly { [EnableIfNot=is_official, MinVersion=1] bool debug; };
+    union Shape { Point point; double radius; };
+    struct Empty {};
+    [Native] struct NativeThing;
+    feature kFancyDrawing {
+      const string name = \"FancyDrawing\";
+      const bool default_state = false;
+    };
+    // Something to draw on.
+    [Stable, Uuid=0badf00d-0000-4000-8000-00000000cafe]
+    interface Canvas {
+      Clear();
+      // Draws `shape`, replying once done.
+      //
+      // The color is ignored for now.
+      Draw(Shape shape, Color color) => (bool success);
+      [MinVersion=1] GetPoints() => (array<Point> points);
+      [Sync, NoInterrupt] GetSize() => (int32 width, int32 height);
+      Notify@7(string message) => ();
+      Bind(pending_receiver<Canvas> receiver, pending_remote<Canvas>? remote);
+      Share(handle<shared_buffer> buffer, handle? any, array<handle<platform>> files);
+    };";
+
+#[gtest(MojomParserTest, DeparseModuleRoundTrip)]
+fn test_deparse_module_round_trip() {
+    let module = parse_module(REPRESENTATIVE_MOJOM).unwrap();
+    let deparsed = deparse_module(&module);
+    let reparsed = parse_module(&deparsed).unwrap();
+    expect_eq!(without_spans(&reparsed), without_spans(&module));
+    expect_eq!(deparse_module(&reparsed), deparsed);
+}
+
+#[gtest(MojomParserTest, DeparseAttributes)]
+fn test_deparse_attributes() {
+    let module = parse_module(
+        "[Uuid=0BADF00D-0000-4000-8000-00000000CAFE, EnableIf=is_linux, Stable]
+         interface Widget {
+           [NoInterrupt, Sync, MinVersion=0x2, EnableIfNot=is_official]
+           Measure([MinVersion=2] int32 width@1, int32 height@0) => ([MinVersion=3] bool ok);
+           [EnableIf=is_linux] Poke@4();
+         };
+         [Extensible] enum Shape { [MinVersion=1] ROUND, [Default] SQUARE };
+         [EnableIf=is_linux] struct Size { [EnableIfNot=is_official, MinVersion=1] int32 w; };",
+    )
+    .unwrap();
+    let deparsed = deparse_module(&module);
+    // Attributes come out in a canonical order, with values in their canonical
+    // form: integers in decimal and UUIDs in lowercase.
+    expect_eq!(
+        deparsed,
+        "[EnableIf=is_linux, Stable, Uuid=0badf00d-0000-4000-8000-00000000cafe] \
+         interface Widget {\n\
+         \x20 [EnableIfNot=is_official, MinVersion=2, Sync, NoInterrupt] \
+         Measure([MinVersion=2] int32 width@1, int32 height@0) => ([MinVersion=3] bool ok);\n\
+         \x20 [EnableIf=is_linux] Poke@4();\n\
+         };\n\n\
+         [Extensible] enum Shape {\n  [MinVersion=1] ROUND,\n  [Default] SQUARE,\n};\n\n\
+         [EnableIf=is_linux] struct Size {\n  [EnableIfNot=is_official, MinVersion=1] int32 w;\n};\n"
+    );
+    let reparsed = parse_module(&deparsed).unwrap();
+    expect_eq!(without_spans(&reparsed), without_spans(&module));
+    expect_eq!(deparse_module(&reparsed), deparsed);
+}
+
+#[gtest(MojomParserTest, DeparseModuleFormat)]
+fn test_deparse_module_format() {
+    let module = parse_module(
+        "enum E{A,B=-1,};[MinVersion=1]struct S{[MinVersion=1]int32 a;};
+         interface I{F(int8 a,E e)=>();};const int64 k=(1+2)*3;",
+    )
+    .unwrap();
+    expect_eq!(
+        deparse_module(&module),
+        "enum E {\n  A,\n  B = -1,\n};\n\n\
+         [MinVersion=1] struct S {\n  [MinVersion=1] int32 a;\n};\n\n\
+         interface I {\n  F(int8 a, E e) => ();\n};\n\n\
+         const int64 k = (1 + 2) * 3;\n"
+    );
+}
+
+#[gtest(MojomParserTest, ModuleStatement)]
+fn test_module_statement() {
+    let module = parse_module("module foo.bar; import \"a.mojom\"; struct S {};").unwrap();
+    expect_eq!(module.name.as_deref(), Some("foo.bar"));
+    expect_eq!(
+        deparse_module(&module),
+        "module foo.bar;\n\nimport \"a.mojom\";\n\nstruct S {\n};\n"
+    );
+    expect_eq!(parse_module("struct S {};").unwrap().name, None);
+
+    // Errors point at the offending `module` keyword.
+    for (source, message) in [
+        ("module foo; module bar;", "only one `module` statement"),
+        ("struct S {}; module foo;", "must come before all imports and declarations"),
+        ("import \"a.mojom\"; module foo;", "must come before all imports and declarations"),
+    ] {
+        let err = parse_module(source).unwrap_err().downcast::<ParseError>().unwrap();
+        expect_true!(err.message.contains(message), "{}: {}", source, err);
+        let start = source.rfind("module").unwrap();
+        expect_eq!(err.span, Span { start, end: start + "module".len() }, "{}", source);
+    }
+}
+
+#[gtest(MojomParserTest, Spans)]
+fn test_spans() {
+    let source = "// A comment.
+struct Point {
+  int32 x;
+  [MinVersion=1]
+    array<int32>  labels ;
+};
+
+interface Canvas {
+  Draw(Point p) => ();
+};";
+    let module = parse_module(source).unwrap();
+    let [Declaration::Struct(point), Declaration::Interface(canvas)] =
+        module.declarations.as_slice()
+    else {
+        panic!("Unexpected declarations {:?}", module.declarations)
+    };
+    expect_eq!(point.fields[0].span.text(source), "int32 x");
+    expect_eq!(line_and_column(source, point.fields[0].span.start), (3, 3));
+    expect_eq!(point.fields[1].span.text(source), "[MinVersion=1]\n    array<int32>  labels");
+    expect_eq!(line_and_column(source, point.fields[1].span.end), (5, 25));
+    expect_true!(point.span.text(source).starts_with("struct Point {"));
+    expect_true!(point.span.text(source).ends_with("labels ;\n}"));
+    expect_eq!(canvas.methods[0].span.text(source), "Draw(Point p) => ()");
+    expect_eq!(line_and_column(source, canvas.methods[0].span.start), (9, 3));
+}
+
+#[gtest(MojomParserTest, TypeAndValueSpans)]
+fn test_type_and_value_spans() {
+    let source = "struct S { map<string, array<int32>?> m; int32 x = -(1 + kTwo) * 3; };";
+    let module = parse_module(source).unwrap();
+    let [m, x] = only_struct(&module).fields.as_slice() else { panic!() };
+    expect_eq!(m.ty.span.text(source), "map<string, array<int32>?>");
+    let TypeKind::Map { key, value } = &m.ty.kind else { panic!("Expected a map, got {:?}", m.ty) };
+    expect_eq!(key.span.text(source), "string");
+    expect_eq!(value.span.text(source), "array<int32>?");
+    expect_eq!(x.ty.span.text(source), "int32");
+
+    let default = x.default.as_ref().unwrap();
+    expect_eq!(default.span.text(source), "-(1 + kTwo) * 3");
+    let ValueExprKind::Binary { lhs, rhs, .. } = &default.kind else { panic!("{:?}", default) };
+    expect_eq!(lhs.span.text(source), "-(1 + kTwo)");
+    expect_eq!(rhs.span.text(source), "3");
+    let ValueExprKind::Unary { operand, .. } = &lhs.kind else { panic!("{:?}", lhs) };
+    // Parentheses are part of the span of the expression they wrap.
+    expect_eq!(operand.span.text(source), "(1 + kTwo)");
+}
+
+#[gtest(MojomParserTest, LineAndColumn)]
+fn test_line_and_column() {
+    let source = "ab\n\ncé\nd";
+    expect_eq!(line_and_column(source, 0), (1, 1));
+    expect_eq!(line_and_column(source, 2), (1, 3));
+    expect_eq!(line_and_column(source, 3), (2, 1));
+    expect_eq!(line_and_column(source, 4), (3, 1));
+    // `é` is two bytes, but one column.
+    expect_eq!(line_and_column(source, 7), (3, 3));
+    expect_eq!(line_and_column(source, 8), (4, 1));
+}
+
+#[gtest(MojomParserTest, RecoverFromErrors)]
+fn test_recover_from_errors() {
+    let source = "struct A { int32 x; };
+struct B { int32 missing_semicolon };
+enum C { X, Y };
+interface D { Method(int32 a,) => (); };
+const int32 kE = 5;";
+    let (module, errors) = parse_module_with_recovery(source);
+    let names: Vec<&str> = module
+        .declarations
+        .iter()
+        .map(|declaration| match declaration {
+            Declaration::Struct(s) => s.name.as_str(),
+            Declaration::Enum(e) => e.name.as_str(),
+            Declaration::Const(c) => c.name.as_str(),
+            _ => panic!("Unexpected declaration {:?}", declaration),
+        })
+        .collect();
+    expect_eq!(names, vec!["A", "C", "kE"]);
+
+    expect_eq!(errors.len(), 2);
+    expect_true!(errors[0].message.contains("Expected `;`"), "{}", errors[0]);
+    expect_eq!(errors[0].span.text(source), "}");
+    expect_eq!(line_and_column(source, errors[0].span.start), (2, 36));
+    expect_eq!(errors[1].span.text(source), ")");
+    expect_eq!(line_and_column(source, errors[1].span.start), (4, 30));
+
+    // Without recovery, only the first error is reported.
+    let err = parse_module(source).unwrap_err().to_string();
+    expect_eq!(err, errors[0].message);
+}
+
+#[gtest(MojomParserTest, RecoverFromUnterminatedDeclaration)]
+fn test_recover_from_unterminated_declaration() {
+    let source = "struct A { int32 x; }; struct B { int32 y;";
+    let (module, errors) = parse_module_with_recovery(source);
+    expect_eq!(module.declarations.len(), 1);
+    expect_eq!(errors.len(), 1);
+    expect_eq!(errors[0].span.start, source.len());
+
+    let (module, errors) = parse_module_with_recovery("struct A { int32 x; }; $");
+    expect_eq!(module.declarations.len(), 0);
+    expect_eq!(errors.len(), 1);
+    expect_eq!(errors[0].span.start, 23);
+}
+
+#[gtest(MojomParserTest, ParseDeclaration)]
+fn test_parse_declaration() {
+    // The same as in a whole module, spans and doc comments included.
+    let source = "  // A point.\n  struct Point { int32 x; int32 y; };  // Done.\n";
+    let declaration = parse_declaration(source).unwrap();
+    expect_eq!(parse_module(source).unwrap().declarations, vec![declaration.clone()]);
+    let Declaration::Struct(s) = &declaration else { panic!("Unexpected {:?}", declaration) };
+    expect_eq!(s.name, "Point");
+    expect_eq!(s.doc.as_deref(), Some("A point."));
+    expect_eq!(s.span.text(source), "struct Point { int32 x; int32 y; }");
+
+    let declaration =
+        parse_declaration("[Extensible] enum Color { RED, [Default] BLUE };").unwrap();
+    let Declaration::Enum(e) = &declaration else { panic!("Unexpected {:?}", declaration) };
+    expect_eq!(e.name, "Color");
+    expect_true!(e.extensible);
+    expect_eq!(e.values.len(), 2);
+}
+
+#[gtest(MojomParserTest, ParseDeclarationErrors)]
+fn test_parse_declaration_errors() {
+    let errors = |source: &str| -> Vec<(String, String)> {
+        let errors = parse_declaration(source).unwrap_err();
+        errors.iter().map(|error| (error.message.clone(), error.span.text(source).into())).collect()
+    };
+    let source = "struct A { int32 x; }; struct B {};";
+    expect_eq!(
+        errors(source),
+        [("Unexpected trailing input `struct` after the declaration".into(), "struct".into())]
+    );
+    expect_eq!(
+        errors("enum E { X }; ;"),
+        [("Unexpected trailing input `;` after the declaration".into(), ";".into())]
+    );
+    expect_eq!(
+        errors("struct A { int32 x }"),
+        [("Expected `;` while parsing a struct field, but found `}`".into(), "}".into())]
+    );
+    expect_eq!(errors("").len(), 1);
+    expect_eq!(errors("import \"a.mojom\";").len(), 1);
+    expect_eq!(errors("struct A { string s = \"\\q\"; };").len(), 1);
+}
+
+#[gtest(MojomParserTest, SyncMethods)]
+fn test_sync_methods() {
+    let module = parse_module(
+        "interface Blocking {
+           [Sync] Get() => (int32 value);
+           [NoInterrupt, Sync] GetNoInterrupt() => ();
+           Async() => (int32 value);
+         };",
+    )
+    .unwrap();
+    let [Declaration::Interface(interface)] = module.declarations.as_slice() else {
+        panic!("Unexpected declarations {:?}", module.declarations)
+    };
+    let flags: Vec<(&str, bool, bool)> = interface
+        .methods
+        .iter()
+        .map(|method| (method.name.as_str(), method.is_sync, method.no_interrupt))
+        .collect();
+    expect_eq!(
+        flags,
+        vec![("Get", true, false), ("GetNoInterrupt", true, true), ("Async", false, false)]
+    );
+    expect_true!(deparse_module(&module).contains("  [Sync, NoInterrupt] GetNoInterrupt() => ();"));
+}
+
+#[gtest(MojomParserTest, SyncMethodWithoutResponse)]
+fn test_sync_method_without_response() {
+    let err = parse_module("interface I { [Sync] Fire(int32 x); };").unwrap_err().to_string();
+    expect_true!(err.contains("Sync method `Fire` must have a response"), "{}", err);
+
+    let source = "interface I { [Sync=1] Get() => (); };";
+    let err = parse_module(source).unwrap_err().downcast::<ParseError>().unwrap();
+    expect_eq!(err.message, "Sync attribute does not take a value, but found `1`");
+    expect_eq!(err.span.start, source.find('1').unwrap());
+}
+
+#[gtest(MojomParserTest, OneWayAndEmptyResponses)]
+fn test_one_way_and_empty_responses() {
+    let module = parse_module(
+        "interface I {
+           Fire(int32 x);
+           Ack(int32 x) => ();
+           Get() => (int32 value);
+         };",
+    )
+    .unwrap();
+    let [Declaration::Interface(interface)] = module.declarations.as_slice() else {
+        panic!("Unexpected declarations {:?}", module.declarations)
+    };
+    let [fire, ack, get] = interface.methods.as_slice() else {
+        panic!("Unexpected methods {:?}", interface.methods)
+    };
+    // A one-way method has no response at all, which is different from an
+    // empty one: only the latter replies.
+    expect_eq!(fire.response_parameters, None);
+    expect_eq!(ack.response_parameters, Some(vec![]));
+    expect_eq!(get.response_parameters.as_ref().map(Vec::len), Some(1));
+    expect_eq!(fire.response_struct(), None);
+    expect_eq!(ack.response_struct().map(|response| response.fields), Some(vec![]));
+
+    let deparsed = deparse_module(&module);
+    expect_true!(deparsed.contains("  Fire(int32 x);\n"), "{}", deparsed);
+    expect_true!(deparsed.contains("  Ack(int32 x) => ();\n"), "{}", deparsed);
+    expect_eq!(without_spans(&parse_module(&deparsed).unwrap()), without_spans(&module));
+}
+
+#[gtest(MojomParserTest, ArraysAndFieldDefaults)]
+fn test_arrays_and_field_defaults() {
+    let module = parse_module("const array<int32> kDefaults = [1, -2, kThree];").unwrap();
+    let [Declaration::Const(constant)] = module.declarations.as_slice() else {
+        panic!("Unexpected declarations {:?}", module.declarations)
+    };
+    expect_eq!(
+        without_value_spans(&constant.value),
+        unspanned(ValueExprKind::Array(vec![
+            unspanned(ValueExprKind::Integer(1)),
+            unspanned(ValueExprKind::Integer(-2)),
+            unspanned(ValueExprKind::Name("kThree".to_string())),
+        ]))
+    );
+
+    let module = parse_module("struct S { int32 a = 5; int32 b@0; array<int8> c = []; };").unwrap();
+    let [a, b, c] = only_struct(&module).fields.as_slice() else { panic!() };
+    expect_eq!(a.default.as_ref().map(|value| &value.kind), Some(&ValueExprKind::Integer(5)));
+    expect_eq!(b.default, None);
+    expect_eq!(c.default.as_ref().map(|value| &value.kind), Some(&ValueExprKind::Array(vec![])));
+    let deparsed = deparse_module(&module);
+    expect_true!(
+        deparsed.contains(
+            "  int32 a = 5;
+"
+        ),
+        "{}",
+        deparsed
+    );
+    expect_true!(
+        deparsed.contains(
+            "  array<int8> c = [];
+"
+        ),
+        "{}",
+        deparsed
+    );
+    expect_eq!(without_spans(&parse_module(&deparsed).unwrap()), without_spans(&module));
+
+    for (source, message) in [
+        ("union U { int32 a = 5; };", "Field `a` of union `U` must not have a default value"),
+        ("interface I { F(int32 a = 5); };", "Parameter `a` must not have a default value"),
+        ("const array<int32> k = [1 2];", "Expected `,` while parsing an array literal"),
+    ] {
+        let err = parse_module(source).unwrap_err().to_string();
+        expect_true!(err.contains(message), "{}", err);
+    }
+}
+
+/// Return the names of the fields of `s`.
+fn field_names(s: &Struct) -> Vec<&str> {
+    s.fields.iter().map(|field| field.name.as_str()).collect()
+}
+
+#[gtest(MojomParserTest, EnableIf)]
+fn test_enable_if() {
+    let source = "struct Config {
+                    int32 always;
+                    [EnableIf=is_win] int32 win_only;
+                    [EnableIfNot=is_win] int32 not_win;
+                  };
+                  [EnableIf=is_android] interface AndroidOnly {};
+                  enum E { A, [EnableIf=is_win] B, C };";
+    let features = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+
+    let win = parse_module_with_features(source, &features(&["is_win"])).unwrap();
+    expect_eq!(win.declarations.len(), 2);
+    let Declaration::Struct(config) = &win.declarations[0] else { panic!() };
+    expect_eq!(field_names(config), vec!["always", "win_only"]);
+    expect_eq!(config.fields[1].condition, Some(FeatureCondition::EnableIf("is_win".to_string())));
+    let Declaration::Enum(e) = &win.declarations[1] else { panic!() };
+    expect_eq!(resolve_enum_values(e).unwrap()[2], ("C".to_string(), 2));
+
+    let android = parse_module_with_features(source, &features(&["is_android"])).unwrap();
+    expect_eq!(android.declarations.len(), 3);
+    let Declaration::Struct(config) = &android.declarations[0] else { panic!() };
+    expect_eq!(field_names(config), vec!["always", "not_win"]);
+    let Declaration::Enum(e) = &android.declarations[2] else { panic!() };
+    expect_eq!(resolve_enum_values(e).unwrap()[1], ("C".to_string(), 1));
+
+    // Without a feature set, everything is kept, and the conditions survive
+    // deparsing.
+    let everything = parse_module(source).unwrap();
+    let Declaration::Struct(config) = &everything.declarations[0] else { panic!() };
+    expect_eq!(field_names(config), vec!["always", "win_only", "not_win"]);
+    let deparsed = deparse_module(&everything);
+    expect_true!(deparsed.contains("  [EnableIfNot=is_win] int32 not_win;\n"), "{}", deparsed);
+    expect_true!(
+        deparsed.contains("[EnableIf=is_android] interface AndroidOnly {"),
+        "{}",
+        deparsed
+    );
+}
+
+#[gtest(MojomParserTest, EnableIfInvalid)]
+fn test_enable_if_invalid() {
+    let no_features = Default::default();
+    for (source, expected) in [
+        ("struct S { [EnableIf=a, EnableIfNot=b] int32 x; };", "cannot both be used"),
+        ("struct S { [EnableIf] int32 x; };", "requires a feature name"),
+        ("struct S { [EnableIf=1] int32 x; };", "requires a feature name"),
+        // Disabled declarations must still parse.
+        ("[EnableIf=a] struct S { int32 };", "Expected an identifier"),
+    ] {
+        let err = parse_module_with_features(source, &no_features).unwrap_err().to_string();
+        expect_true!(err.contains(expected), "{}: {}", source, err);
+    }
+    let source = "struct S { [EnableIf=1] int32 x; };";
+    let err = parse_module(source).unwrap_err().downcast::<ParseError>().unwrap();
+    expect_eq!(err.span.text(source), "1");
+}
+
+#[gtest(MojomParserTest, EndpointAndHandleTypes)]
+fn test_endpoint_and_handle_types() {
+    let module = parse_module(
+        "interface Factory {
+           Create(pending_receiver<Foo> receiver, handle<shared_buffer> buffer,
+                  pending_remote<bar.mojom.Client>? client);
+         };",
+    )
+    .unwrap();
+    let [Declaration::Interface(factory)] = module.declarations.as_slice() else {
+        panic!("Unexpected declarations {:?}", module.declarations)
+    };
+    let kinds: Vec<&TypeKind> =
+        factory.methods[0].parameters.iter().map(|parameter| &parameter.ty.kind).collect();
+    expect_eq!(
+        kinds,
+        vec![
+            &TypeKind::PendingReceiver("Foo".to_string()),
+            &TypeKind::Handle(HandleKind::SharedBuffer),
+            &TypeKind::PendingRemote("bar.mojom.Client".to_string()),
+        ]
+    );
+
+    for source in [
+        "pending_remote<Foo>",
+        "pending_receiver<Foo>?",
+        "pending_associated_remote<Foo>",
+        "pending_associated_receiver<Foo>",
+        "handle",
+        "handle<message_pipe>?",
+        "handle<data_pipe_consumer>",
+        "handle<data_pipe_producer>",
+        "handle<platform>",
+    ] {
+        expect_eq!(deparse_type(&parse_type(source).unwrap()), source);
+    }
+}
+
+#[gtest(MojomParserTest, InvalidHandleTypes)]
+fn test_invalid_handle_types() {
+    let err = parse_type("handle<bogus>").unwrap_err().to_string();
+    expect_true!(err.contains("Unknown handle type `bogus`"), "{}", err);
+    expect_true!(parse_type("pending_remote").is_err());
+    expect_true!(parse_type("pending_remote<>").is_err());
+}
+
+#[gtest(MojomParserTest, FixedSizeArrays)]
+fn test_fixed_size_arrays() {
+    let ty = parse_type("array<uint8, 16>").unwrap();
+    let TypeKind::Array { element_type, length } = &ty.kind else {
+        panic!("Expected an array, got {:?}", ty)
+    };
+    expect_eq!(element_type.kind, TypeKind::UInt8);
+    expect_eq!(*length, Some(16));
+
+    for source in
+        ["array<int32>", "array<uint8, 16>", "array<array<int8, 2>>?", "array<string?, 3>"]
+    {
+        expect_eq!(deparse_type(&parse_type(source).unwrap()), source);
+    }
+    expect_eq!(deparse_type(&parse_type("array<bool,0x8>").unwrap()), "array<bool, 8>");
+}
+
+#[gtest(MojomParserTest, InvalidArrayLengths)]
+fn test_invalid_array_lengths() {
+    for (source, expected) in [
+        ("array<uint8, 0>", "greater than zero"),
+        ("array<uint8, -1>", "Expected an array length"),
+        ("array<uint8, 0x100000000>", "Invalid array length"),
+        ("array<uint8, kLength>", "Expected an array length"),
+        ("array<uint8,>", "Expected an array length"),
+    ] {
+        let err = parse_type(source).unwrap_err().to_string();
+        expect_true!(err.contains(expected), "{}: {}", source, err);
+    }
+}
+
+#[gtest(MojomParserTest, Maps)]
+fn test_maps() {
+    let ty = parse_type("map<string, int32>").unwrap();
+    let TypeKind::Map { key, value } = &ty.kind else { panic!("Expected a map, got {:?}", ty) };
+    expect_eq!(
+        **key,
+        Type { kind: TypeKind::String, nullable: false, span: Span { start: 4, end: 10 } }
+    );
+    expect_eq!(
+        **value,
+        Type { kind: TypeKind::Int32, nullable: false, span: Span { start: 12, end: 17 } }
+    );
+
+    let ty = parse_type("map<int64, array<uint8>?>").unwrap();
+    let TypeKind::Map { value, .. } = &ty.kind else { panic!("Expected a map, got {:?}", ty) };
+    expect_true!(value.nullable);
+    expect_true!(matches!(value.kind, TypeKind::Array { .. }));
+
+    for source in [
+        "map<string, int32>",
+        "map<string, array<uint8>?>?",
+        "map<Enum, map<bool, Foo?>>",
+        "array<map<uint8, pending_remote<I>>>",
+    ] {
+        expect_eq!(deparse_type(&parse_type(source).unwrap()), source);
+    }
+}
+
+#[gtest(MojomParserTest, InvalidMapKeys)]
+fn test_invalid_map_keys() {
+    for source in [
+        "map<array<int32>, string>",
+        "map<string?, int32>",
+        "map<map<string, int32>, int32>",
+        "map<handle, int32>",
+    ] {
+        let err = parse_type(source).unwrap_err().to_string();
+        expect_true!(err.contains("Map keys must be"), "{}: {}", source, err);
+        expect_true!(err.contains("at byte 4"), "{}: {}", source, err);
+    }
+    expect_true!(parse_type("map<string>").is_err());
+}
+
+#[gtest(MojomParserTest, DocComments)]
+fn test_doc_comments() {
+    let module = parse_module(
+        "// Not attached, because of the blank line.
+
+         // Draws things.
+         //   Indentation after the first space is kept.
+         interface Canvas {
+           // Clears the canvas.
+           Clear();
+           Draw(int32 x); // Describes Draw, not Resize.
+           Resize([MinVersion=1] int32 width);
+         };
+         struct Point {
+           // The first coordinate.
+           [MinVersion=1] int32 x;
+           int32 y;
+           /* Block comments don't count. */
+           int32 z;
+         };",
+    )
+    .unwrap();
+    let Declaration::Interface(interface) = &module.declarations[0] else {
+        panic!("Expected an interface, got {:?}", module.declarations[0])
+    };
+    expect_eq!(
+        interface.doc.as_deref(),
+        Some("Draws things.\n  Indentation after the first space is kept.")
+    );
+    let docs: Vec<_> = interface.methods.iter().map(|method| method.doc.as_deref()).collect();
+    expect_eq!(docs, [Some("Clears the canvas."), None, None]);
+    expect_eq!(interface.methods[2].parameters[0].doc, None);
+
+    let Declaration::Struct(s) = &module.declarations[1] else {
+        panic!("Expected a struct, got {:?}", module.declarations[1])
+    };
+    let docs: Vec<_> = s.fields.iter().map(|field| field.doc.as_deref()).collect();
+    expect_eq!(docs, [Some("The first coordinate."), None, None]);
+
+    // A trailing comment isn't part of the doc comment for the next line.
+    let module = parse_module("struct P { int32 x; // Not a doc.\n// Doc.\nint32 y; };").unwrap();
+    let docs: Vec<_> = only_struct(&module).fields.iter().map(|f| f.doc.as_deref()).collect();
+    expect_eq!(docs, [None, Some("Doc.")]);
+}
+
+#[gtest(MojomParserTest, StableInterfaces)]
+fn test_stable_interfaces() {
+    let module = parse_module(
+        "[Stable, Uuid=01234567-89ab-cdef-0123-456789ABCDEF] interface A {};
+         [Stable] interface B {};
+         [Uuid=\"fedcba98-7654-3210-fedc-ba9876543210\", Stable] interface C {};
+         interface D {};",
+    )
+    .unwrap();
+    let interfaces: Vec<(bool, Option<u128>)> = module
+        .declarations
+        .iter()
+        .map(|declaration| match declaration {
+            Declaration::Interface(interface) => (interface.stable, interface.uuid),
+            _ => panic!("Expected an interface, got {:?}", declaration),
+        })
+        .collect();
+    expect_eq!(
+        interfaces,
+        vec![
+            (true, Some(0x0123456789abcdef0123456789abcdef)),
+            (true, None),
+            (true, Some(0xfedcba9876543210fedcba9876543210)),
+            (false, None),
+        ]
+    );
+    expect_eq!(
+        deparse_declaration(&module.declarations[0]),
+        "[Stable, Uuid=01234567-89ab-cdef-0123-456789abcdef] interface A {\n};\n"
+    );
+}
+
+#[gtest(MojomParserTest, ExtraAttributes)]
+fn test_extra_attributes() {
+    let source = "[Stable, ServiceSandbox=sandbox.mojom.Sandbox.kService]
+                  interface Widget {
+                    [Sync, Priority=-3, Label=\"a \\\"b\\\"\", Watched] Poke() => ();
+                  };";
+    let module = parse_module(source).unwrap();
+    let Declaration::Interface(interface) = &module.declarations[0] else { panic!() };
+    expect_true!(interface.stable);
+    let sandbox = AttrValue::Identifier("sandbox.mojom.Sandbox.kService".to_string());
+    expect_eq!(interface.extra_attributes, vec![("ServiceSandbox".to_string(), Some(sandbox))]);
+    let method = &interface.methods[0];
+    expect_true!(method.is_sync);
+    expect_eq!(
+        method.extra_attributes,
+        vec![
+            ("Priority".to_string(), Some(AttrValue::Integer(-3))),
+            ("Label".to_string(), Some(AttrValue::String("a \"b\"".to_string()))),
+            ("Watched".to_string(), None),
+        ]
+    );
+
+    // Extra attributes come out after the known ones, in their original order.
+    let deparsed = deparse_module(&module);
+    expect_true!(
+        deparsed.starts_with(
+            "[Stable, ServiceSandbox=sandbox.mojom.Sandbox.kService] interface Widget {\n\
+             \x20 [Sync, Priority=-3, Label=\"a \\\"b\\\"\", Watched] Poke() => ();\n"
+        ),
+        "{}",
+        deparsed
+    );
+    expect_eq!(without_spans(&parse_module(&deparsed).unwrap()), without_spans(&module));
+
+    // An attribute that isn't known on one kind of node is extra there.
+    let module = parse_module("[Sync] struct S {};").unwrap();
+    let Declaration::Struct(s) = &module.declarations[0] else { panic!() };
+    expect_eq!(s.extra_attributes, vec![("Sync".to_string(), None)]);
+
+    let err = parse_module("[Scale=1.5] struct S {};").unwrap_err().to_string();
+    expect_eq!(
+        err,
+        "Attribute `Scale` must be an identifier, string or integer, but found `1.5` at byte 7"
+    );
+}
+
+#[gtest(MojomParserTest, InvalidUuids)]
+fn test_invalid_uuids() {
+    for uuid in [
+        "01234567-89ab-cdef-0123-456789abcde",
+        "01234567-89ab-cdef-0123-456789abcdeg",
+        "0123456789abcdef0123456789abcdef",
+        "01234567-89ab-cdef-0123-456789abcdef0",
+        "\"01234567_89ab_cdef_0123_456789abcdef\"",
+    ] {
+        let source = format!("[Stable, Uuid={}] interface A {{}};", uuid);
+        let (_, errors) = parse_module_with_recovery(&source);
+        expect_eq!(errors.len(), 1, "{}", source);
+        expect_true!(errors[0].message.contains("Uuid must be of the form"), "{:?}", errors[0]);
+        expect_eq!(errors[0].span.text(&source), uuid);
+    }
+
+    let err = parse_module("[Uuid=01234567-89ab-cdef-0123-456789abcdef] interface A {};")
+        .unwrap_err()
+        .to_string();
+    expect_eq!(err, "Interface `A` has a Uuid attribute, so it must also be Stable");
+}
+
+#[gtest(MojomParserTest, Features)]
+fn test_features() {
+    let module = parse_module(
+        "feature kMyFeature {
+           const string name = \"MyFeature\";
+           const bool default_state = true;
+         };",
+    )
+    .unwrap();
+    let [Declaration::Feature(feature)] = module.declarations.as_slice() else {
+        panic!("Expected a single feature, got {:?}", module.declarations)
+    };
+    expect_eq!(feature.name, "kMyFeature");
+    expect_eq!(feature.feature_name(), Some("MyFeature"));
+    expect_eq!(feature.default_state(), Some(true));
+    expect_eq!(
+        deparse_module(&module),
+        "feature kMyFeature {\n  const string name = \"MyFeature\";\n  \
+         const bool default_state = true;\n};\n"
+    );
+}
+
+#[gtest(MojomParserTest, InvalidFeatures)]
+fn test_invalid_features() {
+    let err = parse_module(
+        "feature kF { const string name = \"F\"; const bool default_state = false; \
+         const int32 priority = 1; };",
+    )
+    .unwrap_err()
+    .to_string();
+    expect_eq!(
+        err,
+        "Unexpected constant `priority` in feature `kF`; only `name` and `default_state` are \
+         allowed"
+    );
+
+    for source in [
+        "feature kF { const string name = \"F\"; };",
+        "feature kF { const bool default_state = false; };",
+        "feature kF { const string name = kOther; const bool default_state = false; };",
+        "feature kF { const string name = \"F\"; const bool default_state = 0; };",
+        "feature kF { const int32 name = 1; const bool default_state = false; };",
+    ] {
+        let err = parse_module(source).unwrap_err().to_string();
+        expect_true!(err.starts_with("Feature `kF` must have"), "{}: {}", source, err);
+    }
+    let err = parse_module(
+        "feature kF { const string name = \"F\"; const string name = \"G\"; \
+         const bool default_state = false; };",
+    )
+    .unwrap_err()
+    .to_string();
+    expect_eq!(err, "Duplicate constant `name` in feature `kF`");
+}
+
+#[gtest(MojomParserTest, FormatError)]
+fn test_format_error() {
+    let source = "struct S {};
+interface Canvas {
+  Clear();
+\tDraw(Shape shape, Color) => ();
+};";
+    let err = parse_module(source).unwrap_err().downcast::<ParseError>().unwrap();
+    expect_eq!(
+        format_error(&err, source),
+        "4:25: Expected an identifier while parsing a method parameter, but found `)`
+\tDraw(Shape shape, Color) => ();
+\t                       ^"
+    );
+    // Displaying the error with its path puts the path in front.
+    let path = Path::new("canvas.mojom");
+    expect_eq!(
+        err.display(path, source).to_string(),
+        format!("canvas.mojom:{}", format_error(&err, source))
+    );
+
+    // Spans reaching past the end of the line are cut off there.
+    let source = "const string s = \"abc\\q\";\n";
+    let (_, errors) = parse_module_with_recovery(source);
+    expect_eq!(
+        format_error(&errors[0], source),
+        "1:22: Invalid escape sequence `\\q`\nconst string s = \"abc\\q\";\n                     ^^"
+    );
+}
@@ -0,0 +1,750 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Defines semantic passes over a parsed .mojom file.
+//!
+//! The parser in parse_source.rs only checks that a file is syntactically
+//! valid. The functions here compute information that depends on more than a
+//! single declaration in isolation, such as the actual values of enums and
+//! constants.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::ast::*;
+use crate::deparse_source::deparse_type;
+use crate::parse_source::{parse_module, ParseError};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// An error found by a semantic pass, along with the span of the declaration
+/// or member it's about.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl std::fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SemanticError {}
+
+/// Return the smallest and largest values of an integer type, or None if the
+/// type isn't an integer.
+fn integer_range(kind: &TypeKind) -> Option<(i128, i128)> {
+    let range = match kind {
+        TypeKind::Int8 => (i8::MIN.into(), i8::MAX.into()),
+        TypeKind::UInt8 => (0, u8::MAX.into()),
+        TypeKind::Int16 => (i16::MIN.into(), i16::MAX.into()),
+        TypeKind::UInt16 => (0, u16::MAX.into()),
+        TypeKind::Int32 => (i32::MIN.into(), i32::MAX.into()),
+        TypeKind::UInt32 => (0, u32::MAX.into()),
+        TypeKind::Int64 => (i64::MIN.into(), i64::MAX.into()),
+        TypeKind::UInt64 => (0, u64::MAX.into()),
+        _ => return None,
+    };
+    Some(range)
+}
+
+/// Look up a previously-declared constant.
+fn lookup_constant<'a>(name: &str, constants: &'a HashMap<String, Value>) -> Result<&'a Value> {
+    match constants.get(name) {
+        Some(value) => Ok(value),
+        None => bail!("Unknown constant `{}`", name),
+    }
+}
+
+/// Give `error` the span of `expr`, unless it already has the narrower span of
+/// one of the operands of `expr`.
+fn with_expr_span(error: anyhow::Error, expr: &ValueExpr) -> anyhow::Error {
+    if error.is::<SemanticError>() {
+        return error;
+    }
+    SemanticError { message: format!("{:#}", error), span: expr.span }.into()
+}
+
+/// Evaluate an integer expression, without regard to the type it will be
+/// stored in.
+fn eval_integer_expr(expr: &ValueExpr, constants: &HashMap<String, Value>) -> Result<i128> {
+    eval_integer_expr_inner(expr, constants).map_err(|error| with_expr_span(error, expr))
+}
+
+fn eval_integer_expr_inner(expr: &ValueExpr, constants: &HashMap<String, Value>) -> Result<i128> {
+    let value = match &expr.kind {
+        ValueExprKind::Integer(value) => *value,
+        ValueExprKind::Float(value) => bail!("Expected an integer, but found {:?}", value),
+        ValueExprKind::String(value) => bail!("Expected an integer, but found {:?}", value),
+        ValueExprKind::Array(_) => bail!("Expected an integer, but found an array"),
+        ValueExprKind::Name(name) => match lookup_constant(name, constants)? {
+            Value::Integer(value) => *value,
+            _ => bail!("Constant `{}` is not an integer", name),
+        },
+        ValueExprKind::Unary { op, operand } => {
+            let operand = eval_integer_expr(operand, constants)?;
+            match op {
+                UnaryOp::Negate => operand
+                    .checked_neg()
+                    .with_context(|| format!("Overflow evaluating `-{}`", operand))?,
+                UnaryOp::Not => !operand,
+            }
+        }
+        ValueExprKind::Binary { op, lhs, rhs } => {
+            let lhs = eval_integer_expr(lhs, constants)?;
+            let rhs = eval_integer_expr(rhs, constants)?;
+            let shift_amount = || match u32::try_from(rhs) {
+                Ok(amount) if amount < 64 => Ok(amount),
+                _ => bail!("Shift amount {} must be between 0 and 63", rhs),
+            };
+            let result = match op {
+                BinaryOp::Or => Some(lhs | rhs),
+                BinaryOp::Xor => Some(lhs ^ rhs),
+                BinaryOp::And => Some(lhs & rhs),
+                BinaryOp::ShiftLeft => lhs.checked_mul(1 << shift_amount()?),
+                BinaryOp::ShiftRight => Some(lhs >> shift_amount()?),
+                BinaryOp::Add => lhs.checked_add(rhs),
+                BinaryOp::Subtract => lhs.checked_sub(rhs),
+                BinaryOp::Multiply => lhs.checked_mul(rhs),
+                BinaryOp::Divide | BinaryOp::Remainder if rhs == 0 => {
+                    bail!("Division by zero")
+                }
+                BinaryOp::Divide => lhs.checked_div(rhs),
+                BinaryOp::Remainder => lhs.checked_rem(rhs),
+            };
+            result
+                .with_context(|| format!("Overflow evaluating `{} {} {}`", lhs, op.symbol(), rhs))?
+        }
+    };
+    Ok(value)
+}
+
+/// Evaluate a floating point expression, in double precision. Only `+`, `-`,
+/// `*` and `/` are allowed. Integers are converted to floating point.
+fn eval_float_expr(expr: &ValueExpr, constants: &HashMap<String, Value>) -> Result<f64> {
+    eval_float_expr_inner(expr, constants).map_err(|error| with_expr_span(error, expr))
+}
+
+fn eval_float_expr_inner(expr: &ValueExpr, constants: &HashMap<String, Value>) -> Result<f64> {
+    let value = match &expr.kind {
+        ValueExprKind::Integer(value) => *value as f64,
+        ValueExprKind::Float(value) => *value,
+        ValueExprKind::String(value) => bail!("Expected a number, but found {:?}", value),
+        ValueExprKind::Array(_) => bail!("Expected a number, but found an array"),
+        ValueExprKind::Name(name) => match name.as_str() {
+            "float.INFINITY" | "double.INFINITY" => f64::INFINITY,
+            "float.NEGATIVE_INFINITY" | "double.NEGATIVE_INFINITY" => f64::NEG_INFINITY,
+            "float.NAN" | "double.NAN" => f64::NAN,
+            _ => match lookup_constant(name, constants)? {
+                Value::Integer(value) => *value as f64,
+                Value::Float(value) => f64::from(*value),
+                Value::Double(value) => *value,
+                Value::NaN => f64::NAN,
+                Value::String(_) | Value::Enum { .. } | Value::Array(_) => {
+                    bail!("Constant `{}` is not a number", name)
+                }
+            },
+        },
+        ValueExprKind::Unary { op: UnaryOp::Negate, operand } => {
+            -eval_float_expr(operand, constants)?
+        }
+        ValueExprKind::Unary { op: UnaryOp::Not, .. } => {
+            bail!("Operator `~` cannot be used on floating point values")
+        }
+        ValueExprKind::Binary { op, lhs, rhs } => {
+            let lhs = eval_float_expr(lhs, constants)?;
+            let rhs = eval_float_expr(rhs, constants)?;
+            match op {
+                BinaryOp::Add => lhs + rhs,
+                BinaryOp::Subtract => lhs - rhs,
+                BinaryOp::Multiply => lhs * rhs,
+                BinaryOp::Divide => lhs / rhs,
+                _ => bail!("Operator `{}` cannot be used on floating point values", op.symbol()),
+            }
+        }
+    };
+    Ok(value)
+}
+
+/// Evaluate a value of `enum_decl`, which must be either `Enum.VALUE` or the
+/// name of a constant of the same enum type.
+fn eval_enum_expr(
+    expr: &ValueExpr,
+    enum_decl: &Enum,
+    constants: &HashMap<String, Value>,
+) -> Result<Value> {
+    let ValueExprKind::Name(name) = &expr.kind else {
+        bail!("Expected a value of enum `{}`", enum_decl.name)
+    };
+    if let Some(value_name) =
+        name.strip_prefix(enum_decl.name.as_str()).and_then(|rest| rest.strip_prefix('.'))
+    {
+        let values = resolve_enum_values(enum_decl)?;
+        let Some((_, value)) = values.iter().find(|(other, _)| other == value_name) else {
+            bail!("Enum `{}` has no value `{}`", enum_decl.name, value_name)
+        };
+        return Ok(Value::Enum { enum_name: enum_decl.name.clone(), value: *value });
+    }
+    match lookup_constant(name, constants)? {
+        value @ Value::Enum { enum_name, .. } if *enum_name == enum_decl.name => Ok(value.clone()),
+        _ => bail!("Constant `{}` is not a value of enum `{}`", name, enum_decl.name),
+    }
+}
+
+/// Evaluate an array literal of type `array<element_type, length>`, where
+/// `length` is None for arrays of any length. Arrays must be written out as
+/// literals, rather than naming another array constant.
+fn eval_array_expr(
+    expr: &ValueExpr,
+    element_type: &Type,
+    length: Option<u32>,
+    constants: &HashMap<String, Value>,
+    module: Option<&Module>,
+) -> Result<Value> {
+    let ValueExprKind::Array(elements) = &expr.kind else { bail!("Expected an array literal") };
+    match length {
+        Some(length) if elements.len() != length as usize => {
+            bail!("Expected {} elements, but found {}", length, elements.len())
+        }
+        _ => (),
+    }
+    let values = elements
+        .iter()
+        .enumerate()
+        .map(|(index, element)| {
+            eval_const_expr_with_enums(element, element_type, constants, module)
+                .with_context(|| format!("Invalid array element {}", index))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Value::Array(values))
+}
+
+/// Evaluate the value of a constant of type `ty`. Names are looked up in
+/// `constants`, which holds the values of previously-declared constants.
+///
+/// Only integer, floating point, string and array types are supported. It is an
+/// error for the result not to fit in `ty`. See eval_const_expr_in_module for
+/// enum types.
+pub fn eval_const_expr(
+    expr: &ValueExpr,
+    ty: &Type,
+    constants: &HashMap<String, Value>,
+) -> Result<Value> {
+    eval_const_expr_with_enums(expr, ty, constants, None)
+}
+
+/// Like eval_const_expr, but also supports constants whose type is an enum
+/// declared in `module`. Their values are written `MyEnum.FOO`.
+pub fn eval_const_expr_in_module(
+    expr: &ValueExpr,
+    ty: &Type,
+    constants: &HashMap<String, Value>,
+    module: &Module,
+) -> Result<Value> {
+    eval_const_expr_with_enums(expr, ty, constants, Some(module))
+}
+
+fn eval_const_expr_with_enums(
+    expr: &ValueExpr,
+    ty: &Type,
+    constants: &HashMap<String, Value>,
+    module: Option<&Module>,
+) -> Result<Value> {
+    eval_const_expr_with_enums_inner(expr, ty, constants, module)
+        .map_err(|error| with_expr_span(error, expr))
+}
+
+fn eval_const_expr_with_enums_inner(
+    expr: &ValueExpr,
+    ty: &Type,
+    constants: &HashMap<String, Value>,
+    module: Option<&Module>,
+) -> Result<Value> {
+    let declaration = match (&ty.kind, module) {
+        (TypeKind::Named(name), Some(module)) => module.find_declaration(name),
+        _ => None,
+    };
+    if let Some(Declaration::Enum(enum_decl)) = declaration {
+        return eval_enum_expr(expr, enum_decl, constants);
+    }
+    if let TypeKind::Array { element_type, length } = &ty.kind {
+        return eval_array_expr(expr, element_type, *length, constants, module);
+    }
+    if let Some((min, max)) = integer_range(&ty.kind) {
+        let value = eval_integer_expr(expr, constants)?;
+        if value < min || value > max {
+            bail!("Value {} does not fit in type `{}`", value, deparse_type(ty))
+        }
+        return Ok(Value::Integer(value));
+    }
+    let value = match ty.kind {
+        TypeKind::String => {
+            return match &expr.kind {
+                ValueExprKind::String(value) => Ok(Value::String(value.clone())),
+                ValueExprKind::Name(name) => match lookup_constant(name, constants)? {
+                    Value::String(value) => Ok(Value::String(value.clone())),
+                    _ => bail!("Constant `{}` is not a string", name),
+                },
+                _ => bail!("Expected a string literal or constant"),
+            };
+        }
+        TypeKind::Float | TypeKind::Double => eval_float_expr(expr, constants)?,
+        _ => bail!("Constants of type `{}` are not supported", deparse_type(ty)),
+    };
+    if value.is_nan() {
+        return Ok(Value::NaN);
+    }
+    if ty.kind == TypeKind::Double {
+        return Ok(Value::Double(value));
+    }
+    let single = value as f32;
+    if value.is_finite() && !single.is_finite() {
+        bail!("Value {:?} does not fit in type `float`", value)
+    }
+    Ok(Value::Float(single))
+}
+
+/// Compute the values of all the constants declared in `module`, in
+/// declaration order. Each constant may refer to the ones before it.
+pub fn resolve_constants(module: &Module) -> Result<HashMap<String, Value>> {
+    let mut constants = HashMap::new();
+    for declaration in &module.declarations {
+        let Declaration::Const(constant) = declaration else { continue };
+        let value = eval_const_expr_in_module(&constant.value, &constant.ty, &constants, module)
+            .with_context(|| format!("Invalid value for constant `{}`", constant.name))?;
+        if constants.insert(constant.name.clone(), value).is_some() {
+            bail!("Duplicate constant `{}`", constant.name)
+        }
+    }
+    Ok(constants)
+}
+
+/// Check the default value of each field of `strukt` against the field's type,
+/// and return their values in field order, with None for fields which have no
+/// default.
+///
+/// Defaults are evaluated like constants, so they may refer to the constants
+/// and enum values of `module`.
+pub fn resolve_field_defaults(strukt: &Struct, module: &Module) -> Result<Vec<Option<Value>>> {
+    let constants = resolve_constants(module)?;
+    strukt
+        .fields
+        .iter()
+        .map(|field| {
+            let Some(default) = &field.default else { return Ok(None) };
+            let value = eval_const_expr_in_module(default, &field.ty, &constants, module)
+                .with_context(|| {
+                    format!("Invalid default for field `{}` of `{}`", field.name, strukt.name)
+                })?;
+            Ok(Some(value))
+        })
+        .collect()
+}
+
+/// Add the names referred to by `expr` to `names`.
+fn referenced_names<'a>(expr: &'a ValueExpr, names: &mut Vec<&'a str>) {
+    match &expr.kind {
+        ValueExprKind::Name(name) => names.push(name),
+        ValueExprKind::Unary { operand, .. } => referenced_names(operand, names),
+        ValueExprKind::Binary { lhs, rhs, .. } => {
+            referenced_names(lhs, names);
+            referenced_names(rhs, names);
+        }
+        ValueExprKind::Array(elements) => {
+            for element in elements {
+                referenced_names(element, names);
+            }
+        }
+        ValueExprKind::Integer(_) | ValueExprKind::Float(_) | ValueExprKind::String(_) => (),
+    }
+}
+
+/// Compute the value of `constant` and every constant it depends on, adding
+/// them to `values`. `stack` holds the constants currently being evaluated,
+/// innermost last, for detecting cycles.
+fn eval_constant<'a>(
+    module: &'a Module,
+    constant: &'a Const,
+    stack: &mut Vec<&'a str>,
+    values: &mut HashMap<String, Value>,
+) -> Result<Value> {
+    if let Some(value) = values.get(&constant.name) {
+        return Ok(value.clone());
+    }
+    if let Some(cycle_start) = stack.iter().position(|name| *name == constant.name) {
+        let mut cycle = stack[cycle_start..].to_vec();
+        cycle.push(&constant.name);
+        bail!("Circular constant definition: {}", cycle.join(" -> "))
+    }
+    stack.push(&constant.name);
+    let mut names = vec![];
+    referenced_names(&constant.value, &mut names);
+    for name in names {
+        if let Some(Declaration::Const(dependency)) = module.find_declaration(name) {
+            eval_constant(module, dependency, stack, values)?;
+        }
+    }
+    stack.pop();
+    let value = eval_const_expr_in_module(&constant.value, &constant.ty, values, module)
+        .with_context(|| format!("Invalid value for constant `{}`", constant.name))?;
+    values.insert(constant.name.clone(), value.clone());
+    Ok(value)
+}
+
+// This lives here rather than in ast.rs since it's built on the evaluator.
+impl Module {
+    /// Compute the value of the constant called `name`, or return None if
+    /// there's no such constant.
+    ///
+    /// Unlike resolve_constants, this follows references to other constants
+    /// wherever they are declared. It's an error for them to form a cycle.
+    pub fn const_value(&self, name: &str) -> Result<Option<Value>> {
+        let Some(Declaration::Const(constant)) = self.find_declaration(name) else {
+            return Ok(None);
+        };
+        eval_constant(self, constant, &mut vec![], &mut HashMap::new()).map(Some)
+    }
+}
+
+/// Compute the integer value of each value of an enum, in declaration order.
+///
+/// Values without an explicit initializer are one greater than the previous
+/// value, or 0 if they come first. Initializers may refer to earlier values of
+/// the same enum by name. No two values may be equal.
+pub fn resolve_enum_values(enum_decl: &Enum) -> Result<Vec<(String, i128)>> {
+    let mut resolved: Vec<(String, i128)> = vec![];
+    let mut earlier_values = HashMap::new();
+    let mut next_value = 0;
+    for enum_value in &enum_decl.values {
+        let value = match &enum_value.value {
+            Some(expr) => eval_integer_expr(expr, &earlier_values).with_context(|| {
+                format!("Invalid value for `{}.{}`", enum_decl.name, enum_value.name)
+            })?,
+            None => next_value,
+        };
+        if let Some((other_name, _)) = resolved.iter().find(|(_, other)| *other == value) {
+            bail!(
+                "Values `{}` and `{}` of enum `{}` both have the value {}",
+                other_name,
+                enum_value.name,
+                enum_decl.name,
+                value
+            )
+        }
+        resolved.push((enum_value.name.clone(), value));
+        earlier_values.insert(enum_value.name.clone(), Value::Integer(value));
+        next_value = value + 1;
+    }
+    Ok(resolved)
+}
+
+/// Check that every value of an enum fits in its storage, which is always an
+/// int32 on the wire. All out of range values are listed in the error.
+pub fn validate_enum_range(enum_decl: &Enum) -> Result<()> {
+    let out_of_range: Vec<String> = resolve_enum_values(enum_decl)?
+        .into_iter()
+        .filter(|(_, value)| i32::try_from(*value).is_err())
+        .map(|(name, value)| format!("`{}` = {}", name, value))
+        .collect();
+    if !out_of_range.is_empty() {
+        bail!(
+            "Values of enum `{}` must fit in an int32, but found {}",
+            enum_decl.name,
+            out_of_range.join(", ")
+        )
+    }
+    Ok(())
+}
+
+/// Check the `[Default]` values of an enum. An `[Extensible]` enum must have
+/// exactly one, which is used in place of values the receiver doesn't know. Other
+/// enums must not have any.
+///
+/// The error's span is that of the offending value, which starts with its
+/// `[Default]` attribute, or of the whole enum if it's missing a default.
+pub fn validate_enum_default(enum_decl: &Enum) -> std::result::Result<(), SemanticError> {
+    let defaults: Vec<&EnumValue> =
+        enum_decl.values.iter().filter(|enum_value| enum_value.is_default).collect();
+    let (message, span) = match (enum_decl.extensible, defaults.as_slice()) {
+        (true, [_]) | (false, []) => return Ok(()),
+        (true, []) => (
+            format!("Extensible enum `{}` must have a [Default] value", enum_decl.name),
+            enum_decl.span,
+        ),
+        (true, [first, second, ..]) => (
+            format!(
+                "Extensible enum `{}` must have only one [Default] value, but both `{}` and `{}` \
+                 are marked [Default]",
+                enum_decl.name, first.name, second.name
+            ),
+            second.span,
+        ),
+        (false, [first, ..]) => (
+            format!(
+                "Enum `{}` is not [Extensible], so its value `{}` cannot be [Default]",
+                enum_decl.name, first.name
+            ),
+            first.span,
+        ),
+    };
+    Err(SemanticError { message, span })
+}
+
+/// Find the file that `import_path` refers to, in the first include directory
+/// that has it.
+fn find_import(import_path: &str, include_dirs: &[PathBuf]) -> Result<PathBuf> {
+    for dir in include_dirs {
+        let candidate = dir.join(import_path);
+        if candidate.is_file() {
+            return Ok(candidate);
+        }
+    }
+    bail!("Could not find imported file `{}` in any of {:?}", import_path, include_dirs)
+}
+
+/// Parse the file at `file_path`, stored in `modules` under `key`, along with
+/// everything it imports. `import_stack` holds the keys of the files currently
+/// being loaded, innermost last, for detecting cycles.
+fn load_module(
+    key: &str,
+    file_path: &Path,
+    include_dirs: &[PathBuf],
+    import_stack: &mut Vec<String>,
+    modules: &mut HashMap<String, Module>,
+) -> Result<()> {
+    let source = std::fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to read {}", file_path.display()))?;
+    let module = parse_module(&source).map_err(|error| match error.downcast::<ParseError>() {
+        Ok(error) => anyhow!("{}", error.display(file_path, &source)),
+        Err(error) => error.context(format!("Failed to parse {}", file_path.display())),
+    })?;
+    import_stack.push(key.to_string());
+    for import in &module.imports {
+        if let Some(cycle_start) = import_stack.iter().position(|key| *key == import.path) {
+            let mut cycle = import_stack[cycle_start..].to_vec();
+            cycle.push(import.path.clone());
+            bail!("Circular import: {}", cycle.join(" -> "))
+        }
+        if modules.contains_key(&import.path) {
+            continue;
+        }
+        let import_file = find_import(&import.path, include_dirs)
+            .with_context(|| format!("Unresolved import in {}", file_path.display()))?;
+        load_module(&import.path, &import_file, include_dirs, import_stack, modules)?;
+    }
+    import_stack.pop();
+    modules.insert(key.to_string(), module);
+    Ok(())
+}
+
+/// Parse the .mojom file at `root`, and every file it imports, directly or
+/// indirectly.
+///
+/// Imports are looked up in each of `include_dirs` in turn. The result maps
+/// each import path to the parsed module; the root file is stored under `root`
+/// as given. Circular imports are an error.
+pub fn resolve_imports(root: &Path, include_dirs: &[PathBuf]) -> Result<HashMap<String, Module>> {
+    let mut modules = HashMap::new();
+    let root_key = root.to_string_lossy();
+    load_module(&root_key, root, include_dirs, &mut vec![], &mut modules)?;
+    Ok(modules)
+}
+
+/// Check that `ty` and any types nested in it refer only to declared types,
+/// adding an error to `errors` for each one that doesn't. Errors have the span
+/// of the offending type, or `span` for types with none, e.g. from a builder.
+fn check_type(ty: &Type, span: Span, modules: &[&Module], errors: &mut Vec<SemanticError>) {
+    let lookup = |name: &str| modules.iter().find_map(|module| module.find_declaration(name));
+    let message = match &ty.kind {
+        TypeKind::Array { element_type, .. } => {
+            return check_type(element_type, span, modules, errors);
+        }
+        TypeKind::Map { key, value } => {
+            check_type(key, span, modules, errors);
+            return check_type(value, span, modules, errors);
+        }
+        TypeKind::Named(name) => match lookup(name) {
+            None => format!("Undefined type `{}`", name),
+            Some(Declaration::Const(_)) => format!("`{}` is a constant, not a type", name),
+            Some(Declaration::Feature(_)) => format!("`{}` is a feature, not a type", name),
+            Some(_) => return,
+        },
+        TypeKind::PendingRemote(name)
+        | TypeKind::PendingReceiver(name)
+        | TypeKind::PendingAssociatedRemote(name)
+        | TypeKind::PendingAssociatedReceiver(name) => match lookup(name) {
+            None => format!("Undefined interface `{}`", name),
+            Some(Declaration::Interface(_)) => return,
+            Some(_) => format!("`{}` is not an interface", name),
+        },
+        // Everything else is built in.
+        _ => return,
+    };
+    let span = if ty.span == Span::default() { span } else { ty.span };
+    errors.push(SemanticError { message, span });
+}
+
+/// Check that every type used in `module` is declared, either in `module`
+/// itself or in one of the files it imports. `imports` holds the parsed
+/// imported files by import path, as returned by resolve_imports.
+///
+/// Returns every error found, rather than stopping at the first.
+pub fn resolve_types(
+    module: &Module,
+    imports: &HashMap<String, Module>,
+) -> std::result::Result<(), Vec<SemanticError>> {
+    let mut errors = vec![];
+    let mut modules = vec![module];
+    for import in &module.imports {
+        match imports.get(&import.path) {
+            Some(imported) => modules.push(imported),
+            None => errors.push(SemanticError {
+                message: format!("Imported file `{}` was not loaded", import.path),
+                span: import.span,
+            }),
+        }
+    }
+    let check_fields = |fields: &[Field], errors: &mut Vec<SemanticError>| {
+        for field in fields {
+            check_type(&field.ty, field.span, &modules, errors);
+        }
+    };
+    for declaration in &module.declarations {
+        match declaration {
+            Declaration::Struct(s) => check_fields(&s.fields, &mut errors),
+            Declaration::Union(u) => check_fields(&u.fields, &mut errors),
+            Declaration::Interface(interface) => {
+                for method in &interface.methods {
+                    check_fields(&method.parameters, &mut errors);
+                    check_fields(
+                        method.response_parameters.as_deref().unwrap_or_default(),
+                        &mut errors,
+                    );
+                }
+            }
+            Declaration::Const(constant) => {
+                check_type(&constant.ty, constant.span, &modules, &mut errors)
+            }
+            Declaration::Enum(_) | Declaration::Feature(_) => (),
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Check that no struct in `module` contains itself through non-nullable
+/// struct fields, which would make every value of it infinitely large. Cycles
+/// through a nullable field, an array or a map are fine, since those can end.
+///
+/// Returns an error for each cycle, naming the fields it goes through.
+pub fn check_struct_cycles(module: &Module) -> std::result::Result<(), Vec<SemanticError>> {
+    let mut errors = vec![];
+    let mut done = vec![];
+    for declaration in &module.declarations {
+        if let Declaration::Struct(s) = declaration {
+            visit_struct_fields(s, module, &mut vec![], &mut done, &mut errors);
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Visit `s` and the structs it contains by value, depth first, for
+/// check_struct_cycles. `path` holds the structs being visited with the field
+/// followed out of each, and `done` the structs whose cycles are all reported.
+fn visit_struct_fields<'a>(
+    s: &'a Struct,
+    module: &'a Module,
+    path: &mut Vec<(&'a Struct, &'a str)>,
+    done: &mut Vec<&'a str>,
+    errors: &mut Vec<SemanticError>,
+) {
+    if done.contains(&s.name.as_str()) {
+        return;
+    }
+    if let Some(start) = path.iter().position(|(visiting, _)| visiting.name == s.name) {
+        let steps: Vec<String> = path[start..]
+            .iter()
+            .map(|(visiting, field)| format!("{}.{}", visiting.name, field))
+            .collect();
+        errors.push(SemanticError {
+            message: format!(
+                "Struct `{}` contains itself through non-nullable fields: {} -> {}",
+                s.name,
+                steps.join(" -> "),
+                s.name
+            ),
+            span: s.span,
+        });
+        return;
+    }
+    for field in &s.fields {
+        let TypeKind::Named(name) = &field.ty.kind else { continue };
+        if field.ty.nullable {
+            continue;
+        }
+        if let Some(Declaration::Struct(inner)) = module.find_declaration(name) {
+            path.push((s, &field.name));
+            visit_struct_fields(inner, module, path, done, errors);
+            path.pop();
+        }
+    }
+    done.push(&s.name);
+}
+
+/// Check that every method parameter which is an associated endpoint, i.e. a
+/// `pending_associated_remote` or `pending_associated_receiver`, is sent along
+/// with a `pending_remote` or `pending_receiver` in the same parameter list.
+/// That gives the associated endpoint a message pipe to share.
+///
+/// Returns an error for each associated endpoint without one, naming the
+/// method and the parameter.
+pub fn check_associated_interfaces(module: &Module) -> std::result::Result<(), Vec<SemanticError>> {
+    let mut errors = vec![];
+    for declaration in &module.declarations {
+        let Declaration::Interface(interface) = declaration else { continue };
+        for method in &interface.methods {
+            let response_parameters = method.response_parameters.as_deref().unwrap_or_default();
+            for parameters in [method.parameters.as_slice(), response_parameters] {
+                let has_pipe = parameters.iter().any(|parameter| {
+                    matches!(
+                        parameter.ty.kind,
+                        TypeKind::PendingRemote(_) | TypeKind::PendingReceiver(_)
+                    )
+                });
+                if has_pipe {
+                    continue;
+                }
+                for parameter in parameters {
+                    if let TypeKind::PendingAssociatedRemote(_)
+                    | TypeKind::PendingAssociatedReceiver(_) = parameter.ty.kind
+                    {
+                        errors.push(SemanticError {
+                            message: format!(
+                                "Method `{}.{}` has the associated endpoint `{}`, but no \
+                                 pending_remote or pending_receiver parameter for it to share \
+                                 a message pipe with",
+                                interface.name, method.name, parameter.name
+                            ),
+                            span: parameter.span,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,724 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use crate::without_spans;
+use mojom_parser::*;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Parse `source`, which must contain only a single enum.
+fn parse_enum(source: &str) -> Enum {
+    match parse_module(source).unwrap().declarations.as_slice() {
+        [Declaration::Enum(e)] => e.clone(),
+        declarations => panic!("Expected a single enum, got {:?}", declarations),
+    }
+}
+
+#[gtest(MojomResolveTest, EnumAutoIncrement)]
+fn test_enum_auto_increment() {
+    let color = parse_enum("enum Color { RED, GREEN = 5, BLUE, ALPHA = -2, BETA };");
+    expect_eq!(color.values[0].value, None);
+    expect_eq!(
+        color.values[1].value.as_ref().map(|value| &value.kind),
+        Some(&ValueExprKind::Integer(5))
+    );
+    expect_eq!(
+        color.values[3].value.as_ref().map(|value| &value.kind),
+        Some(&ValueExprKind::Integer(-2))
+    );
+    expect_eq!(
+        resolve_enum_values(&color).unwrap(),
+        vec![
+            ("RED".to_string(), 0),
+            ("GREEN".to_string(), 5),
+            ("BLUE".to_string(), 6),
+            ("ALPHA".to_string(), -2),
+            ("BETA".to_string(), -1),
+        ]
+    );
+}
+
+#[gtest(MojomResolveTest, EnumHex)]
+fn test_enum_hex() {
+    let flags = parse_enum("enum Flags { A = 0x1, B = 0x10, C, D = 0XfF, };");
+    expect_eq!(
+        resolve_enum_values(&flags).unwrap(),
+        vec![
+            ("A".to_string(), 1),
+            ("B".to_string(), 16),
+            ("C".to_string(), 17),
+            ("D".to_string(), 255),
+        ]
+    );
+}
+
+#[gtest(MojomResolveTest, EnumDuplicateValue)]
+fn test_enum_duplicate_value() {
+    let e = parse_enum("enum Dup { A = 2, B = 1, C };");
+    let err = resolve_enum_values(&e).unwrap_err().to_string();
+    expect_true!(err.contains("`A` and `C`"), "{}", err);
+    expect_true!(err.contains("the value 2"), "{}", err);
+}
+
+#[gtest(MojomResolveTest, EnumBadLiteral)]
+fn test_enum_bad_literal() {
+    for source in ["enum E { A = 0xZZ };", "enum E { A = 12ab };", "enum E { A = = };"] {
+        expect_true!(parse_module(source).is_err(), "{}", source);
+    }
+}
+
+#[gtest(MojomResolveTest, EnumRange)]
+fn test_enum_range() {
+    let e = parse_enum("enum E { MIN = -0x80000000, ZERO = 0, MAX = 0x7fffffff };");
+    expect_true!(validate_enum_range(&e).is_ok());
+
+    let e = parse_enum("enum E { OK = 1, BIG = 0x100000000, NEG = -0x80000001 };");
+    expect_eq!(
+        validate_enum_range(&e).unwrap_err().to_string(),
+        "Values of enum `E` must fit in an int32, but found `BIG` = 4294967296, \
+         `NEG` = -2147483649"
+    );
+    // Implicit values count too.
+    let e = parse_enum("enum E { LAST = 0x7fffffff, NEXT };");
+    let err = validate_enum_range(&e).unwrap_err().to_string();
+    expect_true!(err.contains("`NEXT` = 2147483648"), "{}", err);
+}
+
+#[gtest(MojomResolveTest, EnumReferencesEarlierValue)]
+fn test_enum_references_earlier_value() {
+    let e = parse_enum("enum Bits { A = 1 << 2, B = A | 1, C };");
+    expect_eq!(
+        resolve_enum_values(&e).unwrap(),
+        vec![("A".to_string(), 4), ("B".to_string(), 5), ("C".to_string(), 6)]
+    );
+    expect_true!(resolve_enum_values(&parse_enum("enum E { A = B, B };")).is_err());
+}
+
+#[gtest(MojomResolveTest, ConstShifts)]
+fn test_const_shifts() {
+    let module = parse_module(
+        "const int32 kMax = 1 << 20;
+         const uint64 kHigh = 0xff << 56;
+         const int8 kLow = -128 >> 3;
+         const int32 kMixed = 1 + 2 * 3 << 1 | 0x100;
+         const int32 kParens = (1 + 2) * -(3 - 4);",
+    )
+    .unwrap();
+    let constants = resolve_constants(&module).unwrap();
+    expect_eq!(constants["kMax"], Value::Integer(1 << 20));
+    expect_eq!(constants["kHigh"], Value::Integer(0xff << 56));
+    expect_eq!(constants["kLow"], Value::Integer(-16));
+    expect_eq!(constants["kMixed"], Value::Integer(0x10e));
+    expect_eq!(constants["kParens"], Value::Integer(3));
+}
+
+#[gtest(MojomResolveTest, ConstReferences)]
+fn test_const_references() {
+    let module = parse_module(
+        "const uint32 kBase = 0x10;
+         const uint32 kDouble = kBase * 2;
+         const uint32 kMask = ~kDouble & 0xff;",
+    )
+    .unwrap();
+    let constants = resolve_constants(&module).unwrap();
+    expect_eq!(constants["kDouble"], Value::Integer(0x20));
+    expect_eq!(constants["kMask"], Value::Integer(0xdf));
+
+    // Constants may only refer to ones declared before them.
+    let module = parse_module("const int32 kA = kB; const int32 kB = 1;").unwrap();
+    let err = format!("{:#}", resolve_constants(&module).unwrap_err());
+    expect_true!(err.contains("Unknown constant `kB`"), "{}", err);
+}
+
+#[gtest(MojomResolveTest, ConstOverflow)]
+fn test_const_overflow() {
+    for source in [
+        "const int32 kTooBig = 0x100000000;",
+        "const int32 kTooBig = 1 << 31;",
+        "const uint8 kNegative = -1;",
+        "const int64 kShift = 1 << 64;",
+        "const int64 kDivide = 1 / 0;",
+        "const uint64 kHuge = 0xffffffffffffffff * 0xffffffffffffffff * 0xffffffffffffffff;",
+        // The product is the most negative value the evaluator can hold.
+        "const int64 kNegate = -((0 - 9223372036854775807 - 1) * 9223372036854775808 * 2);",
+    ] {
+        let module = parse_module(source).unwrap();
+        expect_true!(resolve_constants(&module).is_err(), "{}", source);
+    }
+
+    let module = parse_module("const int32 kMin = -0x80000000;").unwrap();
+    expect_eq!(resolve_constants(&module).unwrap()["kMin"], Value::Integer(i32::MIN.into()));
+}
+
+#[gtest(MojomResolveTest, ConstErrorSpans)]
+fn test_const_error_spans() {
+    // Errors point at the innermost expression they are about.
+    for (source, expected) in [
+        ("const int64 kDivide = 2 * (1 / 0);", "(1 / 0)"),
+        ("const int32 kUnknown = 1 + kMissing;", "kMissing"),
+        ("const int8 kTooBig = 100 + 100;", "100 + 100"),
+        ("const double kNot = 1.5 * ~2.0;", "~2.0"),
+        ("const array<int8> kElement = [1, 1000];", "1000"),
+        ("struct S { int32 x = \"one\"; };", "\"one\""),
+    ] {
+        let module = parse_module(source).unwrap();
+        let err = match module.declarations[0] {
+            Declaration::Struct(ref s) => resolve_field_defaults(s, &module).unwrap_err(),
+            _ => resolve_constants(&module).unwrap_err(),
+        };
+        let err = err.downcast::<SemanticError>().unwrap();
+        expect_eq!(err.span.text(source), expected, "{}: {}", source, err);
+    }
+}
+
+#[gtest(MojomResolveTest, ConstFloats)]
+fn test_const_floats() {
+    let module = parse_module(
+        "const double kRate = 0.125;
+         const double kSmall = 1.5e-10;
+         const double kBig = 2E+300;
+         const double kWhole = 1e3;
+         const float kHalf = 5e-1;
+         const double kFromInt = 2;
+         const double kDoubleRate = kRate * 2;
+         const float kInf = float.INFINITY;
+         const double kNegInf = double.NEGATIVE_INFINITY;
+         const double kNaN = double.NAN;
+         const float kFloatNaN = float.NAN;",
+    )
+    .unwrap();
+    let constants = resolve_constants(&module).unwrap();
+    expect_eq!(constants["kRate"], Value::Double(0.125));
+    expect_eq!(constants["kSmall"], Value::Double(1.5e-10));
+    expect_eq!(constants["kBig"], Value::Double(2e300));
+    expect_eq!(constants["kWhole"], Value::Double(1000.0));
+    expect_eq!(constants["kHalf"], Value::Float(0.5));
+    expect_eq!(constants["kFromInt"], Value::Double(2.0));
+    expect_eq!(constants["kDoubleRate"], Value::Double(0.25));
+    expect_eq!(constants["kInf"], Value::Float(f32::INFINITY));
+    expect_eq!(constants["kNegInf"], Value::Double(f64::NEG_INFINITY));
+    expect_eq!(constants["kNaN"], Value::NaN);
+    expect_eq!(constants["kFloatNaN"], Value::NaN);
+
+    // Floats survive deparsing exactly.
+    let deparsed = deparse_module(&module);
+    expect_true!(deparsed.contains("const double kSmall = 1.5e-10;"), "{}", deparsed);
+    expect_true!(deparsed.contains("const double kBig = 2e300;"), "{}", deparsed);
+    expect_true!(deparsed.contains("const double kWhole = 1000.0;"), "{}", deparsed);
+    expect_eq!(resolve_constants(&parse_module(&deparsed).unwrap()).unwrap(), constants);
+}
+
+#[gtest(MojomResolveTest, InvalidConstFloats)]
+fn test_invalid_const_floats() {
+    for source in [
+        "const float kTooBig = 1e300;",
+        "const double kShift = 1.0 << 2;",
+        "const int32 kNotInt = 1.5;",
+        "const int32 kNotIntName = float.INFINITY;",
+    ] {
+        let module = parse_module(source).unwrap();
+        expect_true!(resolve_constants(&module).is_err(), "{}", source);
+    }
+    for source in ["const double k = 1e400;", "const double k = 1.5x;", "const double k = 1.5e;"] {
+        expect_true!(parse_module(source).is_err(), "{}", source);
+    }
+}
+
+#[gtest(MojomResolveTest, ConstSigns)]
+fn test_const_signs() {
+    let module = parse_module(
+        "const int32 kMin = -2147483648;
+         const int64 kMin64 = -9223372036854775808;
+         const int64 kMinHex = -0x8000000000000000;
+         const double kNegative = -1.0e3;
+         const float kNegativeExponent = -2.5e-1;
+         const int32 kPlus = +5;
+         const double kPlusDouble = +1.5;
+         const int32 kPlusMinus = +-3 - -+4;",
+    )
+    .unwrap();
+    let constants = resolve_constants(&module).unwrap();
+    expect_eq!(constants["kMin"], Value::Integer(i32::MIN.into()));
+    expect_eq!(constants["kMin64"], Value::Integer(i64::MIN.into()));
+    expect_eq!(constants["kMinHex"], Value::Integer(i64::MIN.into()));
+    expect_eq!(constants["kNegative"], Value::Double(-1000.0));
+    expect_eq!(constants["kNegativeExponent"], Value::Float(-0.25));
+    expect_eq!(constants["kPlus"], Value::Integer(5));
+    expect_eq!(constants["kPlusDouble"], Value::Double(1.5));
+    expect_eq!(constants["kPlusMinus"], Value::Integer(1));
+
+    // The sign is part of the literal, and a `+` is dropped.
+    let Declaration::Const(min) = &module.declarations[0] else { panic!() };
+    expect_eq!(min.value.kind, ValueExprKind::Integer(i32::MIN.into()));
+    expect_true!(deparse_module(&module).contains("const int32 kPlus = 5;"));
+
+    // One past the end is still out of range.
+    let module = parse_module("const int32 kTooSmall = -2147483649;").unwrap();
+    expect_true!(resolve_constants(&module).is_err());
+}
+
+#[gtest(MojomResolveTest, ConstStrings)]
+fn test_const_strings() {
+    let source = r#"const string kGreeting = "tab\there \"quoted\" back\\slash\n\x41\u00e9\u2603 é";
+                    const string kCopy = kGreeting;
+                    const string kEmpty = "";"#;
+    let module = parse_module(source).unwrap();
+    let constants = resolve_constants(&module).unwrap();
+    let expected = "tab\there \"quoted\" back\\slash\nA\u{e9}\u{2603} \u{e9}";
+    expect_eq!(constants["kGreeting"], Value::String(expected.to_string()));
+    expect_eq!(constants["kCopy"], Value::String(expected.to_string()));
+    expect_eq!(constants["kEmpty"], Value::String(String::new()));
+
+    // Deparsing escapes whatever needs it, and the result parses back to the
+    // same strings.
+    let deparsed = deparse_module(&module);
+    expect_true!(
+        deparsed.contains(r#"= "tab\there \"quoted\" back\\slash\nAé☃ é";"#),
+        "{}",
+        deparsed
+    );
+    let reparsed = parse_module(&deparsed).unwrap();
+    expect_eq!(without_spans(&reparsed), without_spans(&module));
+    expect_eq!(deparse_string("\u{1}\r"), r#""\u0001\r""#);
+
+    let module = parse_module("const string kNotString = 5;").unwrap();
+    expect_true!(resolve_constants(&module).is_err());
+}
+
+#[gtest(MojomResolveTest, ConstEnums)]
+fn test_const_enums() {
+    let module = parse_module(
+        "enum Color { RED, GREEN = 5, BLUE };
+         const Color kDefault = Color.BLUE;
+         const Color kCopy = kDefault;",
+    )
+    .unwrap();
+    let constants = resolve_constants(&module).unwrap();
+    let blue = Value::Enum { enum_name: "Color".to_string(), value: 6 };
+    expect_eq!(constants["kDefault"], blue);
+    expect_eq!(constants["kCopy"], blue);
+    expect_eq!(module.const_value("kCopy").unwrap(), Some(blue));
+
+    // Without the module, there's no way to look up the enum.
+    let Declaration::Const(constant) = &module.declarations[1] else { panic!() };
+    expect_true!(eval_const_expr(&constant.value, &constant.ty, &HashMap::new()).is_err());
+}
+
+#[gtest(MojomResolveTest, InvalidConstEnums)]
+fn test_invalid_const_enums() {
+    let err = |source: &str| {
+        let module = parse_module(source).unwrap();
+        format!("{:#}", resolve_constants(&module).unwrap_err())
+    };
+    expect_eq!(
+        err("enum Color { RED }; const Color kDefault = Color.PURPLE;"),
+        "Invalid value for constant `kDefault`: Enum `Color` has no value `PURPLE`"
+    );
+    expect_eq!(
+        err("enum Color { RED }; enum Size { BIG }; const Color kDefault = Size.BIG;"),
+        "Invalid value for constant `kDefault`: Unknown constant `Size.BIG`"
+    );
+    expect_eq!(
+        err("enum Color { RED }; const Color kDefault = 0;"),
+        "Invalid value for constant `kDefault`: Expected a value of enum `Color`"
+    );
+    expect_eq!(
+        err("enum Color { RED }; const int32 kZero = 0; const Color kDefault = kZero;"),
+        "Invalid value for constant `kDefault`: Constant `kZero` is not a value of enum `Color`"
+    );
+}
+
+#[gtest(MojomResolveTest, ArrayConstants)]
+fn test_array_constants() {
+    let module = parse_module(
+        "const int32 kThree = 3;
+         const array<int32> kDefaults = [1, 2, kThree];
+         const array<array<string>, 1> kNested = [[\"a\", \"b\"]];",
+    )
+    .unwrap();
+    let constants = resolve_constants(&module).unwrap();
+    expect_eq!(
+        constants["kDefaults"],
+        Value::Array(vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)])
+    );
+    expect_eq!(
+        constants["kNested"],
+        Value::Array(vec![Value::Array(vec![
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+        ])])
+    );
+
+    let err = |source: &str| {
+        let module = parse_module(source).unwrap();
+        format!("{:#}", resolve_constants(&module).unwrap_err())
+    };
+    expect_eq!(
+        err("const array<uint8> k = [1, 256];"),
+        "Invalid value for constant `k`: Invalid array element 1: \
+         Value 256 does not fit in type `uint8`"
+    );
+    expect_eq!(
+        err("const array<int32, 2> k = [1];"),
+        "Invalid value for constant `k`: Expected 2 elements, but found 1"
+    );
+    expect_eq!(
+        err("const int32 k = [1];"),
+        "Invalid value for constant `k`: Expected an integer, but found an array"
+    );
+}
+
+#[gtest(MojomResolveTest, FieldDefaults)]
+fn test_field_defaults() {
+    let module = parse_module(
+        "enum Color { RED, BLUE };
+         const int32 kFive = 5;
+         struct S {
+           int32 a = kFive;
+           string b;
+           Color c = Color.BLUE;
+           array<int32> d = [1, 2];
+         };",
+    )
+    .unwrap();
+    let Declaration::Struct(s) = &module.declarations[2] else { panic!() };
+    expect_eq!(
+        resolve_field_defaults(s, &module).unwrap(),
+        vec![
+            Some(Value::Integer(5)),
+            None,
+            Some(Value::Enum { enum_name: "Color".to_string(), value: 1 }),
+            Some(Value::Array(vec![Value::Integer(1), Value::Integer(2)])),
+        ]
+    );
+
+    // The parser accepts any value; the type is only checked here.
+    let module = parse_module("struct S { int8 a = 1; int8 b = \"two\"; };").unwrap();
+    let Declaration::Struct(s) = &module.declarations[0] else { panic!() };
+    expect_eq!(
+        format!("{:#}", resolve_field_defaults(s, &module).unwrap_err()),
+        "Invalid default for field `b` of `S`: Expected an integer, but found \"two\""
+    );
+}
+
+#[gtest(MojomResolveTest, InvalidStringEscapes)]
+fn test_invalid_string_escapes() {
+    for (source, bad_escape) in [
+        (r#"const string k = "ab\qc";"#, r"\q"),
+        (r#"const string k = "ab\x4";"#, r"\x4"),
+        (r#"const string k = "ab\xg1";"#, r"\xg1"),
+        (r#"const string k = "ab\u12";"#, r"\u12"),
+        (r#"const string k = "ab\ud800";"#, r"\ud800"),
+    ] {
+        let err = parse_module(source).unwrap_err().downcast::<ParseError>().unwrap();
+        expect_eq!(err.message, format!("Invalid escape sequence `{}`", bad_escape));
+        expect_eq!(err.span.text(source), bad_escape);
+
+        let (_, errors) = parse_module_with_recovery(source);
+        expect_eq!(errors[0].span.start, source.find(bad_escape).unwrap());
+    }
+}
+
+#[gtest(MojomResolveTest, ExtensibleEnumDefault)]
+fn test_extensible_enum_default() {
+    let e = parse_enum("[Extensible] enum Mode { OFF, [Default] AUTO, ON };");
+    expect_true!(e.extensible);
+    let defaults: Vec<bool> = e.values.iter().map(|enum_value| enum_value.is_default).collect();
+    expect_eq!(defaults, vec![false, true, false]);
+    expect_true!(validate_enum_default(&e).is_ok());
+    expect_true!(validate_enum_default(&parse_enum("enum Plain { A, B };")).is_ok());
+    expect_eq!(
+        deparse_module(&parse_module("[Extensible] enum Mode { [Default] A };").unwrap()),
+        "[Extensible] enum Mode {\n  [Default] A,\n};\n"
+    );
+}
+
+#[gtest(MojomResolveTest, InvalidEnumDefaults)]
+fn test_invalid_enum_defaults() {
+    let source = "[Extensible] enum Mode { [Default] OFF, AUTO, [Default] ON };";
+    let err = validate_enum_default(&parse_enum(source)).unwrap_err();
+    expect_true!(err.message.contains("both `OFF` and `ON`"), "{}", err);
+    expect_eq!(err.span.text(source), "[Default] ON");
+
+    let source = "[Extensible] enum Mode { OFF, ON };";
+    let err = validate_enum_default(&parse_enum(source)).unwrap_err();
+    expect_true!(err.message.contains("must have a [Default] value"), "{}", err);
+    expect_eq!(err.span.text(source), source.trim_end_matches(';'));
+
+    let source = "enum Mode { OFF, [Default] ON };";
+    let err = validate_enum_default(&parse_enum(source)).unwrap_err();
+    expect_true!(err.message.contains("is not [Extensible]"), "{}", err);
+    expect_eq!(err.span.text(source), "[Default] ON");
+}
+
+/// Create a fresh directory for `test_name`, containing the given files.
+fn write_files(test_name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("mojom_parser_{}_{}", test_name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, contents) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+#[gtest(MojomResolveTest, Imports)]
+fn test_imports() {
+    let dir = write_files(
+        "imports",
+        &[
+            ("root.mojom", "import \"shared/point.mojom\";\nstruct Line { Point a; Point b; };"),
+            ("include/shared/point.mojom", "struct Point { int32 x; int32 y; };"),
+        ],
+    );
+    let root = dir.join("root.mojom");
+    let modules = resolve_imports(&root, &[dir.join("missing"), dir.join("include")]).unwrap();
+    expect_eq!(modules.len(), 2);
+
+    let root_module = &modules[root.to_str().unwrap()];
+    expect_eq!(root_module.imports[0].path, "shared/point.mojom");
+    let point_module = &modules["shared/point.mojom"];
+    expect_true!(matches!(
+        point_module.declarations.as_slice(),
+        [Declaration::Struct(s)] if s.name == "Point"
+    ));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[gtest(MojomResolveTest, CircularImport)]
+fn test_circular_import() {
+    let dir = write_files(
+        "circular_import",
+        &[
+            ("a.mojom", "import \"b.mojom\";"),
+            ("b.mojom", "import \"c.mojom\";"),
+            ("c.mojom", "import \"b.mojom\";"),
+        ],
+    );
+    let err = format!(
+        "{:#}",
+        resolve_imports(&dir.join("a.mojom"), std::slice::from_ref(&dir)).unwrap_err()
+    );
+    expect_true!(err.contains("Circular import: b.mojom -> c.mojom -> b.mojom"), "{}", err);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[gtest(MojomResolveTest, MissingImport)]
+fn test_missing_import() {
+    let dir = write_files("missing_import", &[("a.mojom", "import \"nowhere/b.mojom\";")]);
+    let err = format!(
+        "{:#}",
+        resolve_imports(&dir.join("a.mojom"), std::slice::from_ref(&dir)).unwrap_err()
+    );
+    expect_true!(err.contains("`nowhere/b.mojom`"), "{}", err);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[gtest(MojomResolveTest, ImportsBeforeDeclarations)]
+fn test_imports_before_declarations() {
+    let module = parse_module("import \"a.mojom\"; import \"b/c.mojom\"; struct S {};").unwrap();
+    let paths: Vec<&str> = module.imports.iter().map(|import| import.path.as_str()).collect();
+    expect_eq!(paths, vec!["a.mojom", "b/c.mojom"]);
+    expect_eq!(
+        deparse_module(&module),
+        "import \"a.mojom\";\nimport \"b/c.mojom\";\n\nstruct S {\n};\n"
+    );
+
+    expect_true!(parse_module("struct S {}; import \"a.mojom\";").is_err());
+    expect_true!(parse_module("import a;").is_err());
+    expect_true!(parse_module("import \"a.mojom;").is_err());
+}
+
+#[gtest(MojomResolveTest, UndefinedTypes)]
+fn test_undefined_types() {
+    let source = "struct S { Point p; array<map<string, Missing>> m; pending_remote<S> r; };
+                  const int32 kX = 1;
+                  interface I { F(kX x) => (Color c); G(pending_receiver<I> i); };
+                  enum Color { RED };";
+    let module = parse_module(source).unwrap();
+    let errors = resolve_types(&module, &HashMap::new()).unwrap_err();
+    let errors: Vec<(&str, &str)> =
+        errors.iter().map(|error| (error.message.as_str(), error.span.text(source))).collect();
+    expect_eq!(
+        errors,
+        vec![
+            ("Undefined type `Point`", "Point"),
+            ("Undefined type `Missing`", "Missing"),
+            ("`S` is not an interface", "pending_remote<S>"),
+            ("`kX` is a constant, not a type", "kX"),
+        ]
+    );
+}
+
+#[gtest(MojomResolveTest, InterfaceForwardReferences)]
+fn test_interface_forward_references() {
+    // Interfaces may refer to each other before they are declared.
+    let module = parse_module(
+        "interface A { GetB() => (pending_remote<B> b); };
+         struct Holder { pending_receiver<C> c; };
+         interface B { GetA@2() => (pending_remote<A> a); };
+         interface C {};",
+    )
+    .unwrap();
+    expect_eq!(resolve_types(&module, &HashMap::new()), Ok(()));
+}
+
+#[gtest(MojomResolveTest, AssociatedInterfaces)]
+fn test_associated_interfaces() {
+    let module = parse_module(
+        "interface Foo {};
+         interface Bar {
+           Bind(pending_remote<Foo> foo, pending_associated_receiver<Foo> associated);
+           Get() => (pending_receiver<Foo> foo, pending_associated_remote<Foo> associated);
+           Plain(int32 x) => (pending_remote<Foo> foo);
+         };",
+    )
+    .unwrap();
+    expect_eq!(check_associated_interfaces(&module), Ok(()));
+
+    let source = "interface Foo {};
+                  interface Bar {
+                    Bind(int32 x, pending_associated_receiver<Foo> associated);
+                    Get(pending_remote<Foo> foo) => (pending_associated_remote<Foo> other);
+                  };";
+    let errors = check_associated_interfaces(&parse_module(source).unwrap()).unwrap_err();
+    let errors: Vec<(&str, &str)> =
+        errors.iter().map(|error| (error.message.as_str(), error.span.text(source))).collect();
+    // The pipe must be in the same message, so a request parameter doesn't
+    // count for the response.
+    expect_eq!(
+        errors,
+        vec![
+            (
+                "Method `Bar.Bind` has the associated endpoint `associated`, but no \
+                 pending_remote or pending_receiver parameter for it to share a message pipe \
+                 with",
+                "pending_associated_receiver<Foo> associated",
+            ),
+            (
+                "Method `Bar.Get` has the associated endpoint `other`, but no pending_remote or \
+                 pending_receiver parameter for it to share a message pipe with",
+                "pending_associated_remote<Foo> other",
+            ),
+        ]
+    );
+}
+
+#[gtest(MojomResolveTest, StructCycles)]
+fn test_struct_cycles() {
+    let source = "struct Node { int32 value; Node next; };";
+    let errors = check_struct_cycles(&parse_module(source).unwrap()).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|error| error.message.as_str()).collect();
+    expect_eq!(
+        messages,
+        vec!["Struct `Node` contains itself through non-nullable fields: Node.next -> Node"]
+    );
+
+    // Arrays, maps and nullable fields can end the recursion.
+    let module = parse_module(
+        "struct Tree { array<Tree> children; map<string, Tree> by_name; Tree? parent; };",
+    )
+    .unwrap();
+    expect_eq!(check_struct_cycles(&module), Ok(()));
+
+    let source = "struct A { B b; }; struct B { int8 x; C c; }; struct C { A a; };
+                  struct D { A a; };";
+    let errors = check_struct_cycles(&parse_module(source).unwrap()).unwrap_err();
+    let errors: Vec<(&str, &str)> =
+        errors.iter().map(|error| (error.message.as_str(), error.span.text(source))).collect();
+    // The cycle is only reported once, even though D leads into it too.
+    expect_eq!(
+        errors,
+        vec![(
+            "Struct `A` contains itself through non-nullable fields: A.b -> B.c -> C.a -> A",
+            "struct A { B b; }",
+        )]
+    );
+}
+
+#[gtest(MojomResolveTest, TypesFromImports)]
+fn test_types_from_imports() {
+    let dir = write_files(
+        "types_from_imports",
+        &[
+            ("a.mojom", "import \"b.mojom\"; struct A { B b; array<Color> colors; };"),
+            ("b.mojom", "struct B {}; enum Color { RED };"),
+        ],
+    );
+    let modules = resolve_imports(&dir.join("a.mojom"), std::slice::from_ref(&dir)).unwrap();
+    let root = &modules[&dir.join("a.mojom").to_string_lossy().to_string()];
+    expect_eq!(resolve_types(root, &modules), Ok(()));
+
+    // Without the import, the same types aren't found.
+    let errors = resolve_types(root, &HashMap::new()).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|error| error.message.as_str()).collect();
+    expect_eq!(
+        messages,
+        vec![
+            "Imported file `b.mojom` was not loaded",
+            "Undefined type `B`",
+            "Undefined type `Color`"
+        ]
+    );
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[gtest(MojomResolveTest, ImportParseErrors)]
+fn test_import_parse_errors() {
+    let dir = write_files(
+        "import_parse_errors",
+        &[("a.mojom", "import \"b.mojom\";"), ("b.mojom", "struct B {\n  int32 x\n};")],
+    );
+    let err = resolve_imports(&dir.join("a.mojom"), std::slice::from_ref(&dir)).unwrap_err();
+    let b = dir.join("b.mojom");
+    expect_eq!(
+        err.to_string(),
+        format!(
+            "{}:3:1: Expected `;` while parsing a struct field, but found `}}`\n}};\n^",
+            b.display()
+        )
+    );
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[gtest(MojomResolveTest, ConstValue)]
+fn test_const_value() {
+    let module = parse_module(
+        "const int32 kDerived = kBase * 2 + kOffset;
+         const int32 kBase = 0x10;
+         const int32 kOffset = kBase - 1;
+         const string kName = \"name\";
+         struct kNotAConstant {};",
+    )
+    .unwrap();
+    expect_eq!(module.const_value("kBase").unwrap(), Some(Value::Integer(0x10)));
+    expect_eq!(module.const_value("kDerived").unwrap(), Some(Value::Integer(0x2f)));
+    expect_eq!(module.const_value("kName").unwrap(), Some(Value::String("name".to_string())));
+    expect_eq!(module.const_value("kMissing").unwrap(), None);
+    expect_eq!(module.const_value("kNotAConstant").unwrap(), None);
+}
+
+#[gtest(MojomResolveTest, ConstValueCycle)]
+fn test_const_value_cycle() {
+    let module = parse_module(
+        "const int32 kA = kB + 1;
+         const int32 kB = kC * 2;
+         const int32 kC = kA;
+         const int32 kSelf = kSelf;",
+    )
+    .unwrap();
+    let err = module.const_value("kB").unwrap_err().to_string();
+    expect_eq!(err, "Circular constant definition: kB -> kC -> kA -> kB");
+    let err = module.const_value("kSelf").unwrap_err().to_string();
+    expect_eq!(err, "Circular constant definition: kSelf -> kSelf");
+}
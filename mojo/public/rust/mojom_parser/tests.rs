@@ -0,0 +1,134 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+mod ast_tests;
+mod builders_tests;
+mod diff_tests;
+#[cfg(feature = "serde")]
+mod json_tests;
+mod lexer_tests;
+mod pack_tests;
+mod parse_source_tests;
+mod resolve_tests;
+
+chromium::import! {
+    "//mojo/public/rust/mojom_parser";
+}
+
+use mojom_parser::{
+    Const, Declaration, Field, Module, Span, Type, TypeKind, ValueExpr, ValueExprKind,
+};
+
+/// Return `module` with every span reset to the default, for comparing modules
+/// parsed from differently formatted source.
+fn without_spans(module: &Module) -> Module {
+    let mut module = module.clone();
+    for import in &mut module.imports {
+        import.span = Span::default();
+    }
+    for declaration in &mut module.declarations {
+        match declaration {
+            Declaration::Struct(s) => {
+                s.span = Span::default();
+                s.fields = without_field_spans(&s.fields);
+            }
+            Declaration::Union(u) => {
+                u.span = Span::default();
+                u.fields = without_field_spans(&u.fields);
+            }
+            Declaration::Interface(interface) => {
+                interface.span = Span::default();
+                for method in &mut interface.methods {
+                    method.span = Span::default();
+                    method.parameters = without_field_spans(&method.parameters);
+                    if let Some(parameters) = &mut method.response_parameters {
+                        *parameters = without_field_spans(parameters);
+                    }
+                }
+            }
+            Declaration::Enum(e) => {
+                e.span = Span::default();
+                for value in &mut e.values {
+                    value.span = Span::default();
+                    if let Some(value) = &mut value.value {
+                        *value = without_value_spans(value);
+                    }
+                }
+            }
+            Declaration::Const(constant) => *constant = without_const_spans(constant),
+            Declaration::Feature(feature) => {
+                feature.span = Span::default();
+                for constant in &mut feature.constants {
+                    *constant = without_const_spans(constant);
+                }
+            }
+        }
+    }
+    module
+}
+
+/// Return `fields` with their spans reset to the default, like without_spans.
+fn without_field_spans(fields: &[Field]) -> Vec<Field> {
+    fields
+        .iter()
+        .map(|field| Field {
+            ty: without_type_spans(&field.ty),
+            default: field.default.as_ref().map(without_value_spans),
+            span: Span::default(),
+            ..field.clone()
+        })
+        .collect()
+}
+
+fn without_const_spans(constant: &Const) -> Const {
+    Const {
+        ty: without_type_spans(&constant.ty),
+        value: without_value_spans(&constant.value),
+        span: Span::default(),
+        ..constant.clone()
+    }
+}
+
+/// Return `ty` with its span and those of any nested types reset to the
+/// default, like without_spans.
+fn without_type_spans(ty: &Type) -> Type {
+    let kind = match &ty.kind {
+        TypeKind::Array { element_type, length } => TypeKind::Array {
+            element_type: Box::new(without_type_spans(element_type)),
+            length: *length,
+        },
+        TypeKind::Map { key, value } => TypeKind::Map {
+            key: Box::new(without_type_spans(key)),
+            value: Box::new(without_type_spans(value)),
+        },
+        kind => kind.clone(),
+    };
+    Type { kind, nullable: ty.nullable, span: Span::default() }
+}
+
+/// A value expression with no span, for comparing against the result of
+/// without_value_spans.
+fn unspanned(kind: ValueExprKind) -> ValueExpr {
+    ValueExpr { kind, span: Span::default() }
+}
+
+/// Return `expr` with its span and those of its operands reset to the default,
+/// like without_spans.
+fn without_value_spans(expr: &ValueExpr) -> ValueExpr {
+    let kind = match &expr.kind {
+        ValueExprKind::Array(elements) => {
+            ValueExprKind::Array(elements.iter().map(without_value_spans).collect())
+        }
+        ValueExprKind::Unary { op, operand } => {
+            ValueExprKind::Unary { op: *op, operand: Box::new(without_value_spans(operand)) }
+        }
+        ValueExprKind::Binary { op, lhs, rhs } => ValueExprKind::Binary {
+            op: *op,
+            lhs: Box::new(without_value_spans(lhs)),
+            rhs: Box::new(without_value_spans(rhs)),
+        },
+        kind => kind.clone(),
+    };
+    ValueExpr { kind, span: Span::default() }
+}
@@ -0,0 +1,460 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! The command line tool, as a library function so that it can be tested.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::AddAssign;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::etc1::blob_format;
+use crate::resize::{box_downscale, fit_dimensions};
+use crate::stats::{block_stats, write_stats_csv};
+use crate::{
+    decode_image, perceptual_psnr, psnr, write_png, EncodeOptions, Error, Etc1Image, Format,
+    Quality, RgbaImage,
+};
+
+/// The usage line printed for bad arguments.
+pub const USAGE: &str = "Usage: texture_compressor [--format etc1|etc2] \
+                         [--alpha-mode none|separate-etc1] \
+                         [--quality fast|normal|best] [--perceptual] [--dither] [--mipmaps] \
+                         [--max-size <w>x<h>] [--force-rgba] [--metrics] [--ktx] \
+                         [--verify-psnr <db>] [--stats] [--timing] [--verbose] \
+                         (<input.png|jpg|webp> <output.png> [etc1_output.etc1|ktx] | \
+                         <input.etc1> <output.png> | \
+                         <input_dir> <output_dir>)";
+
+/// Options set by flags on the command line.
+struct Options {
+    format: Format,
+    encode: EncodeOptions,
+    /// Whether to generate mipmaps in the compressed blob.
+    mipmaps: bool,
+    /// The largest width and height to compress, if any. Larger inputs are
+    /// scaled down to fit before compressing.
+    max_size: Option<(u32, u32)>,
+    /// Whether to print quality metrics for the compressed image.
+    metrics: bool,
+    /// Whether to write the compressed image as a KTX file instead of a blob.
+    ktx: bool,
+    /// The lowest PSNR in dB to accept for the compressed image, if any.
+    verify_psnr: Option<f64>,
+    /// Whether to write per-block statistics as CSV next to the output.
+    stats: bool,
+    /// Whether to write the output PNG as RGBA even if the format has no
+    /// alpha, rather than as RGB.
+    force_rgba: bool,
+    /// Whether to print how long each stage took.
+    timing: bool,
+}
+
+/// Return the pixels of an RGBA image as interleaved bytes.
+fn rgba_bytes(pixels: &[u32]) -> Vec<u8> {
+    pixels.iter().flat_map(|pixel| pixel.to_le_bytes()).collect()
+}
+
+/// Return the value following a flag, or an error naming the flag.
+fn flag_value<'a>(
+    args: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<&'a str, Error> {
+    args.next()
+        .map(String::as_str)
+        .ok_or_else(|| Error::BadArguments(format!("{} needs a value", flag)))
+}
+
+/// How long each stage of converting an image took, or of converting every
+/// image in a batch. Stages which didn't run, like encoding for a blob input,
+/// took zero time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// Decoding the input PNG, JPEG or WebP file, and scaling it down for
+    /// `--max-size`.
+    pub decode_input: Duration,
+    /// Compressing the image, including any mipmaps.
+    pub encode_blocks: Duration,
+    /// Decompressing level 0 again, for the output PNG.
+    pub decode_blocks: Duration,
+    /// Writing the output PNG.
+    pub encode_png: Duration,
+}
+
+impl Timings {
+    /// Run `f`, adding the time it took to the stage that `stage` picks.
+    fn time<T>(
+        &mut self,
+        stage: impl Fn(&mut Timings) -> &mut Duration,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let start = Instant::now();
+        let result = f();
+        *stage(self) += start.elapsed();
+        result
+    }
+}
+
+impl AddAssign for Timings {
+    fn add_assign(&mut self, other: Timings) {
+        self.decode_input += other.decode_input;
+        self.encode_blocks += other.encode_blocks;
+        self.decode_blocks += other.decode_blocks;
+        self.encode_png += other.encode_png;
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "Input decode: {:.2} ms, block encode: {:.2} ms, block decode: {:.2} ms, \
+             PNG encode: {:.2} ms",
+            ms(self.decode_input),
+            ms(self.encode_blocks),
+            ms(self.decode_blocks),
+            ms(self.encode_png)
+        )
+    }
+}
+
+/// What the command line tool found, for main to print to stdout once it's
+/// done.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    /// How long each stage took, over every image converted.
+    pub timings: Timings,
+    /// Whether `--timing` asked for `timings` to be printed.
+    pub show_timings: bool,
+    /// With `--metrics`, the PSNR in dB of each compressed image, along with
+    /// the space it was measured in.
+    pub psnrs: Vec<(&'static str, f64)>,
+    /// For an input directory, how many images were converted.
+    pub converted: Option<usize>,
+}
+
+impl AddAssign for Report {
+    fn add_assign(&mut self, other: Report) {
+        self.timings += other.timings;
+        self.psnrs.extend(other.psnrs);
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (space, psnr) in &self.psnrs {
+            writeln!(f, "PSNR ({}): {:.2} dB", space, psnr)?;
+        }
+        if let Some(converted) = self.converted {
+            writeln!(f, "Converted {} files", converted)?;
+        }
+        if self.show_timings {
+            writeln!(f, "{}", self.timings)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the command line tool with `args`, not including the program name,
+/// returning what it found.
+pub fn run(args: &[String]) -> Result<Report, Error> {
+    let mut options = Options {
+        format: Format::Etc1,
+        encode: EncodeOptions::default(),
+        mipmaps: false,
+        max_size: None,
+        metrics: false,
+        ktx: false,
+        verify_psnr: None,
+        stats: false,
+        force_rgba: false,
+        timing: false,
+    };
+    // Kept apart from `options.encode` so that a later `--quality` does not
+    // reset them.
+    let mut perceptual = false;
+    let mut dither = false;
+    let mut separate_alpha = false;
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                options.format = match flag_value(&mut args, arg)? {
+                    "etc1" => Format::Etc1,
+                    "etc2" => Format::Etc2Rgba,
+                    value => {
+                        return Err(Error::BadArguments(format!("Unknown format: {}", value)));
+                    }
+                }
+            }
+            "--quality" => {
+                options.encode = EncodeOptions::with_quality(match flag_value(&mut args, arg)? {
+                    "fast" => Quality::Fast,
+                    "normal" => Quality::Normal,
+                    "best" => Quality::Best,
+                    value => {
+                        return Err(Error::BadArguments(format!("Unknown quality: {}", value)));
+                    }
+                })
+            }
+            "--alpha-mode" => {
+                separate_alpha = match flag_value(&mut args, arg)? {
+                    "none" => false,
+                    "separate-etc1" => true,
+                    value => {
+                        return Err(Error::BadArguments(format!("Unknown alpha mode: {}", value)));
+                    }
+                }
+            }
+            "--perceptual" => perceptual = true,
+            "--dither" => dither = true,
+            "--mipmaps" => options.mipmaps = true,
+            "--max-size" => {
+                let value = flag_value(&mut args, arg)?;
+                let size = value
+                    .split_once('x')
+                    .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+                options.max_size = match size {
+                    Some((width, height)) if width > 0 && height > 0 => Some((width, height)),
+                    _ => return Err(Error::BadArguments(format!("Invalid size: {}", value))),
+                };
+            }
+            "--stats" => options.stats = true,
+            "--force-rgba" => options.force_rgba = true,
+            "--timing" => options.timing = true,
+            // Handled by main, which sets up logging before calling this.
+            "--verbose" => (),
+            "--metrics" => options.metrics = true,
+            "--ktx" => options.ktx = true,
+            "--verify-psnr" => {
+                let value = flag_value(&mut args, arg)?;
+                options.verify_psnr = Some(value.parse().map_err(|_| {
+                    Error::BadArguments(format!("Invalid PSNR threshold: {}", value))
+                })?);
+            }
+            flag if flag.starts_with("--") => {
+                return Err(Error::BadArguments(format!("Unknown flag: {}", flag)));
+            }
+            _ => positional.push(Path::new(arg)),
+        }
+    }
+    options.encode.perceptual = perceptual;
+    options.encode.dither = dither;
+    if separate_alpha {
+        if options.format != Format::Etc1 || options.ktx {
+            return Err(Error::BadArguments(
+                "--alpha-mode separate-etc1 only works with ETC1 blobs".to_string(),
+            ));
+        }
+        options.format = Format::Etc1SeparateAlpha;
+    }
+    let (input_path, output_path, etc1_output_path) = match positional[..] {
+        [input_path, output_path] => (input_path, output_path, None),
+        [input_path, output_path, etc1_output_path] => {
+            (input_path, output_path, Some(etc1_output_path))
+        }
+        _ => {
+            return Err(Error::BadArguments(format!(
+                "Expected 2 or 3 paths, got {}",
+                positional.len()
+            )));
+        }
+    };
+    if input_path.is_dir() {
+        if etc1_output_path.is_some() {
+            return Err(Error::BadArguments(
+                "Expected only an output directory for an input directory".to_string(),
+            ));
+        }
+        convert_dir(input_path, output_path, &options)
+    } else {
+        convert(input_path, output_path, etc1_output_path, &options)
+    }
+    .map(|report| Report { show_timings: options.timing, ..report })
+}
+
+/// Convert each PNG in `input_dir` as if by `convert`, writing the outputs to
+/// `output_dir` with the same stem and the extensions .png and .etc1 or .ktx.
+///
+/// Failures are logged and skipped, then reported as a whole at the end.
+/// Returns what every conversion found, combined.
+fn convert_dir(input_dir: &Path, output_dir: &Path, options: &Options) -> Result<Report, Error> {
+    std::fs::create_dir_all(output_dir)?;
+    let mut input_paths = vec![];
+    for entry in std::fs::read_dir(input_dir)? {
+        let path = entry?.path();
+        if path.is_file()
+            && path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+        {
+            input_paths.push(path);
+        }
+    }
+    input_paths.sort();
+
+    let (mut succeeded, mut failed) = (0, 0);
+    let mut report = Report::default();
+    for input_path in &input_paths {
+        let output_path = output_dir.join(input_path.file_name().unwrap()).with_extension("png");
+        let etc1_output_path = output_path.with_extension(if options.ktx { "ktx" } else { "etc1" });
+        match convert(input_path, &output_path, Some(&etc1_output_path), options) {
+            Ok(file_report) => {
+                succeeded += 1;
+                report += file_report;
+            }
+            Err(err) => {
+                log::error!("Failed to convert {}: {}", input_path.display(), err);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(Error::BatchFailed { succeeded, failed });
+    }
+    Ok(Report { converted: Some(succeeded), ..report })
+}
+
+/// Compress `input_path`, then write the decompressed image to `output_path`
+/// so that it shows the compression loss, and the compressed blob or KTX file
+/// to `etc1_output_path` if given. With `--stats`, per-block statistics are
+/// written next to `output_path`, with a `.csv` extension.
+///
+/// If `input_path` is an ETC1 or ETC2 blob instead, by extension or by its
+/// magic bytes, it is decoded to `output_path` without compressing anything.
+///
+/// Returns how long each stage took, and the PSNR with `--metrics`.
+fn convert(
+    input_path: &Path,
+    output_path: &Path,
+    etc1_output_path: Option<&Path>,
+    options: &Options,
+) -> Result<Report, Error> {
+    let Options { format, ref encode, mipmaps, max_size, metrics, ktx, verify_psnr, stats, .. } =
+        *options;
+    let mut timings = Timings::default();
+    let input = std::fs::read(input_path).map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => Error::FileNotFound(input_path.to_owned()),
+        _ => Error::Io(err),
+    })?;
+    let is_blob = input_path.extension().is_some_and(|extension| extension == "etc1")
+        || blob_format(&input).is_some();
+    if is_blob {
+        if etc1_output_path.is_some() {
+            return Err(Error::BadArguments(
+                "Expected only an output path for an ETC1 blob input".to_string(),
+            ));
+        }
+        let image = Etc1Image::read_blob(&input)?;
+        log::info!(
+            "Decoding {:?} blob {} ({}x{})",
+            image.format,
+            input_path.display(),
+            image.width,
+            image.height
+        );
+        let decoded = timings.time(|t| &mut t.decode_blocks, || image.decode());
+        log::info!("Writing {}", output_path.display());
+        timings.time(
+            |t| &mut t.encode_png,
+            || write_decoded_png(&image, &decoded, output_path, options.force_rgba),
+        )?;
+        return Ok(Report { timings, ..Report::default() });
+    }
+    log::info!("Decoding {}", input_path.display());
+    let RgbaImage { mut width, mut height, mut pixels } =
+        timings.time(|t| &mut t.decode_input, || decode_image(&input))?;
+    if let Some((max_width, max_height)) = max_size {
+        let (dst_width, dst_height) = fit_dimensions(width, height, max_width, max_height);
+        if (dst_width, dst_height) != (width, height) {
+            log::info!(
+                "Resizing {} from {}x{} to {}x{}",
+                input_path.display(),
+                width,
+                height,
+                dst_width,
+                dst_height
+            );
+            pixels = timings.time(
+                |t| &mut t.decode_input,
+                || box_downscale(&pixels, width, height, dst_width, dst_height),
+            );
+            (width, height) = (dst_width, dst_height);
+        }
+    }
+    if format == Format::Etc1 && pixels.iter().any(|pixel| pixel >> 24 != 0xFF) {
+        log::warn!("ETC1 has no alpha, so the alpha of {} will be dropped", input_path.display());
+    }
+    log::info!("Encoding {} ({}x{}) as {:?}", input_path.display(), width, height, format);
+    let image = timings.time(
+        |t| &mut t.encode_blocks,
+        || {
+            if mipmaps {
+                Etc1Image::encode_with_mipmaps(&pixels, width, height, format, encode)
+            } else {
+                Etc1Image::encode(&pixels, width, height, format, encode)
+            }
+        },
+    );
+    log::debug!("Encoded {} mip levels", image.levels().len());
+    if let Some(etc1_output_path) = etc1_output_path {
+        log::info!("Writing {}", etc1_output_path.display());
+        let mut writer = BufWriter::new(File::create(etc1_output_path)?);
+        if ktx {
+            image.write_ktx(&mut writer)?;
+        } else {
+            image.write_blob(&mut writer)?;
+        }
+        writer.flush()?;
+    }
+    if stats {
+        let mut writer = BufWriter::new(File::create(output_path.with_extension("csv"))?);
+        write_stats_csv(&mut writer, &block_stats(&image, &pixels))?;
+        writer.flush()?;
+    }
+    let decoded = timings.time(|t| &mut t.decode_blocks, || image.decode());
+    // PSNR is measured in the same space as the encoder's error metric.
+    let space = if encode.perceptual { "perceptual" } else { "linear RGB" };
+    let psnr = (metrics || verify_psnr.is_some()).then(|| {
+        let (original, decoded) = (rgba_bytes(&pixels), rgba_bytes(&decoded));
+        if encode.perceptual {
+            perceptual_psnr(&original, &decoded)
+        } else {
+            psnr(&original, &decoded)
+        }
+    });
+    // The output is written even if verification fails, to show what went
+    // wrong.
+    log::info!("Writing {}", output_path.display());
+    timings.time(
+        |t| &mut t.encode_png,
+        || write_decoded_png(&image, &decoded, output_path, options.force_rgba),
+    )?;
+    match (psnr, verify_psnr) {
+        (Some(psnr), Some(threshold)) if psnr < threshold => {
+            Err(Error::PsnrBelowThreshold { psnr, threshold })
+        }
+        _ => {
+            let psnrs = psnr.filter(|_| metrics).map(|psnr| (space, psnr)).into_iter().collect();
+            Ok(Report { timings, psnrs, ..Report::default() })
+        }
+    }
+}
+
+/// Write `decoded`, the decompressed level 0 of `image`, to `path` as a PNG.
+///
+/// The PNG's color type depends on the compressed format, not the input: RGB
+/// for ETC1, which has no alpha, and RGBA otherwise or with `force_rgba`.
+fn write_decoded_png(
+    image: &Etc1Image,
+    decoded: &[u32],
+    path: &Path,
+    force_rgba: bool,
+) -> Result<(), Error> {
+    let writer = BufWriter::new(File::create(path)?);
+    let alpha = force_rgba || image.format.has_alpha();
+    write_png(writer, decoded, image.width, image.height, alpha)
+}
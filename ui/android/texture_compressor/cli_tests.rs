@@ -0,0 +1,308 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use std::cell::RefCell;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use std::time::Duration;
+
+use texture_compressor::cli::run;
+use texture_compressor::{read_png, write_png, Error, Etc1Image};
+
+/// An empty directory for the files of one test, which is removed when it's
+/// dropped, so that tests clean up after themselves even if they fail.
+struct TempDir(PathBuf);
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Return an empty directory for the files of the test `name`.
+fn temp_dir(name: &str) -> TempDir {
+    let dir =
+        std::env::temp_dir().join(format!("texture_compressor_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+}
+
+/// Turn paths and flags into the arguments of `run`.
+fn args(args: &[&dyn AsRef<Path>]) -> Vec<String> {
+    args.iter().map(|arg| arg.as_ref().to_str().unwrap().to_string()).collect()
+}
+
+thread_local! {
+    /// The messages logged on this thread by capture_logs, if it's running.
+    static LOGGED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Keeps each log record on the thread which logged it, since tests run in
+/// parallel.
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        LOGGED.with_borrow_mut(|logged| {
+            if let Some(logged) = logged {
+                logged.push(format!("{}: {}", record.level(), record.args()));
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Return the messages logged on this thread while running `f`, with their
+/// levels.
+fn capture_logs(f: impl FnOnce()) -> Vec<String> {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&CapturingLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+    });
+    LOGGED.set(Some(vec![]));
+    f();
+    LOGGED.take().unwrap()
+}
+
+#[gtest(TextureCompressorTest, CliRun)]
+fn test_cli_run() {
+    let dir = temp_dir("run");
+    let input = dir.join("input.png");
+    let pixels = vec![0xFF2080C0; 8 * 4];
+    write_png(std::fs::File::create(&input).unwrap(), &pixels, 8, 4, false).unwrap();
+    let (output, blob) = (dir.join("output.png"), dir.join("output.etc1"));
+
+    expect_true!(run(&args(&[&input, &output, &blob])).is_ok());
+    expect_true!(output.exists());
+    expect_true!(blob.exists());
+}
+
+#[gtest(TextureCompressorTest, CliTiming)]
+fn test_cli_timing() {
+    let dir = temp_dir("timing");
+    let input = dir.join("input.png");
+    let pixels: Vec<u32> = (0..64 * 64).map(|i| 0xFF000000 | (i * 0x010203)).collect();
+    write_png(std::fs::File::create(&input).unwrap(), &pixels, 64, 64, false).unwrap();
+    let (output, blob) = (dir.join("output.png"), dir.join("output.etc1"));
+
+    let report = run(&args(&[&"--timing", &input, &output, &blob])).unwrap();
+    expect_true!(report.show_timings);
+    expect_gt!(report.timings.encode_blocks, Duration::ZERO);
+    expect_gt!(report.timings.decode_input, Duration::ZERO);
+    expect_true!(report.to_string().starts_with("Input decode: "), "{}", report);
+    // Decoding a blob doesn't encode anything.
+    let report = run(&args(&[&blob, &output])).unwrap();
+    expect_false!(report.show_timings);
+    expect_eq!(report.timings.encode_blocks, Duration::ZERO);
+    expect_gt!(report.timings.decode_blocks, Duration::ZERO);
+    expect_eq!(report.to_string(), "");
+}
+
+#[gtest(TextureCompressorTest, CliMaxSize)]
+fn test_cli_max_size() {
+    let dir = temp_dir("max_size");
+    let input = dir.join("input.png");
+    write_png(std::fs::File::create(&input).unwrap(), &[0xFF2080C0; 100 * 50], 100, 50, false)
+        .unwrap();
+    let (output, blob) = (dir.join("output.png"), dir.join("output.etc1"));
+    let dimensions = |max_size: &str| {
+        run(&args(&[&"--max-size", &max_size, &input, &output, &blob])).unwrap();
+        let image = Etc1Image::read_blob(&std::fs::read(&blob).unwrap()).unwrap();
+        let decoded =
+            read_png(std::io::BufReader::new(std::fs::File::open(&output).unwrap())).unwrap();
+        expect_eq!((decoded.width, decoded.height), (image.width, image.height));
+        (image.width, image.height)
+    };
+    expect_eq!(dimensions("64x64"), (64, 32));
+    // An input which already fits is left alone.
+    expect_eq!(dimensions("128x128"), (100, 50));
+
+    for bad in ["64", "64x", "0x64", "ax64"] {
+        let result = run(&args(&[&"--max-size", &bad, &input, &output]));
+        expect_true!(matches!(result, Err(Error::BadArguments(_))), "{}", bad);
+    }
+}
+
+#[gtest(TextureCompressorTest, CliVerboseLogging)]
+fn test_cli_verbose_logging() {
+    let dir = temp_dir("verbose_logging");
+    let input = dir.join("input.png");
+    write_png(std::fs::File::create(&input).unwrap(), &[0xFF2080C0; 100 * 50], 100, 50, false)
+        .unwrap();
+    let (output, blob) = (dir.join("output.png"), dir.join("output.etc1"));
+    let logged = capture_logs(|| {
+        run(&args(&[&"--verbose", &"--max-size", &"64x64", &input, &output, &blob])).unwrap();
+    });
+    let (input, output, blob) = (input.display(), output.display(), blob.display());
+    expect_eq!(
+        logged,
+        vec![
+            format!("INFO: Decoding {}", input),
+            "DEBUG: Normalizing 100x50 pixels with 3 channels to RGBA".to_string(),
+            format!("INFO: Resizing {} from 100x50 to 64x32", input),
+            format!("INFO: Encoding {} (64x32) as Etc1", input),
+            "DEBUG: Encoded 1 mip levels".to_string(),
+            format!("INFO: Writing {}", blob),
+            format!("INFO: Writing {}", output),
+        ]
+    );
+}
+
+#[gtest(TextureCompressorTest, CliOutputColorType)]
+fn test_cli_output_color_type() {
+    let dir = temp_dir("output_color_type");
+    let (input, output) = (dir.join("input.png"), dir.join("output.png"));
+    write_png(std::fs::File::create(&input).unwrap(), &[0xFF2080C0; 8 * 8], 8, 8, false).unwrap();
+    // The color type byte of the IHDR chunk, after the signature, the chunk's
+    // length and type, the dimensions and the bit depth.
+    let color_type = |flags: &[&dyn AsRef<Path>]| {
+        run(&args(&[flags, &[&input, &output]].concat())).unwrap();
+        std::fs::read(&output).unwrap()[25]
+    };
+    const RGB: u8 = 2;
+    const RGBA: u8 = 6;
+    expect_eq!(color_type(&[&"--format", &"etc2"]), RGBA);
+    expect_eq!(color_type(&[&"--format", &"etc1"]), RGB);
+    expect_eq!(color_type(&[&"--format", &"etc1", &"--force-rgba"]), RGBA);
+}
+
+#[gtest(TextureCompressorTest, CliVerifyPsnr)]
+fn test_cli_verify_psnr() {
+    let dir = temp_dir("verify_psnr");
+    let (flat, noisy) = (dir.join("flat.png"), dir.join("noisy.png"));
+    write_png(std::fs::File::create(&flat).unwrap(), &[0xFF2080C0; 8 * 8], 8, 8, false).unwrap();
+    // Pixels from a xorshift generator, which ETC1 can't come close to.
+    let mut state = 0x12345678u32;
+    let pixels: Vec<u32> = (0..8 * 8)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state | 0xFF000000
+        })
+        .collect();
+    write_png(std::fs::File::create(&noisy).unwrap(), &pixels, 8, 8, false).unwrap();
+    let output = dir.join("output.png");
+
+    expect_eq!(run(&args(&[&"--verify-psnr", &"30", &flat, &output])).unwrap().psnrs, vec![]);
+    let report = run(&args(&[&"--metrics", &noisy, &output])).unwrap();
+    expect_eq!(report.psnrs.len(), 1);
+    expect_eq!(report.psnrs[0].0, "linear RGB");
+    expect_true!(report.to_string().starts_with("PSNR (linear RGB): "), "{}", report);
+    let result = run(&args(&[&"--verify-psnr", &"30", &noisy, &output]));
+    expect_true!(
+        matches!(result, Err(Error::PsnrBelowThreshold { psnr, threshold: 30.0 }) if psnr < 30.0)
+    );
+    let result = run(&args(&[&"--verify-psnr", &"high", &flat, &output]));
+    expect_true!(matches!(result, Err(Error::BadArguments(_))));
+}
+
+#[gtest(TextureCompressorTest, CliBatch)]
+fn test_cli_batch() {
+    let dir = temp_dir("batch");
+    let (input_dir, output_dir) = (dir.join("input"), dir.join("output"));
+    std::fs::create_dir(&input_dir).unwrap();
+    for name in ["a.png", "b.PNG"] {
+        let file = std::fs::File::create(input_dir.join(name)).unwrap();
+        write_png(file, &[0xFF2080C0; 8 * 4], 8, 4, false).unwrap();
+    }
+    std::fs::write(input_dir.join("corrupt.png"), b"not a png").unwrap();
+    // Files without a .png extension are ignored.
+    std::fs::write(input_dir.join("notes.txt"), b"not a png either").unwrap();
+
+    let result = run(&args(&[&input_dir, &output_dir]));
+    expect_true!(matches!(result, Err(Error::BatchFailed { succeeded: 2, failed: 1 })));
+    for name in ["a.png", "a.etc1", "b.png", "b.etc1"] {
+        expect_true!(output_dir.join(name).exists(), "{}", name);
+    }
+    expect_false!(output_dir.join("notes.etc1").exists());
+
+    std::fs::remove_file(input_dir.join("corrupt.png")).unwrap();
+    let report = run(&args(&[&"--ktx", &input_dir, &output_dir])).unwrap();
+    expect_eq!(report.converted, Some(2));
+    expect_eq!(report.to_string(), "Converted 2 files\n");
+    expect_true!(output_dir.join("a.ktx").exists());
+}
+
+#[gtest(TextureCompressorTest, CliMissingInput)]
+fn test_cli_missing_input() {
+    let dir = temp_dir("missing_input");
+    let input = dir.join("missing.png");
+    let result = run(&args(&[&input, &dir.join("output.png")]));
+    expect_true!(matches!(result, Err(Error::FileNotFound(path)) if path == input));
+}
+
+#[gtest(TextureCompressorTest, CliWrongArgumentCount)]
+fn test_cli_wrong_argument_count() {
+    for paths in [&["a.png"][..], &["a.png", "b.png", "c.etc1", "d"][..]] {
+        let result = run(&paths.iter().map(|path| path.to_string()).collect::<Vec<_>>());
+        expect_true!(matches!(result, Err(Error::BadArguments(_))));
+    }
+    expect_true!(matches!(run(&[]), Err(Error::BadArguments(_))));
+}
+
+#[gtest(TextureCompressorTest, CliBadFlags)]
+fn test_cli_bad_flags() {
+    for flags in [&["--format", "bc1"][..], &["--quality"][..], &["--bogus"][..]] {
+        let mut args: Vec<String> = flags.iter().map(|flag| flag.to_string()).collect();
+        args.extend(["a.png".to_string(), "b.png".to_string()]);
+        expect_true!(matches!(run(&args), Err(Error::BadArguments(_))));
+    }
+}
+
+#[gtest(TextureCompressorTest, CliDecodeBlob)]
+fn test_cli_decode_blob() {
+    let dir = temp_dir("decode_blob");
+    let input = dir.join("input.png");
+    let pixels: Vec<u32> = (0..8 * 8).map(|i| 0xFF000000 | (i * 0x030507)).collect();
+    write_png(std::fs::File::create(&input).unwrap(), &pixels, 8, 8, false).unwrap();
+    let (encoded, blob) = (dir.join("encoded.png"), dir.join("input.etc1"));
+    expect_true!(run(&args(&[&input, &encoded, &blob])).is_ok());
+
+    // The blob decodes to the same pixels that the encoder wrote out.
+    let decoded = dir.join("decoded.png");
+    expect_true!(run(&args(&[&blob, &decoded])).is_ok());
+    let read =
+        |path| read_png(std::io::BufReader::new(std::fs::File::open(path).unwrap())).unwrap();
+    let image = Etc1Image::read_blob(&std::fs::read(&blob).unwrap()).unwrap();
+    expect_eq!(read(&decoded).pixels, image.decode());
+    expect_eq!(read(&decoded), read(&encoded));
+
+    // The magic bytes are enough to recognize a blob.
+    let renamed = dir.join("blob.bin");
+    std::fs::copy(&blob, &renamed).unwrap();
+    expect_true!(run(&args(&[&renamed, &decoded])).is_ok());
+
+    let bytes = std::fs::read(&blob).unwrap();
+    let truncated = dir.join("truncated.etc1");
+    std::fs::write(&truncated, &bytes[..bytes.len() - 1]).unwrap();
+    let result = run(&args(&[&truncated, &decoded]));
+    expect_true!(matches!(result, Err(Error::InvalidBlob(_))));
+    let bad_magic = dir.join("bad_magic.etc1");
+    std::fs::write(&bad_magic, [&b"XXXX"[..], &bytes[4..]].concat()).unwrap();
+    let result = run(&args(&[&bad_magic, &decoded]));
+    expect_true!(matches!(result, Err(Error::InvalidBlob(_))));
+}
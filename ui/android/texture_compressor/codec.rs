@@ -0,0 +1,445 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Conversion between image files and compressed ETC1 images.
+//!
+//! This is the pipeline used by the command line tool, exposed so that it can
+//! be reused and tested without going through the file system. Input can be
+//! PNG, or JPEG and WebP with the `jpeg` and `webp` features; output is always
+//! PNG.
+
+use std::fmt;
+use std::io::{self, BufRead, Cursor, Seek, Write};
+use std::path::PathBuf;
+
+use crate::etc1::{
+    decode_etc1, decode_etc1_separate_alpha, encode_etc1_blocks, encode_etc1_separate_alpha,
+    read_etc1_blob, write_etc1_blob, Format,
+};
+use crate::etc2::{decode_etc2_rgba, encode_etc2_rgba};
+use crate::ktx::write_ktx;
+use crate::mipmap::mip_chain;
+use crate::EncodeOptions;
+
+/// An error from reading, writing or converting an image.
+#[derive(Debug)]
+pub enum Error {
+    /// The command line arguments are invalid, for the given reason.
+    BadArguments(String),
+    /// The input file doesn't exist.
+    FileNotFound(PathBuf),
+    /// Some files of a batch failed to convert. Each has already been reported.
+    BatchFailed {
+        succeeded: usize,
+        failed: usize,
+    },
+    /// The compressed image's PSNR in dB is below the requested threshold.
+    PsnrBelowThreshold {
+        psnr: f64,
+        threshold: f64,
+    },
+    Io(io::Error),
+    PngDecoding(png::DecodingError),
+    PngEncoding(png::EncodingError),
+    /// The PNG uses a color type or bit depth the compressor doesn't handle.
+    UnsupportedPng {
+        color_type: png::ColorType,
+        bit_depth: png::BitDepth,
+    },
+    /// The input doesn't start with the magic bytes of any supported format.
+    UnknownImageFormat,
+    /// The input is not a valid ETC1 or ETC2 blob, for the given reason.
+    InvalidBlob(String),
+    /// The input is in a format whose decoder wasn't enabled at build time.
+    ImageFormatNotEnabled(&'static str),
+    /// A JPEG or WebP input couldn't be decoded.
+    ImageDecoding {
+        format: &'static str,
+        message: String,
+    },
+    /// The input image is empty, larger than `max_dimension` in either
+    /// direction, or too large to hold in memory.
+    InvalidDimensions {
+        width: u32,
+        height: u32,
+        max_dimension: u32,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BadArguments(reason) => write!(f, "{}", reason),
+            Error::FileNotFound(path) => write!(f, "File not found: {}", path.display()),
+            Error::BatchFailed { succeeded, failed } => {
+                write!(f, "{} of {} files failed to convert", failed, succeeded + failed)
+            }
+            Error::PsnrBelowThreshold { psnr, threshold } => {
+                write!(f, "PSNR of {:.2} dB is below the threshold of {:.2} dB", psnr, threshold)
+            }
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::PngDecoding(err) => write!(f, "Failed to decode PNG: {}", err),
+            Error::PngEncoding(err) => write!(f, "Failed to encode PNG: {}", err),
+            Error::UnsupportedPng { color_type, bit_depth } => {
+                write!(f, "Unsupported PNG format: {:?} with bit depth {:?}", color_type, bit_depth)
+            }
+            Error::UnknownImageFormat => {
+                write!(f, "Unknown image format; expected a PNG, JPEG or WebP file")
+            }
+            Error::InvalidBlob(reason) => write!(f, "{}", reason),
+            Error::ImageFormatNotEnabled(format) => {
+                write!(f, "{} input is not supported in this build", format)
+            }
+            Error::ImageDecoding { format, message } => {
+                write!(f, "Failed to decode {}: {}", format, message)
+            }
+            Error::InvalidDimensions { width, height, max_dimension } => write!(
+                f,
+                "Invalid image dimensions {}x{}; each must be between 1 and {}",
+                width, height, max_dimension
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::PngDecoding(err) => Some(err),
+            Error::PngEncoding(err) => Some(err),
+            Error::BadArguments(_)
+            | Error::FileNotFound(_)
+            | Error::BatchFailed { .. }
+            | Error::PsnrBelowThreshold { .. }
+            | Error::UnsupportedPng { .. }
+            | Error::UnknownImageFormat
+            | Error::InvalidBlob(_)
+            | Error::ImageFormatNotEnabled(_)
+            | Error::ImageDecoding { .. }
+            | Error::InvalidDimensions { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<png::DecodingError> for Error {
+    fn from(err: png::DecodingError) -> Self {
+        Error::PngDecoding(err)
+    }
+}
+
+impl From<png::EncodingError> for Error {
+    fn from(err: png::EncodingError) -> Self {
+        Error::PngEncoding(err)
+    }
+}
+
+/// A compressed ETC1 or ETC2 image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Etc1Image {
+    pub format: Format,
+    pub width: u32,
+    pub height: u32,
+    /// The compressed blocks in row major order, as returned by `encode_etc1`
+    /// or `encode_etc2_rgba`.
+    pub blocks: Vec<u8>,
+    /// The compressed blocks of each mip level after level 0, if any.
+    pub mipmaps: Vec<Vec<u8>>,
+}
+
+/// Compress a `width`x`height` RGBA image to `format`.
+fn encode_level(
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    format: Format,
+    options: &EncodeOptions,
+) -> Vec<u8> {
+    match format {
+        Format::Etc1 => encode_etc1_blocks(pixels, width, height, options),
+        Format::Etc2Rgba => encode_etc2_rgba(pixels, width, height, options),
+        Format::Etc1SeparateAlpha => encode_etc1_separate_alpha(pixels, width, height, options),
+    }
+}
+
+impl Etc1Image {
+    /// Compress a `width`x`height` RGBA image to `format`, without mipmaps.
+    pub fn encode(
+        pixels: &[u32],
+        width: u32,
+        height: u32,
+        format: Format,
+        options: &EncodeOptions,
+    ) -> Self {
+        let blocks = encode_level(pixels, width, height, format, options);
+        Etc1Image { format, width, height, blocks, mipmaps: vec![] }
+    }
+
+    /// Like `encode`, but also generate and compress the full mip chain down to
+    /// 1x1. See `mip_chain`.
+    pub fn encode_with_mipmaps(
+        pixels: &[u32],
+        width: u32,
+        height: u32,
+        format: Format,
+        options: &EncodeOptions,
+    ) -> Self {
+        let mipmaps = mip_chain(pixels, width, height)
+            .iter()
+            .map(|(pixels, width, height)| encode_level(pixels, *width, *height, format, options))
+            .collect();
+        Etc1Image { mipmaps, ..Self::encode(pixels, width, height, format, options) }
+    }
+
+    /// Decompress level 0 of the image to RGBA. Alpha is 255 for ETC1.
+    pub fn decode(&self) -> Vec<u32> {
+        match self.format {
+            Format::Etc1 => decode_etc1(&self.blocks, self.width, self.height),
+            Format::Etc2Rgba => decode_etc2_rgba(&self.blocks, self.width, self.height),
+            Format::Etc1SeparateAlpha => {
+                decode_etc1_separate_alpha(&self.blocks, self.width, self.height)
+            }
+        }
+    }
+
+    /// Return the compressed blocks of each mip level, starting from level 0.
+    pub fn levels(&self) -> Vec<&[u8]> {
+        std::iter::once(&self.blocks).chain(&self.mipmaps).map(Vec::as_slice).collect()
+    }
+
+    /// Write the image as a blob with a header. See `write_etc1_blob`.
+    pub fn write_blob(&self, writer: &mut impl Write) -> Result<(), Error> {
+        Ok(write_etc1_blob(writer, self.format, &self.levels(), self.width, self.height)?)
+    }
+
+    /// Write the image as a KTX file. See `write_ktx`.
+    pub fn write_ktx(&self, writer: &mut impl Write) -> Result<(), Error> {
+        Ok(write_ktx(writer, self.format, &self.levels(), self.width, self.height)?)
+    }
+
+    /// Read an image from a blob written by `write_blob`.
+    pub fn read_blob(blob: &[u8]) -> Result<Self, Error> {
+        let (header, levels) =
+            read_etc1_blob(blob).map_err(|err| Error::InvalidBlob(err.to_string()))?;
+        let mut levels = levels.into_iter().map(<[u8]>::to_vec);
+        Ok(Etc1Image {
+            format: header.format,
+            width: header.width,
+            height: header.height,
+            blocks: levels.next().unwrap(),
+            mipmaps: levels.collect(),
+        })
+    }
+}
+
+/// A decoded image, in RGBA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    /// The pixels in row major order with no padding between rows.
+    pub pixels: Vec<u32>,
+}
+
+impl RgbaImage {
+    /// Build an image from interleaved gray, gray and alpha, RGB or RGBA bytes,
+    /// with `channels` bytes per pixel.
+    fn from_bytes(width: u32, height: u32, bytes: &[u8], channels: usize) -> Self {
+        log::debug!("Normalizing {}x{} pixels with {} channels to RGBA", width, height, channels);
+        let pixels = match channels {
+            1 => bytes.iter().map(|&v| u32::from_le_bytes([v, v, v, 0xFF])).collect(),
+            2 => bytes
+                .chunks_exact(2)
+                .map(|p| u32::from_le_bytes([p[0], p[0], p[0], p[1]]))
+                .collect(),
+            3 => bytes
+                .chunks_exact(3)
+                .map(|p| u32::from_le_bytes([p[0], p[1], p[2], 0xFF]))
+                .collect(),
+            4 => bytes.chunks_exact(4).map(|p| u32::from_le_bytes(p.try_into().unwrap())).collect(),
+            _ => unreachable!(),
+        };
+        RgbaImage { width, height, pixels }
+    }
+}
+
+/// The largest width or height accepted by decode_image and read_png. A
+/// 16384x16384 image already takes 1 GiB to decode.
+pub const DEFAULT_MAX_DIMENSION: u32 = 16384;
+
+/// Check the dimensions of an input image before anything is allocated for it,
+/// and return the size in bytes of its pixels at `channels` bytes per pixel.
+///
+/// Both dimensions must be between 1 and `max_dimension`. The size is computed
+/// with checked arithmetic, so it's an error rather than a wrapped value if it
+/// doesn't fit in a `usize`.
+pub fn checked_image_size(
+    width: u32,
+    height: u32,
+    channels: usize,
+    max_dimension: u32,
+) -> Result<usize, Error> {
+    let invalid = || Error::InvalidDimensions { width, height, max_dimension };
+    if width == 0 || height == 0 || width > max_dimension || height > max_dimension {
+        return Err(invalid());
+    }
+    usize::try_from(width)
+        .ok()
+        .zip(usize::try_from(height).ok())
+        .and_then(|(width, height)| width.checked_mul(height)?.checked_mul(channels))
+        .ok_or_else(invalid)
+}
+
+/// Decode a PNG, JPEG or WebP image, picking the decoder from the magic bytes
+/// at the start of `bytes`.
+pub fn decode_image(bytes: &[u8]) -> Result<RgbaImage, Error> {
+    decode_image_with_max_dimension(bytes, DEFAULT_MAX_DIMENSION)
+}
+
+/// Like decode_image, but with a limit other than DEFAULT_MAX_DIMENSION on
+/// the width and height. See checked_image_size.
+pub fn decode_image_with_max_dimension(
+    bytes: &[u8],
+    max_dimension: u32,
+) -> Result<RgbaImage, Error> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        read_png_with_max_dimension(Cursor::new(bytes), max_dimension)
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        decode_jpeg(bytes, max_dimension)
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        decode_webp(bytes, max_dimension)
+    } else {
+        Err(Error::UnknownImageFormat)
+    }
+}
+
+#[cfg(feature = "jpeg")]
+fn decode_jpeg(bytes: &[u8], max_dimension: u32) -> Result<RgbaImage, Error> {
+    use zune_jpeg::zune_core::colorspace::ColorSpace;
+    use zune_jpeg::zune_core::options::DecoderOptions;
+
+    let error = |err: zune_jpeg::errors::DecodeErrors| Error::ImageDecoding {
+        format: "JPEG",
+        message: err.to_string(),
+    };
+    let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::RGB);
+    let mut decoder = zune_jpeg::JpegDecoder::new_with_options(bytes, options);
+    decoder.decode_headers().map_err(error)?;
+    let info = decoder.info().unwrap();
+    checked_image_size(info.width.into(), info.height.into(), 3, max_dimension)?;
+    let rgb = decoder.decode().map_err(error)?;
+    Ok(RgbaImage::from_bytes(info.width.into(), info.height.into(), &rgb, 3))
+}
+
+#[cfg(not(feature = "jpeg"))]
+fn decode_jpeg(_bytes: &[u8], _max_dimension: u32) -> Result<RgbaImage, Error> {
+    Err(Error::ImageFormatNotEnabled("JPEG"))
+}
+
+#[cfg(feature = "webp")]
+fn decode_webp(bytes: &[u8], max_dimension: u32) -> Result<RgbaImage, Error> {
+    let error = |err: image_webp::DecodingError| Error::ImageDecoding {
+        format: "WebP",
+        message: err.to_string(),
+    };
+    let mut decoder = image_webp::WebPDecoder::new(Cursor::new(bytes)).map_err(error)?;
+    let (width, height) = decoder.dimensions();
+    let channels = if decoder.has_alpha() { 4 } else { 3 };
+    let mut buf = vec![0; checked_image_size(width, height, channels, max_dimension)?];
+    decoder.read_image(&mut buf).map_err(error)?;
+    Ok(RgbaImage::from_bytes(width, height, &buf, channels))
+}
+
+#[cfg(not(feature = "webp"))]
+fn decode_webp(_bytes: &[u8], _max_dimension: u32) -> Result<RgbaImage, Error> {
+    Err(Error::ImageFormatNotEnabled("WebP"))
+}
+
+/// Read a PNG of any color type and bit depth.
+///
+/// Palette images are expanded using their palette, grayscale is replicated
+/// to RGB, and 16-bit channels are truncated to 8 bits. A `tRNS` chunk becomes
+/// an alpha channel.
+pub fn read_png(reader: impl BufRead + Seek) -> Result<RgbaImage, Error> {
+    read_png_with_max_dimension(reader, DEFAULT_MAX_DIMENSION)
+}
+
+/// Like read_png, but with a limit other than DEFAULT_MAX_DIMENSION on the
+/// width and height. See checked_image_size.
+pub fn read_png_with_max_dimension(
+    reader: impl BufRead + Seek,
+    max_dimension: u32,
+) -> Result<RgbaImage, Error> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let info = reader.info();
+    // After the transformations, there are at most 4 bytes per pixel.
+    checked_image_size(info.width, info.height, 4, max_dimension)?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let bytes = &buf[..info.buffer_size()];
+    // The transformations leave only these color types, at 8 bits.
+    let channels = match (info.color_type, info.bit_depth) {
+        (png::ColorType::Grayscale, png::BitDepth::Eight) => 1,
+        (png::ColorType::GrayscaleAlpha, png::BitDepth::Eight) => 2,
+        (png::ColorType::Rgb, png::BitDepth::Eight) => 3,
+        (png::ColorType::Rgba, png::BitDepth::Eight) => 4,
+        (color_type, bit_depth) => return Err(Error::UnsupportedPng { color_type, bit_depth }),
+    };
+    Ok(RgbaImage::from_bytes(info.width, info.height, bytes, channels))
+}
+
+/// Write a `width`x`height` RGBA image as an 8-bit PNG. `alpha` selects between
+/// RGBA and RGB output; alpha is dropped for the latter.
+///
+/// The color type and the number of bytes per pixel both come from `alpha`, so
+/// they always agree.
+pub fn write_png(
+    writer: impl Write,
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    alpha: bool,
+) -> Result<(), Error> {
+    let (color_type, channels) =
+        if alpha { (png::ColorType::Rgba, 4) } else { (png::ColorType::Rgb, 3) };
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(color_type);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let bytes: Vec<u8> =
+        pixels.iter().flat_map(|pixel| pixel.to_le_bytes().into_iter().take(channels)).collect();
+    writer.write_image_data(&bytes)?;
+    Ok(writer.finish()?)
+}
+
+/// Read a PNG and compress it to `format`.
+pub fn compress_png(reader: impl BufRead + Seek, format: Format) -> Result<Etc1Image, Error> {
+    let image = read_png(reader)?;
+    Ok(Etc1Image::encode(
+        &image.pixels,
+        image.width,
+        image.height,
+        format,
+        &EncodeOptions::default(),
+    ))
+}
+
+/// Read a PNG and compress it to ETC1.
+pub fn compress_png_to_etc1(reader: impl BufRead + Seek) -> Result<Etc1Image, Error> {
+    compress_png(reader, Format::Etc1)
+}
+
+/// Decompress an image and write it as a PNG, which is RGBA if the image has
+/// alpha and RGB otherwise.
+pub fn etc1_to_png(image: &Etc1Image, writer: impl Write) -> Result<(), Error> {
+    write_png(writer, &image.decode(), image.width, image.height, image.format.has_alpha())
+}
@@ -0,0 +1,231 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use std::io::Cursor;
+
+use texture_compressor::{
+    checked_image_size, compress_png_to_etc1, decode_image, decode_image_with_max_dimension,
+    etc1_to_png, read_png, Error, Etc1Image, RgbaImage, DEFAULT_MAX_DIMENSION,
+};
+
+/// Encode an 8-bit PNG with the given color type in memory.
+fn make_png(bytes: &[u8], width: u32, height: u32, color_type: png::ColorType) -> Vec<u8> {
+    make_png_with(bytes, width, height, color_type, png::BitDepth::Eight, |_| ())
+}
+
+/// Encode a PNG in memory, with `configure` setting any extra chunks.
+fn make_png_with(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+    configure: impl FnOnce(&mut png::Encoder<&mut Vec<u8>>),
+) -> Vec<u8> {
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+    configure(&mut encoder);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(bytes).unwrap();
+    writer.finish().unwrap();
+    png
+}
+
+#[gtest(TextureCompressorTest, CodecPngRoundTrip)]
+fn test_codec_png_round_trip() {
+    let (width, height) = (8, 4);
+    let rgb: Vec<u8> = (0..width * height).flat_map(|_| [0x20, 0x80, 0xC0]).collect();
+    let png = make_png(&rgb, width, height, png::ColorType::Rgb);
+
+    let image = compress_png_to_etc1(Cursor::new(png)).unwrap();
+    expect_eq!((image.width, image.height, image.blocks.len()), (8, 4, 16));
+
+    let mut blob = vec![];
+    image.write_blob(&mut blob).unwrap();
+    expect_eq!(Etc1Image::read_blob(&blob).unwrap(), image);
+
+    let mut output = vec![];
+    etc1_to_png(&image, &mut output).unwrap();
+    let RgbaImage { width: output_width, height: output_height, pixels } =
+        read_png(Cursor::new(output)).unwrap();
+    expect_eq!((output_width, output_height), (width, height));
+    for pixel in pixels {
+        let [r, g, b, a] = pixel.to_le_bytes();
+        // ETC1 is lossy, so only check the colors are close.
+        expect_le!(r.abs_diff(0x20).max(g.abs_diff(0x80)).max(b.abs_diff(0xC0)), 16);
+        expect_eq!(a, 0xFF);
+    }
+}
+
+#[gtest(TextureCompressorTest, CodecPalettePng)]
+fn test_codec_palette_png() {
+    // Two palette entries, the second partly transparent, at 2 bits per pixel.
+    let png = make_png_with(
+        &[0b0001_0100],
+        4,
+        1,
+        png::ColorType::Indexed,
+        png::BitDepth::Two,
+        |encoder| {
+            encoder.set_palette(vec![10, 20, 30, 200, 150, 100]);
+            encoder.set_trns(vec![0xFF, 0x80]);
+        },
+    );
+    expect_eq!(
+        read_png(Cursor::new(png)).unwrap(),
+        RgbaImage {
+            width: 4,
+            height: 1,
+            pixels: vec![0xFF1E140A, 0x806496C8, 0x806496C8, 0xFF1E140A],
+        }
+    );
+}
+
+#[gtest(TextureCompressorTest, CodecGrayscaleAlphaPng)]
+fn test_codec_grayscale_alpha_png() {
+    let png = make_png(&[0x40, 0xFF, 0xC0, 0x20], 2, 1, png::ColorType::GrayscaleAlpha);
+    expect_eq!(
+        read_png(Cursor::new(png)).unwrap(),
+        RgbaImage { width: 2, height: 1, pixels: vec![0xFF404040, 0x20C0C0C0] }
+    );
+    let png = make_png(&[0x00, 0x7F], 2, 1, png::ColorType::Grayscale);
+    expect_eq!(read_png(Cursor::new(png)).unwrap().pixels, vec![0xFF000000, 0xFF7F7F7F]);
+}
+
+#[gtest(TextureCompressorTest, Codec16BitPng)]
+fn test_codec_16_bit_png() {
+    // Each channel is truncated to its high byte.
+    let rgb16 = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
+    let png = make_png_with(&rgb16, 1, 1, png::ColorType::Rgb, png::BitDepth::Sixteen, |_| ());
+    expect_eq!(read_png(Cursor::new(png)).unwrap().pixels, vec![0xFF9A5612]);
+}
+
+#[gtest(TextureCompressorTest, CodecInvalidPng)]
+fn test_codec_invalid_png() {
+    expect_true!(matches!(
+        compress_png_to_etc1(Cursor::new(b"not a png")),
+        Err(Error::PngDecoding(_))
+    ));
+}
+
+/// Overwrite the dimensions in the IHDR chunk of `png`, fixing up its CRC so
+/// that the decoder gets as far as looking at them.
+fn set_png_dimensions(png: &mut [u8], width: u32, height: u32) {
+    png[16..20].copy_from_slice(&width.to_be_bytes());
+    png[20..24].copy_from_slice(&height.to_be_bytes());
+    // The CRC covers the chunk type and data, and follows them.
+    let mut crc = !0u32;
+    for &byte in &png[12..29] {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    png[29..33].copy_from_slice(&(!crc).to_be_bytes());
+}
+
+#[gtest(TextureCompressorTest, CodecInvalidDimensions)]
+fn test_codec_invalid_dimensions() {
+    expect_true!(matches!(checked_image_size(3, 2, 4, 16), Ok(24)));
+    for (width, height) in [(0, 2), (3, 0), (17, 2), (3, 17)] {
+        expect_true!(
+            matches!(
+                checked_image_size(width, height, 4, 16),
+                Err(Error::InvalidDimensions { max_dimension: 16, .. })
+            ),
+            "{}x{}",
+            width,
+            height
+        );
+    }
+    // Within the limit, but the product overflows.
+    expect_true!(matches!(
+        checked_image_size(u32::MAX, u32::MAX, 4, u32::MAX),
+        Err(Error::InvalidDimensions { .. })
+    ));
+
+    // A header claiming a huge image is rejected before allocating for it.
+    let mut png = make_png(&[0x20, 0x80, 0xC0], 1, 1, png::ColorType::Rgb);
+    set_png_dimensions(&mut png, 1, 1 << 30);
+    expect_true!(matches!(
+        decode_image(&png),
+        Err(Error::InvalidDimensions { width: 1, height: 0x40000000, max_dimension })
+            if max_dimension == DEFAULT_MAX_DIMENSION
+    ));
+    let png = make_png(&[0x20; 8 * 3], 8, 1, png::ColorType::Rgb);
+    expect_true!(decode_image_with_max_dimension(&png, 8).is_ok());
+    expect_true!(matches!(
+        decode_image_with_max_dimension(&png, 7),
+        Err(Error::InvalidDimensions { width: 8, height: 1, max_dimension: 7 })
+    ));
+    // The PNG decoder itself refuses an empty image.
+    let mut png = make_png(&[0x20, 0x80, 0xC0], 1, 1, png::ColorType::Rgb);
+    set_png_dimensions(&mut png, 0, 1);
+    expect_true!(matches!(decode_image(&png), Err(Error::PngDecoding(_))));
+}
+
+#[gtest(TextureCompressorTest, CodecDecodeImageSniffing)]
+fn test_codec_decode_image_sniffing() {
+    let rgb = [1, 2, 3, 4, 5, 6];
+    let png = make_png(&rgb, 2, 1, png::ColorType::Rgb);
+    expect_eq!(
+        decode_image(&png).unwrap(),
+        RgbaImage { width: 2, height: 1, pixels: vec![0xFF030201, 0xFF060504] }
+    );
+    expect_true!(matches!(decode_image(b"GIF89a"), Err(Error::UnknownImageFormat)));
+    expect_true!(matches!(decode_image(&[]), Err(Error::UnknownImageFormat)));
+    // Truncated files are reported as errors of the sniffed format.
+    expect_true!(matches!(decode_image(&png[..20]), Err(Error::PngDecoding(_))));
+}
+
+/// Return whether the channels of an RGBA pixel are each within `tolerance` of
+/// `expected`.
+#[cfg(any(feature = "jpeg", feature = "webp"))]
+fn is_close(pixel: u32, expected: [u8; 4], tolerance: u8) -> bool {
+    std::iter::zip(pixel.to_le_bytes(), expected).all(|(a, b)| a.abs_diff(b) <= tolerance)
+}
+
+// The fixtures are 8x8, with the left half (200, 40, 40) and the right half
+// (40, 40, 200). In the WebP, the right half also has alpha 128.
+
+#[cfg(feature = "jpeg")]
+#[gtest(TextureCompressorTest, CodecDecodeJpeg)]
+fn test_codec_decode_jpeg() {
+    let image = decode_image(include_bytes!("test/data/halves_8x8.jpg")).unwrap();
+    expect_eq!((image.width, image.height), (8, 8));
+    // JPEG is lossy, particularly around the edge between the halves.
+    expect_true!(is_close(image.pixels[0], [200, 40, 40, 255], 8));
+    expect_true!(is_close(image.pixels[63], [40, 40, 200, 255], 8));
+    expect_true!(matches!(
+        decode_image(&include_bytes!("test/data/halves_8x8.jpg")[..100]),
+        Err(Error::ImageDecoding { format: "JPEG", .. })
+    ));
+}
+
+#[cfg(feature = "webp")]
+#[gtest(TextureCompressorTest, CodecDecodeWebp)]
+fn test_codec_decode_webp() {
+    let image = decode_image(include_bytes!("test/data/halves_8x8.webp")).unwrap();
+    expect_eq!((image.width, image.height), (8, 8));
+    // The fixture is lossless.
+    expect_eq!(image.pixels[0], u32::from_le_bytes([200, 40, 40, 255]));
+    expect_eq!(image.pixels[63], u32::from_le_bytes([40, 40, 200, 128]));
+}
+
+#[cfg(not(feature = "jpeg"))]
+#[gtest(TextureCompressorTest, CodecJpegNotEnabled)]
+fn test_codec_jpeg_not_enabled() {
+    expect_true!(matches!(
+        decode_image(include_bytes!("test/data/halves_8x8.jpg")),
+        Err(Error::ImageFormatNotEnabled("JPEG"))
+    ));
+}
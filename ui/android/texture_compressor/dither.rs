@@ -18,8 +18,8 @@ const fn create_bayer_matrix(num: i32, denom: i32) -> [[i16; 4]; 4] {
     let mut matrix = [[0i16; 4]; 4];
     // Only while loops are possible, not for, in const fns
     let mut y = 0;
-    let mut x = 0;
     while y < 4 {
+        let mut x = 0;
         while x < 4 {
             let value = (base_pattern[y][x] - 8) * num / denom / 16;
             matrix[y][x] = value as i16;
@@ -37,13 +37,24 @@ const BAYER_63: [[i16; 4]; 4] = create_bayer_matrix(255 * 4, 63 * 3);
 /// Bayer dithering. The purpose of the dithering is mostly to mask artifacts;
 /// the strength of dithering is not really related to the quantization scheme
 /// (444 or 555) nor the selector table values.
+///
+/// Each pixel is offset by its position in the block, and the whole block by
+/// the position of the block in the image, so that the base colors of blocks
+/// along a gradient alternate instead of forming bands. `block_x` is the
+/// column of the block in the first lane, and `block_y` the row of all of
+/// them.
 #[inline]
-pub fn dither(data: &[[[Reg; 3]; 4]; 4]) -> [[[Reg; 3]; 4]; 4] {
+pub fn dither(data: &[[[Reg; 3]; 4]; 4], block_x: u32, block_y: u32) -> [[[Reg; 3]; 4]; 4] {
+    let block_offset = |matrix: &[[i16; 4]; 4]| {
+        let row = &matrix[block_y as usize % 4];
+        Reg::from_array(std::array::from_fn(|lane| row[(block_x as usize + lane) % 4]))
+    };
     let mut out = [[[Reg::default(); 3]; 4]; 4];
-    for y in 0..4 {
-        for x in 0..4 {
-            for (ch, matrix) in [(0, &BAYER_31), (1, &BAYER_63), (2, &BAYER_31)] {
-                out[y][x][ch] = (data[y][x][ch] + Simd::splat(matrix[y][x]))
+    for (ch, matrix) in [(0, &BAYER_31), (1, &BAYER_63), (2, &BAYER_31)] {
+        let block_offset = block_offset(matrix);
+        for y in 0..4 {
+            for x in 0..4 {
+                out[y][x][ch] = (data[y][x][ch] + Simd::splat(matrix[y][x]) + block_offset)
                     .simd_clamp(Simd::splat(0), Simd::splat(255));
             }
         }
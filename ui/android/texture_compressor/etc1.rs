@@ -0,0 +1,409 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Whole-image ETC1 encoding and decoding.
+//!
+//! Encoding is done by the SIMD compressor in `compress_etc1`; this module
+//! wraps it for tightly packed images. Decoding is a straightforward scalar
+//! implementation of the spec, used to inspect the result of compression. It
+//! is not optimized.
+
+use std::io::{self, Write};
+
+#[cfg(feature = "rayon")]
+use crate::compress_etc1_block_row;
+use crate::mipmap::{mip_dimensions, mip_level_count};
+use crate::selectors::TABLES;
+use crate::{compress_etc1_with_options, EncodeOptions};
+
+/// Size in bytes of one compressed 4x4 block.
+pub const BLOCK_SIZE: usize = 8;
+
+/// A compressed texture format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// ETC1, with no alpha.
+    Etc1,
+    /// ETC2 RGBA: an EAC alpha block followed by an ETC1 color block.
+    Etc2Rgba,
+    /// Two ETC1 images one after the other: the color, then the alpha as
+    /// gray. This is for GPUs without ETC2, which sample the alpha from the
+    /// green channel of a second texture.
+    Etc1SeparateAlpha,
+}
+
+impl Format {
+    /// The magic bytes at the start of a blob in this format.
+    pub fn blob_magic(self) -> [u8; 4] {
+        match self {
+            Format::Etc1 => *b"ETC1",
+            Format::Etc2Rgba => *b"ETC2",
+            Format::Etc1SeparateAlpha => *b"ETCA",
+        }
+    }
+
+    /// Size in bytes of one compressed 4x4 block. For separate alpha, this
+    /// counts both the color and the alpha block.
+    pub fn block_size(self) -> usize {
+        match self {
+            Format::Etc1 => BLOCK_SIZE,
+            Format::Etc2Rgba | Format::Etc1SeparateAlpha => crate::etc2::BLOCK_SIZE,
+        }
+    }
+
+    /// Return the size in bytes of a compressed `width`x`height` image.
+    ///
+    /// Panics if the size does not fit in a `usize`.
+    pub fn compressed_size(self, width: u32, height: u32) -> usize {
+        self.checked_compressed_size(width, height).expect("compressed image size overflows usize")
+    }
+
+    /// Like `compressed_size`, but returns `None` instead of panicking, for
+    /// dimensions that come from untrusted input.
+    pub fn checked_compressed_size(self, width: u32, height: u32) -> Option<usize> {
+        (width.div_ceil(4) as usize)
+            .checked_mul(height.div_ceil(4) as usize)?
+            .checked_mul(self.block_size())
+    }
+
+    /// Whether the format keeps the alpha channel.
+    pub fn has_alpha(self) -> bool {
+        self != Format::Etc1
+    }
+
+    /// Return the ETC1 color block of the `block_idx`th block of `blocks`, in
+    /// row major block order.
+    pub fn color_block(self, blocks: &[u8], block_idx: usize) -> &[u8; BLOCK_SIZE] {
+        let start = match self {
+            Format::Etc1 | Format::Etc1SeparateAlpha => block_idx * BLOCK_SIZE,
+            // The color block comes after the alpha block.
+            Format::Etc2Rgba => block_idx * crate::etc2::BLOCK_SIZE + BLOCK_SIZE,
+        };
+        blocks[start..start + BLOCK_SIZE].try_into().unwrap()
+    }
+}
+
+/// Return the size in bytes of the header of an ETC1 blob with `level_count`
+/// mip levels.
+pub fn blob_header_size(level_count: usize) -> usize {
+    20 + 12 * level_count
+}
+
+/// The header of an ETC1 or ETC2 blob, as written by `write_etc1_blob`.
+///
+/// The header is the format's magic bytes, followed by the other fields as big
+/// endian u32s to match the byte order of the blocks. The levels come last,
+/// preceded by their count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Etc1BlobHeader {
+    pub format: Format,
+    pub width: u32,
+    pub height: u32,
+    /// The number of blocks in all levels together.
+    pub block_count: u32,
+    pub levels: Vec<MipLevel>,
+}
+
+/// The location of a mip level in an ETC1 blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MipLevel {
+    pub width: u32,
+    pub height: u32,
+    /// Offset in bytes of the level's blocks from the start of the blob.
+    pub offset: u32,
+}
+
+/// Return the size in bytes of a compressed `width`x`height` image.
+pub fn compressed_size(width: u32, height: u32) -> usize {
+    Format::Etc1.compressed_size(width, height)
+}
+
+/// Compress a `width`x`height` image to ETC1, returning the blocks in row
+/// major order.
+///
+/// `pixels` should be in RGBA, in row major order with no padding between
+/// rows. Alpha is ignored.
+pub fn encode_etc1(pixels: &[u32], width: u32, height: u32, options: &EncodeOptions) -> Vec<u8> {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+    let mut blocks = vec![0; compressed_size(width, height)];
+    compress_etc1_with_options(
+        pixels,
+        &mut blocks,
+        width,
+        height,
+        width,
+        width.div_ceil(4),
+        options,
+    );
+    blocks
+}
+
+/// Like `encode_etc1`, but compresses each row of blocks on a separate rayon
+/// task. The result is identical to `encode_etc1`.
+#[cfg(feature = "rayon")]
+pub fn encode_etc1_parallel(
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    options: &EncodeOptions,
+) -> Vec<u8> {
+    use rayon::prelude::*;
+
+    assert_eq!(pixels.len(), width as usize * height as usize);
+    let blocks_per_row = width.div_ceil(4);
+    let mut blocks = vec![0; compressed_size(width, height)];
+    // An empty image has no rows to split, and rayon rejects empty chunks.
+    if blocks.is_empty() {
+        return blocks;
+    }
+    blocks.par_chunks_mut(blocks_per_row as usize * BLOCK_SIZE).enumerate().for_each(
+        |(block_y, dst)| {
+            // Each row is encoded knowing its position in the whole image, so
+            // that the dither and edge padding match a serial encode.
+            compress_etc1_block_row(pixels, width, height, width, block_y as u32, options, dst);
+        },
+    );
+    blocks
+}
+
+// The formats built from ETC1 blocks compress them in parallel too, when the
+// `rayon` feature allows it.
+#[cfg(not(feature = "rayon"))]
+pub(crate) use self::encode_etc1 as encode_etc1_blocks;
+#[cfg(feature = "rayon")]
+pub(crate) use self::encode_etc1_parallel as encode_etc1_blocks;
+
+/// Compress a `width`x`height` image to two ETC1 images, as in
+/// `Format::Etc1SeparateAlpha`: the color, followed by the alpha broadcast to
+/// gray. Each is laid out as by `encode_etc1`.
+pub fn encode_etc1_separate_alpha(
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    options: &EncodeOptions,
+) -> Vec<u8> {
+    let alpha: Vec<u32> =
+        pixels.iter().map(|pixel| ((pixel >> 24) * 0x010101) | 0xFF000000).collect();
+    [
+        encode_etc1_blocks(pixels, width, height, options),
+        encode_etc1_blocks(&alpha, width, height, options),
+    ]
+    .concat()
+}
+
+/// Decode a `width`x`height` image from blocks produced by
+/// `encode_etc1_separate_alpha`, taking the alpha from the green channel of
+/// the second image.
+///
+/// Returns the pixels in RGBA.
+pub fn decode_etc1_separate_alpha(blocks: &[u8], width: u32, height: u32) -> Vec<u32> {
+    assert_eq!(blocks.len(), compressed_size(width, height) * 2);
+    let (color, alpha) = blocks.split_at(blocks.len() / 2);
+    std::iter::zip(decode_etc1(color, width, height), decode_etc1(alpha, width, height))
+        .map(|(color, alpha)| (color & 0x00FFFFFF) | (((alpha >> 8) & 0xFF) << 24))
+        .collect()
+}
+
+/// The parameters shared by the pixels of each subblock of an ETC1 block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Etc1BlockParams {
+    /// Whether the subblocks are the top and bottom halves of the block,
+    /// rather than the left and right.
+    pub flip: bool,
+    /// Whether the second base color is stored as a delta from the first.
+    pub differential: bool,
+    /// The base colors of the two subblocks, extended to 8 bits.
+    pub base_colors: [[u8; 3]; 2],
+    /// The modifier table index of each subblock.
+    pub table_indices: [u8; 2],
+}
+
+/// Return the parameters of a single ETC1 block.
+pub fn etc1_block_params(block: &[u8; BLOCK_SIZE]) -> Etc1BlockParams {
+    let word = u64::from_be_bytes(*block);
+    let bits = |offset: u32, len: u32| ((word >> offset) & ((1 << len) - 1)) as i32;
+
+    let flip = bits(32, 1) == 1;
+    let differential = bits(33, 1) == 1;
+    let base_colors: [[i32; 3]; 2] = if differential {
+        let extend = |x: i32| (x << 3) | (x >> 2);
+        // The deltas are 3-bit two's complement.
+        let delta = |offset: u32| (bits(offset, 3) << 29) >> 29;
+        let base = [bits(59, 5), bits(51, 5), bits(43, 5)];
+        let deltas = [delta(56), delta(48), delta(40)];
+        [base.map(extend), [0, 1, 2].map(|ch| extend(base[ch] + deltas[ch]))]
+    } else {
+        let extend = |x: i32| x * 17;
+        [
+            [60, 52, 44].map(|offset| extend(bits(offset, 4))),
+            [56, 48, 40].map(|offset| extend(bits(offset, 4))),
+        ]
+    };
+    Etc1BlockParams {
+        flip,
+        differential,
+        // A delta that takes the color out of range is invalid in ETC1.
+        base_colors: base_colors.map(|color| color.map(|c| c.clamp(0, 255) as u8)),
+        table_indices: [bits(37, 3) as u8, bits(34, 3) as u8],
+    }
+}
+
+/// Decode a single ETC1 block.
+///
+/// Returns the pixels as `[y][x][channel]`, in RGB.
+pub fn decode_etc1_block(block: &[u8; BLOCK_SIZE]) -> [[[u8; 3]; 4]; 4] {
+    let word = u64::from_be_bytes(*block);
+    let bits = |offset: u32| (word >> offset) & 1 == 1;
+    let Etc1BlockParams { flip, base_colors, table_indices, .. } = etc1_block_params(block);
+    let tables = table_indices.map(|table_idx| TABLES[usize::from(table_idx)]);
+
+    let mut pixels = [[[0; 3]; 4]; 4];
+    for (y, row) in pixels.iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let subblock = if flip { usize::from(y >= 2) } else { usize::from(x >= 2) };
+            // Pixels are indexed in column major order.
+            let pixel_idx = (x * 4 + y) as u32;
+            let is_large = bits(pixel_idx);
+            let is_negative = bits(pixel_idx + 16);
+            let magnitude = i32::from(tables[subblock][usize::from(is_large)]);
+            let modifier = if is_negative { -magnitude } else { magnitude };
+            *pixel = base_colors[subblock].map(|c| (i32::from(c) + modifier).clamp(0, 255) as u8);
+        }
+    }
+    pixels
+}
+
+/// Decode a `width`x`height` image from ETC1 blocks in row major order, as
+/// produced by `encode_etc1`.
+///
+/// Returns the pixels in RGBA, with alpha set to 255.
+pub fn decode_etc1(blocks: &[u8], width: u32, height: u32) -> Vec<u32> {
+    assert_eq!(blocks.len(), compressed_size(width, height));
+    let mut pixels = vec![0; width as usize * height as usize];
+    for (block_idx, block) in blocks.chunks_exact(BLOCK_SIZE).enumerate() {
+        let decoded = decode_etc1_block(block.try_into().unwrap())
+            .map(|row| row.map(|[r, g, b]| u32::from_le_bytes([r, g, b, 0xFF])));
+        store_block(&mut pixels, width, height, block_idx, &decoded);
+    }
+    pixels
+}
+
+/// Copy the decoded pixels of the `block_idx`th block, in row major block
+/// order, into a `width`x`height` image.
+pub(crate) fn store_block(
+    pixels: &mut [u32],
+    width: u32,
+    height: u32,
+    block_idx: usize,
+    block: &[[u32; 4]; 4],
+) {
+    let (width, height) = (width as usize, height as usize);
+    let blocks_per_row = width.div_ceil(4);
+    let base_x = block_idx % blocks_per_row * 4;
+    let base_y = block_idx / blocks_per_row * 4;
+    for (y, row) in block.iter().enumerate() {
+        for (x, pixel) in row.iter().enumerate() {
+            // Blocks at the right and bottom edges may hang over the edge.
+            if base_x + x < width && base_y + y < height {
+                pixels[(base_y + y) * width + base_x + x] = *pixel;
+            }
+        }
+    }
+}
+
+/// Write `levels`, the compressed mip levels of a `width`x`height` image in
+/// `format` starting from level 0, preceded by a header describing them.
+pub fn write_etc1_blob(
+    writer: &mut impl Write,
+    format: Format,
+    levels: &[&[u8]],
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    let mut offset = blob_header_size(levels.len());
+    let mut level_fields = vec![];
+    for (level, blocks) in levels.iter().enumerate() {
+        let (level_width, level_height) = mip_dimensions(width, height, level as u32);
+        assert_eq!(blocks.len(), format.compressed_size(level_width, level_height));
+        level_fields.extend([level_width, level_height, offset as u32]);
+        offset += blocks.len();
+    }
+    let block_count = levels.iter().map(|blocks| blocks.len() / format.block_size()).sum::<usize>();
+    writer.write_all(&format.blob_magic())?;
+    for field in
+        [width, height, block_count as u32, levels.len() as u32].into_iter().chain(level_fields)
+    {
+        writer.write_all(&field.to_be_bytes())?;
+    }
+    levels.iter().try_for_each(|blocks| writer.write_all(blocks))
+}
+
+/// Return the format of a blob written by `write_etc1_blob`, judging only by
+/// its magic bytes.
+pub fn blob_format(blob: &[u8]) -> Option<Format> {
+    [Format::Etc1, Format::Etc2Rgba, Format::Etc1SeparateAlpha]
+        .into_iter()
+        .find(|format| blob.starts_with(&format.blob_magic()))
+}
+
+/// Parse the header of a blob written by `write_etc1_blob`, returning it along
+/// with the blocks of each level.
+pub fn read_etc1_blob(blob: &[u8]) -> io::Result<(Etc1BlobHeader, Vec<&[u8]>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+    if blob.len() < blob_header_size(0) {
+        return Err(invalid("ETC1 blob is too short for its header"));
+    }
+    let Some(format) = blob_format(blob) else {
+        return Err(invalid("ETC1 blob does not start with the expected magic bytes"));
+    };
+    let field = |i: usize| u32::from_be_bytes(blob[i * 4..i * 4 + 4].try_into().unwrap());
+    let level_count = field(4) as usize;
+    if level_count == 0 {
+        return Err(invalid("ETC1 blob has no mip levels"));
+    }
+    let (width, height) = (field(1), field(2));
+    if level_count > mip_level_count(width, height) as usize {
+        return Err(invalid("ETC1 blob has more mip levels than its dimensions allow"));
+    }
+    let header_size = level_count
+        .checked_mul(12)
+        .and_then(|size| size.checked_add(blob_header_size(0)))
+        .filter(|size| *size <= blob.len())
+        .ok_or_else(|| invalid("ETC1 blob is too short for its header"))?;
+    let levels: Vec<MipLevel> = (0..level_count)
+        .map(|level| {
+            let i = 5 + level * 3;
+            MipLevel { width: field(i), height: field(i + 1), offset: field(i + 2) }
+        })
+        .collect();
+
+    // The levels must be the full chain so far, packed one after the other.
+    let mut offset = header_size;
+    let mut level_blocks = vec![];
+    for (level, info) in levels.iter().enumerate() {
+        if (info.width, info.height) != mip_dimensions(width, height, level as u32) {
+            return Err(invalid("ETC1 blob mip level dimensions do not match the image"));
+        }
+        if info.offset as usize != offset {
+            return Err(invalid("ETC1 blob mip level offsets are not contiguous"));
+        }
+        let blocks = format
+            .checked_compressed_size(info.width, info.height)
+            .and_then(|size| blob.get(offset..offset.checked_add(size)?));
+        let Some(blocks) = blocks else {
+            return Err(invalid("ETC1 blob is too short for its blocks"));
+        };
+        level_blocks.push(blocks);
+        offset += blocks.len();
+    }
+    if offset != blob.len() {
+        return Err(invalid("ETC1 blob length does not match its levels"));
+    }
+    let header = Etc1BlobHeader { format, width, height, block_count: field(3), levels };
+    let block_bytes = (header.block_count as usize).checked_mul(format.block_size());
+    if block_bytes != Some(offset - header_size) {
+        return Err(invalid("ETC1 blob block count does not match its levels"));
+    }
+    Ok((header, level_blocks))
+}
@@ -0,0 +1,306 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use texture_compressor::etc1::{
+    blob_header_size, compressed_size, decode_etc1, decode_etc1_block, decode_etc1_separate_alpha,
+    encode_etc1, encode_etc1_separate_alpha, etc1_block_params, read_etc1_blob, write_etc1_blob,
+    Etc1BlobHeader, Format, MipLevel,
+};
+use texture_compressor::{psnr, EncodeOptions, Etc1Image, Quality};
+
+/// Return the largest difference between any channel of two RGBA images,
+/// ignoring alpha.
+fn max_diff(a: &[u32], b: &[u32]) -> u8 {
+    std::iter::zip(a, b)
+        .flat_map(|(a, b)| {
+            let (a, b) = (a.to_le_bytes(), b.to_le_bytes());
+            (0..3).map(move |ch| a[ch].abs_diff(b[ch]))
+        })
+        .max()
+        .unwrap()
+}
+
+#[gtest(TextureCompressorTest, Etc1SolidBlock)]
+fn test_etc1_solid_block() {
+    for color in [0xFF000000, 0xFFFFFFFF, 0xFF2080C0, 0xFF7F7F7F] {
+        let pixels = [color; 16];
+        let compressed = encode_etc1(&pixels, 4, 4, &EncodeOptions::default());
+        expect_eq!(compressed.len(), 8);
+        let decoded = decode_etc1(&compressed, 4, 4);
+        // Quantization to 555 adds a little error.
+        expect_le!(max_diff(&pixels, &decoded), 8, "{:08x}", color);
+        expect_true!(decoded.iter().all(|pixel| pixel >> 24 == 0xFF));
+    }
+}
+
+#[gtest(TextureCompressorTest, Etc1DecodeBlock)]
+fn test_etc1_decode_block() {
+    // Individual mode, flipped: the top half uses base color (1, 2, 3) * 17 and
+    // table 0, the bottom half (4, 5, 6) * 17 and table 7. Pixel (x, y) has
+    // index bit x * 4 + y: the top left pixel has modifier index 0 (+2), the
+    // one below it index 1 (+8), and the bottom right pixel index 3 (-183).
+    let block = [0x14, 0x25, 0x36, 0b0001_1101, 0x80, 0x00, 0x80, 0x02];
+    let decoded = decode_etc1_block(&block);
+    expect_eq!(decoded[0][0], [19, 36, 53]);
+    expect_eq!(decoded[1][0], [25, 42, 59]);
+    expect_eq!(decoded[3][3], [0, 0, 0]);
+    expect_eq!(decoded[2][0], [68 + 47, 85 + 47, 102 + 47]);
+}
+
+#[gtest(TextureCompressorTest, Etc1Gradient)]
+fn test_etc1_gradient() {
+    let (width, height) = (16, 8);
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            u32::from_le_bytes([(x * 8) as u8, (y * 16) as u8, 128, 0xFF])
+        })
+        .collect();
+    let compressed = encode_etc1(&pixels, width, height, &EncodeOptions::default());
+    expect_eq!(compressed.len(), compressed_size(width, height));
+    let decoded = decode_etc1(&compressed, width, height);
+    expect_le!(max_diff(&pixels, &decoded), 24);
+}
+
+#[gtest(TextureCompressorTest, Etc1Blob)]
+fn test_etc1_blob() {
+    let (width, height) = (10, 6);
+    let pixels = vec![0xFF2080C0; (width * height) as usize];
+    let compressed = encode_etc1(&pixels, width, height, &EncodeOptions::default());
+    let mut blob = vec![];
+    write_etc1_blob(&mut blob, Format::Etc1, &[&compressed], width, height).unwrap();
+    expect_eq!(blob.len(), blob_header_size(1) + compressed.len());
+    expect_eq!(&blob[..4], b"ETC1");
+
+    let (header, levels) = read_etc1_blob(&blob).unwrap();
+    expect_eq!(
+        header,
+        Etc1BlobHeader {
+            format: Format::Etc1,
+            width: 10,
+            height: 6,
+            block_count: 6,
+            levels: vec![MipLevel { width: 10, height: 6, offset: 32 }],
+        }
+    );
+    expect_eq!(levels, vec![&compressed[..]]);
+
+    expect_true!(read_etc1_blob(&blob[..blob_header_size(1) - 1]).is_err());
+    expect_true!(read_etc1_blob(&blob[..blob.len() - 1]).is_err());
+    let mut bad_magic = blob.clone();
+    bad_magic[0] = b'X';
+    expect_true!(read_etc1_blob(&bad_magic).is_err());
+}
+
+#[gtest(TextureCompressorTest, Etc1BlobTooManyLevels)]
+fn test_etc1_blob_too_many_levels() {
+    // A 1x1 image only has one level, but every 1x1 level after it would
+    // otherwise fit, right up until the dimensions are shifted out of range.
+    let level_count = 33;
+    let mut blob = Format::Etc1.blob_magic().to_vec();
+    let header_size = blob_header_size(level_count);
+    let fields = [1, 1, level_count as u32, level_count as u32]
+        .into_iter()
+        .chain((0..level_count).flat_map(|level| [1, 1, (header_size + level * 8) as u32]));
+    for field in fields {
+        blob.extend(u32::to_be_bytes(field));
+    }
+    blob.resize(header_size + level_count * 8, 0);
+    expect_true!(read_etc1_blob(&blob).is_err());
+}
+
+#[gtest(TextureCompressorTest, Etc1BlobHugeDimensions)]
+fn test_etc1_blob_huge_dimensions() {
+    // The size of the level overflows a usize, which must be an error rather
+    // than a panic or a wrapped size that happens to fit the blob.
+    let dimension = u32::MAX;
+    expect_eq!(Format::Etc1SeparateAlpha.checked_compressed_size(dimension, dimension), None);
+    let mut blob = Format::Etc1SeparateAlpha.blob_magic().to_vec();
+    for field in [dimension, dimension, 0, 1, dimension, dimension, blob_header_size(1) as u32] {
+        blob.extend(u32::to_be_bytes(field));
+    }
+    expect_true!(read_etc1_blob(&blob).is_err());
+}
+
+#[gtest(TextureCompressorTest, Etc1OddDimensions)]
+fn test_etc1_odd_dimensions() {
+    let (width, height) = (5, 3);
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            u32::from_le_bytes([(x * 8) as u8, (y * 16) as u8, 128, 0xFF])
+        })
+        .collect();
+    let compressed = encode_etc1(&pixels, width, height, &EncodeOptions::default());
+    expect_eq!(compressed.len(), 2 * 8);
+    let decoded = decode_etc1(&compressed, width, height);
+    expect_eq!(decoded.len(), 15);
+    // The padding is only there to fill out the blocks, so the edge pixels
+    // should be as close as the rest of the image.
+    expect_le!(max_diff(&pixels, &decoded), 24);
+
+    let mut blob = vec![];
+    write_etc1_blob(&mut blob, Format::Etc1, &[&compressed], width, height).unwrap();
+    let (header, _) = read_etc1_blob(&blob).unwrap();
+    expect_eq!((header.width, header.height), (5, 3));
+}
+
+#[cfg(feature = "rayon")]
+#[gtest(TextureCompressorTest, Etc1Parallel)]
+fn test_etc1_parallel() {
+    use texture_compressor::etc1::encode_etc1_parallel;
+
+    // An odd size, so that there are partial blocks at the right and bottom.
+    let (width, height) = (203, 97);
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            u32::from_le_bytes([(x * 7) as u8, (y * 5) as u8, ((x ^ y) * 3) as u8, 0xFF])
+        })
+        .collect();
+    for dither in [false, true] {
+        let options = EncodeOptions { dither, ..EncodeOptions::default() };
+        expect_eq!(
+            encode_etc1_parallel(&pixels, width, height, &options),
+            encode_etc1(&pixels, width, height, &options),
+            "dither: {}",
+            dither
+        );
+    }
+    // Empty images have no rows to split up.
+    let options = EncodeOptions::default();
+    for (width, height) in [(0, 0), (0, 4), (4, 0)] {
+        expect_eq!(encode_etc1_parallel(&[], width, height, &options), vec![]);
+    }
+}
+
+#[gtest(TextureCompressorTest, Etc1QualityLevels)]
+fn test_etc1_quality_levels() {
+    // A noisy texture, with edges running both ways so that flip matters.
+    let (width, height) = (16, 16);
+    let mut state = 1u32;
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let noise = (state >> 24) as u8 / 4;
+            let (x, y) = (i % width, i / width);
+            let edge = if (x % 4 < 2) ^ (y % 8 < 4) { 160 } else { 40 };
+            u32::from_le_bytes([edge + noise, 100 + noise, 200 - edge / 2, 0xFF])
+        })
+        .collect();
+    let bytes = |pixels: &[u32]| pixels.iter().flat_map(|p| p.to_le_bytes()).collect::<Vec<u8>>();
+    let psnr_for = |quality| {
+        let compressed = encode_etc1(&pixels, width, height, &EncodeOptions::with_quality(quality));
+        expect_eq!(compressed.len(), compressed_size(width, height));
+        psnr(&bytes(&pixels), &bytes(&decode_etc1(&compressed, width, height)))
+    };
+    let (fast, normal, best) =
+        (psnr_for(Quality::Fast), psnr_for(Quality::Normal), psnr_for(Quality::Best));
+    expect_ge!(best, fast);
+    expect_ge!(best, normal);
+}
+
+#[gtest(TextureCompressorTest, Etc1TryBothOrientations)]
+fn test_etc1_try_both_orientations() {
+    // Four quadrants, which neither split fits exactly. The split whose
+    // averages quantize best is not the one that decodes best.
+    let quadrants = [0xFF804080, 0xFF808080, 0xFFC080C0, 0xFFC0C080];
+    let pixels: [u32; 16] = std::array::from_fn(|i| quadrants[i % 4 / 2 + i / 8 * 2]);
+    let squared_error = |try_both_orientations| {
+        let options = EncodeOptions { try_both_orientations, ..EncodeOptions::default() };
+        let compressed = encode_etc1(&pixels, 4, 4, &options);
+        let decoded = decode_etc1(&compressed, 4, 4);
+        let err: u32 = std::iter::zip(pixels, decoded)
+            .flat_map(|(a, b)| {
+                let (a, b) = (a.to_le_bytes(), b.to_le_bytes());
+                (0..3).map(move |ch| (a[ch].abs_diff(b[ch]) as u32).pow(2))
+            })
+            .sum();
+        (compressed[3] & 1, err)
+    };
+    // Only the best quality level tries both.
+    expect_false!(EncodeOptions::default().try_both_orientations);
+    expect_true!(EncodeOptions::with_quality(Quality::Best).try_both_orientations);
+    let (guessed_flip, guessed_err) = squared_error(false);
+    let (both_flip, both_err) = squared_error(true);
+    expect_ne!(guessed_flip, both_flip);
+    expect_lt!(both_err, guessed_err);
+}
+
+#[gtest(TextureCompressorTest, Etc1Dither)]
+fn test_etc1_dither() {
+    // A smooth horizontal gray gradient, a step of 1 every 4 pixels, so that
+    // runs of neighboring blocks quantize to the same base color.
+    let (width, height) = (256, 4);
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| {
+            let level = (64 + i % width / 4) as u8;
+            u32::from_le_bytes([level, level, level, 0xFF])
+        })
+        .collect();
+    let identical_neighbors = |dither| {
+        let options = EncodeOptions { dither, ..EncodeOptions::default() };
+        let compressed = encode_etc1(&pixels, width, height, &options);
+        let base_colors: Vec<_> = compressed
+            .chunks_exact(8)
+            .map(|block| etc1_block_params(block.try_into().unwrap()).base_colors)
+            .collect();
+        base_colors.windows(2).filter(|pair| pair[0] == pair[1]).count()
+    };
+    let (plain, dithered) = (identical_neighbors(false), identical_neighbors(true));
+    expect_lt!(dithered, plain);
+
+    // The dither is the same every time.
+    let options = EncodeOptions { dither: true, ..EncodeOptions::default() };
+    expect_eq!(
+        encode_etc1(&pixels, width, height, &options),
+        encode_etc1(&pixels, width, height, &options)
+    );
+}
+
+#[gtest(TextureCompressorTest, Etc1SeparateAlpha)]
+fn test_etc1_separate_alpha() {
+    // A color gradient with an unrelated alpha gradient.
+    let (width, height) = (16, 8);
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            u32::from_le_bytes([(x * 16) as u8, (y * 32) as u8, 128, (255 - x * 8 - y * 12) as u8])
+        })
+        .collect();
+    let compressed = encode_etc1_separate_alpha(&pixels, width, height, &EncodeOptions::default());
+    expect_eq!(compressed.len(), Format::Etc1SeparateAlpha.compressed_size(width, height));
+    // The first half is the plain ETC1 encoding of the color.
+    let color = encode_etc1(&pixels, width, height, &EncodeOptions::default());
+    expect_eq!(compressed[..color.len()], color[..]);
+
+    let decoded = decode_etc1_separate_alpha(&compressed, width, height);
+    let alpha = |pixels: &[u32]| pixels.iter().map(|pixel| pixel >> 24).collect::<Vec<_>>();
+    // The alpha only goes through ETC1 as gray, so it has the same error
+    // bound as a smooth gradient in any other channel.
+    for (a, b) in std::iter::zip(alpha(&pixels), alpha(&decoded)) {
+        expect_le!(a.abs_diff(b), 12, "{} vs {}", a, b);
+    }
+    expect_eq!(max_diff(&decode_etc1(&color, width, height), &decoded), 0);
+
+    let image = Etc1Image::encode(
+        &pixels,
+        width,
+        height,
+        Format::Etc1SeparateAlpha,
+        &EncodeOptions::default(),
+    );
+    let mut blob = vec![];
+    image.write_blob(&mut blob).unwrap();
+    expect_eq!(blob[..4], *b"ETCA");
+    let read = Etc1Image::read_blob(&blob).unwrap();
+    expect_eq!(read.decode(), decoded);
+    expect_true!(image.write_ktx(&mut vec![]).is_err());
+}
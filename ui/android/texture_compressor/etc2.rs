@@ -0,0 +1,165 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Whole-image ETC2 RGBA encoding and decoding.
+//!
+//! Each 4x4 block of ETC2 RGBA is a 64-bit EAC block holding alpha, followed by
+//! a 64-bit color block. ETC1 is a subset of ETC2, so the color blocks come
+//! straight from the ETC1 compressor. The EAC blocks are encoded here with a
+//! scalar search, which is much slower than the SIMD color path.
+
+use crate::etc1::{decode_etc1_block, encode_etc1_blocks, store_block, Format};
+use crate::EncodeOptions;
+
+/// Size in bytes of one compressed 4x4 block, alpha and color together.
+pub const BLOCK_SIZE: usize = 16;
+
+/// The EAC modifier tables. Each value is multiplied by the block's multiplier
+/// and added to its base alpha.
+pub const ALPHA_TABLES: [[i16; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// Return the size in bytes of a compressed `width`x`height` image.
+pub fn compressed_size(width: u32, height: u32) -> usize {
+    Format::Etc2Rgba.compressed_size(width, height)
+}
+
+/// Return the alpha values an EAC block with the given header can represent,
+/// indexed by the 3-bit pixel index.
+fn alpha_palette(base: i32, multiplier: i32, table: usize) -> [u8; 8] {
+    ALPHA_TABLES[table]
+        .map(|modifier| (base + i32::from(modifier) * multiplier).clamp(0, 255) as u8)
+}
+
+/// Encode the alpha of a 4x4 block, given as `[y][x]`, to an EAC block.
+pub fn encode_eac_block(alpha: &[[u8; 4]; 4]) -> [u8; 8] {
+    let values = alpha.as_flattened();
+    let (min, max) = (*values.iter().min().unwrap(), *values.iter().max().unwrap());
+    if min == max {
+        // Encoders must not emit a multiplier of 0, so use multiplier 1 with
+        // table 13, whose index 4 has a modifier of 0, for every pixel.
+        return [min, 0x1D, 0x92, 0x49, 0x24, 0x92, 0x49, 0x24];
+    }
+
+    // For each table, pick the multiplier and base which map its extreme
+    // modifiers closest to the extremes of the block, then try the neighbors of
+    // that guess. Pixels take the closest value in the resulting palette.
+    let (min, max) = (i32::from(min), i32::from(max));
+    let mut best: Option<(u32, [u8; 8])> = None;
+    for (table, modifiers) in ALPHA_TABLES.iter().enumerate() {
+        let (lo, hi) = (i32::from(modifiers[3]), i32::from(modifiers[7]));
+        let multiplier_guess = ((max - min + (hi - lo) / 2) / (hi - lo)).clamp(1, 15);
+        for multiplier in (multiplier_guess - 1).max(1)..=(multiplier_guess + 1).min(15) {
+            let base_guess = (min - lo * multiplier + max - hi * multiplier) / 2;
+            for base in (base_guess - 2).max(0)..=(base_guess + 2).min(255) {
+                let palette = alpha_palette(base, multiplier, table);
+                let mut error = 0;
+                let mut indices = 0u64;
+                // Pixels are in column major order, starting from the top bits.
+                for i in 0..16 {
+                    let (index, distance) = palette
+                        .iter()
+                        .map(|value| value.abs_diff(alpha[i % 4][i / 4]) as u32)
+                        .enumerate()
+                        .min_by_key(|(_, distance)| *distance)
+                        .unwrap();
+                    error += distance * distance;
+                    indices = (indices << 3) | index as u64;
+                }
+                if best.is_none_or(|(best_error, _)| error < best_error) {
+                    let word = (base as u64) << 56
+                        | (multiplier as u64) << 52
+                        | (table as u64) << 48
+                        | indices;
+                    best = Some((error, word.to_be_bytes()));
+                }
+            }
+        }
+    }
+    best.unwrap().1
+}
+
+/// Decode a single EAC block.
+///
+/// Returns the alpha values as `[y][x]`.
+pub fn decode_eac_block(block: &[u8; 8]) -> [[u8; 4]; 4] {
+    let word = u64::from_be_bytes(*block);
+    let palette =
+        alpha_palette((word >> 56) as i32, (word >> 52 & 0xF) as i32, (word >> 48 & 0xF) as usize);
+    let mut alpha = [[0; 4]; 4];
+    for i in 0..16 {
+        // Pixels are in column major order, starting from the top bits.
+        alpha[i % 4][i / 4] = palette[(word >> (45 - 3 * i) & 0x7) as usize];
+    }
+    alpha
+}
+
+/// Compress a `width`x`height` image to ETC2 RGBA, returning the blocks in row
+/// major order.
+///
+/// `pixels` should be in RGBA, in row major order with no padding between
+/// rows. `options` apply to the color blocks.
+pub fn encode_etc2_rgba(
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    options: &EncodeOptions,
+) -> Vec<u8> {
+    let color_blocks = encode_etc1_blocks(pixels, width, height, options);
+    let (width, height) = (width as usize, height as usize);
+    let blocks_per_row = width.div_ceil(4);
+    let mut blocks = Vec::with_capacity(color_blocks.len() * 2);
+    for (block_idx, color_block) in color_blocks.chunks_exact(8).enumerate() {
+        let base_x = block_idx % blocks_per_row * 4;
+        let base_y = block_idx / blocks_per_row * 4;
+        // Out of bounds pixels are padded by replicating the nearest edge
+        // pixel, like the color path in `load_input_block`.
+        let alpha: [[u8; 4]; 4] = std::array::from_fn(|y| {
+            std::array::from_fn(|x| {
+                let (x, y) = ((base_x + x).min(width - 1), (base_y + y).min(height - 1));
+                (pixels[y * width + x] >> 24) as u8
+            })
+        });
+        blocks.extend_from_slice(&encode_eac_block(&alpha));
+        blocks.extend_from_slice(color_block);
+    }
+    blocks
+}
+
+/// Decode a `width`x`height` image from ETC2 RGBA blocks in row major order,
+/// as produced by `encode_etc2_rgba`.
+///
+/// Returns the pixels in RGBA.
+pub fn decode_etc2_rgba(blocks: &[u8], width: u32, height: u32) -> Vec<u32> {
+    assert_eq!(blocks.len(), compressed_size(width, height));
+    let mut pixels = vec![0; width as usize * height as usize];
+    for (block_idx, block) in blocks.chunks_exact(BLOCK_SIZE).enumerate() {
+        let alpha = decode_eac_block(block[..8].try_into().unwrap());
+        let color = decode_etc1_block(block[8..].try_into().unwrap());
+        let decoded = std::array::from_fn(|y| {
+            std::array::from_fn(|x| {
+                let [r, g, b] = color[y][x];
+                u32::from_le_bytes([r, g, b, alpha[y][x]])
+            })
+        });
+        store_block(&mut pixels, width, height, block_idx, &decoded);
+    }
+    pixels
+}
@@ -0,0 +1,55 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use texture_compressor::etc2::{
+    compressed_size, decode_eac_block, decode_etc2_rgba, encode_eac_block, encode_etc2_rgba,
+};
+use texture_compressor::EncodeOptions;
+
+#[gtest(TextureCompressorTest, EacSolidBlock)]
+fn test_eac_solid_block() {
+    for alpha in [0, 1, 128, 255] {
+        let block = encode_eac_block(&[[alpha; 4]; 4]);
+        expect_eq!(decode_eac_block(&block), [[alpha; 4]; 4]);
+        // The multiplier, in the top half of the second byte, must not be 0.
+        expect_eq!(block[1] >> 4, 1);
+    }
+}
+
+#[gtest(TextureCompressorTest, EacDecodeBlock)]
+fn test_eac_decode_block() {
+    // Base 100, multiplier 2, table 13 ([-1, -2, -3, -10, 0, 1, 2, 9]). The
+    // first pixel in column major order, (0, 0), has index 7 (+18) and the
+    // second, (0, 1), index 3 (-20). The rest have index 4 (+0).
+    let indices = [7, 3].into_iter().chain([4; 14]).fold(0u64, |acc, index| (acc << 3) | index);
+    let word = (100 << 56) | (2 << 52) | (13 << 48) | indices;
+    let alpha = decode_eac_block(&word.to_be_bytes());
+    expect_eq!(alpha[0][0], 118);
+    expect_eq!(alpha[1][0], 80);
+    expect_eq!(alpha[0][1], 100);
+    expect_eq!(alpha[3][3], 100);
+}
+
+#[gtest(TextureCompressorTest, Etc2RgbaGradient)]
+fn test_etc2_rgba_gradient() {
+    let (width, height) = (8, 4);
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            u32::from_le_bytes([0x20, 0x80, 0xC0, (x * 32 + y * 8) as u8])
+        })
+        .collect();
+    let compressed = encode_etc2_rgba(&pixels, width, height, &EncodeOptions::default());
+    expect_eq!(compressed.len(), compressed_size(width, height));
+    let decoded = decode_etc2_rgba(&compressed, width, height);
+    let max_alpha_diff =
+        std::iter::zip(&pixels, &decoded).map(|(a, b)| (a >> 24).abs_diff(b >> 24)).max().unwrap();
+    expect_le!(max_alpha_diff, 8);
+}
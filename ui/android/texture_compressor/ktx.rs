@@ -0,0 +1,141 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Writing compressed textures as KTX (version 1) files.
+//!
+//! See https://registry.khronos.org/KTX/specs/1.0/ktxspec.v1.html. Files are
+//! written in little endian, which readers must support either way.
+
+use std::io::{self, Write};
+
+use crate::etc1::Format;
+use crate::mipmap::mip_dimensions;
+
+/// The identifier at the start of every KTX 1 file.
+pub const KTX_IDENTIFIER: [u8; 12] =
+    [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// The value of the endianness field, as written by the file's creator.
+const ENDIANNESS: u32 = 0x04030201;
+
+pub const GL_ETC1_RGB8_OES: u32 = 0x8D64;
+pub const GL_COMPRESSED_RGBA8_ETC2_EAC: u32 = 0x9278;
+pub const GL_RGB: u32 = 0x1907;
+pub const GL_RGBA: u32 = 0x1908;
+
+/// Size in bytes of the fixed part of the header, including the identifier.
+pub const KTX_HEADER_SIZE: usize = 64;
+
+/// The key/value pairs written in every file. Blocks are stored top row first.
+const KEY_VALUES: &[(&str, &str)] = &[("KTXorientation", "S=r,T=d")];
+
+/// The fields of a KTX header which matter for compressed 2D textures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KtxHeader {
+    pub gl_internal_format: u32,
+    pub gl_base_internal_format: u32,
+    pub width: u32,
+    pub height: u32,
+    pub mip_level_count: u32,
+    pub key_value_data_size: u32,
+}
+
+impl Format {
+    /// The OpenGL internal format and base internal format of this format, if
+    /// it is a single texture.
+    pub fn gl_formats(self) -> Option<(u32, u32)> {
+        match self {
+            Format::Etc1 => Some((GL_ETC1_RGB8_OES, GL_RGB)),
+            Format::Etc2Rgba => Some((GL_COMPRESSED_RGBA8_ETC2_EAC, GL_RGBA)),
+            Format::Etc1SeparateAlpha => None,
+        }
+    }
+}
+
+/// Return the key/value data section, with each pair padded to 4 bytes.
+fn key_value_data() -> Vec<u8> {
+    let mut data = vec![];
+    for (key, value) in KEY_VALUES {
+        // The key and value are each NUL terminated.
+        let size = key.len() + value.len() + 2;
+        data.extend_from_slice(&(size as u32).to_le_bytes());
+        data.extend_from_slice(key.as_bytes());
+        data.push(0);
+        data.extend_from_slice(value.as_bytes());
+        data.push(0);
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+    data
+}
+
+/// Write `levels`, the compressed mip levels of a `width`x`height` image in
+/// `format` starting from level 0, as a KTX file.
+pub fn write_ktx(
+    writer: &mut impl Write,
+    format: Format,
+    levels: &[&[u8]],
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    let Some((gl_internal_format, gl_base_internal_format)) = format.gl_formats() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "KTX files hold a single texture, so separate alpha can't be written as KTX",
+        ));
+    };
+    let key_value_data = key_value_data();
+    // Compressed formats have no type, a type size of 1, and no format.
+    let fields = [
+        ENDIANNESS,
+        0,
+        1,
+        0,
+        gl_internal_format,
+        gl_base_internal_format,
+        width,
+        height,
+        // Depth, array elements and faces, for a plain 2D texture.
+        0,
+        0,
+        1,
+        levels.len() as u32,
+        key_value_data.len() as u32,
+    ];
+    writer.write_all(&KTX_IDENTIFIER)?;
+    for field in fields {
+        writer.write_all(&field.to_le_bytes())?;
+    }
+    writer.write_all(&key_value_data)?;
+    for (level, blocks) in levels.iter().enumerate() {
+        let (level_width, level_height) = mip_dimensions(width, height, level as u32);
+        assert_eq!(blocks.len(), format.compressed_size(level_width, level_height));
+        // Blocks are 8 or 16 bytes, so no padding is needed after them.
+        writer.write_all(&(blocks.len() as u32).to_le_bytes())?;
+        writer.write_all(blocks)?;
+    }
+    Ok(())
+}
+
+/// Parse the header of a KTX file written by `write_ktx`.
+pub fn read_ktx_header(bytes: &[u8]) -> io::Result<KtxHeader> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+    if bytes.len() < KTX_HEADER_SIZE {
+        return Err(invalid("KTX file is too short for its header"));
+    }
+    if bytes[..12] != KTX_IDENTIFIER {
+        return Err(invalid("KTX file does not start with the KTX 1 identifier"));
+    }
+    let field = |i: usize| u32::from_le_bytes(bytes[12 + i * 4..16 + i * 4].try_into().unwrap());
+    if field(0) != ENDIANNESS {
+        return Err(invalid("KTX file is not little endian"));
+    }
+    Ok(KtxHeader {
+        gl_internal_format: field(4),
+        gl_base_internal_format: field(5),
+        width: field(6),
+        height: field(7),
+        mip_level_count: field(11),
+        key_value_data_size: field(12),
+    })
+}
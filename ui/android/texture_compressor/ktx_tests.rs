@@ -0,0 +1,73 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use texture_compressor::ktx::{
+    read_ktx_header, KtxHeader, GL_COMPRESSED_RGBA8_ETC2_EAC, GL_ETC1_RGB8_OES, GL_RGB, GL_RGBA,
+    KTX_HEADER_SIZE, KTX_IDENTIFIER,
+};
+use texture_compressor::{EncodeOptions, Etc1Image, Format};
+
+#[gtest(TextureCompressorTest, KtxSingleLevel)]
+fn test_ktx_single_level() {
+    let (width, height) = (12, 8);
+    let pixels = vec![0xFF2080C0; (width * height) as usize];
+    let image = Etc1Image::encode(&pixels, width, height, Format::Etc1, &EncodeOptions::default());
+    let mut ktx = vec![];
+    image.write_ktx(&mut ktx).unwrap();
+
+    expect_eq!(ktx[..12], KTX_IDENTIFIER);
+    let header = read_ktx_header(&ktx).unwrap();
+    expect_eq!(
+        header,
+        KtxHeader {
+            gl_internal_format: GL_ETC1_RGB8_OES,
+            gl_base_internal_format: GL_RGB,
+            width,
+            height,
+            mip_level_count: 1,
+            key_value_data_size: 28,
+        }
+    );
+    // The key/value data is followed by the level's size and its blocks.
+    let level_start = KTX_HEADER_SIZE + header.key_value_data_size as usize;
+    let image_size = u32::from_le_bytes(ktx[level_start..level_start + 4].try_into().unwrap());
+    expect_eq!(image_size as usize, image.blocks.len());
+    expect_eq!(ktx[level_start + 4..], image.blocks[..]);
+}
+
+#[gtest(TextureCompressorTest, KtxMipmapsEtc2)]
+fn test_ktx_mipmaps_etc2() {
+    let (width, height) = (8, 4);
+    let pixels = vec![0x802080C0; (width * height) as usize];
+    let image = Etc1Image::encode_with_mipmaps(
+        &pixels,
+        width,
+        height,
+        Format::Etc2Rgba,
+        &EncodeOptions::default(),
+    );
+    let mut ktx = vec![];
+    image.write_ktx(&mut ktx).unwrap();
+
+    let header = read_ktx_header(&ktx).unwrap();
+    expect_eq!(header.gl_internal_format, GL_COMPRESSED_RGBA8_ETC2_EAC);
+    expect_eq!(header.gl_base_internal_format, GL_RGBA);
+    expect_eq!(header.mip_level_count, 4);
+    // Each level's 16-byte blocks follow its 4-byte size. Level 0 has two
+    // blocks, and the rest have one each.
+    let level_sizes = 4 * 4 + 16 * (2 + 1 + 1 + 1);
+    expect_eq!(ktx.len(), KTX_HEADER_SIZE + header.key_value_data_size as usize + level_sizes);
+}
+
+#[gtest(TextureCompressorTest, KtxRejectsBadIdentifier)]
+fn test_ktx_rejects_bad_identifier() {
+    expect_true!(read_ktx_header(&[0; KTX_HEADER_SIZE]).is_err());
+    expect_true!(read_ktx_header(&KTX_IDENTIFIER).is_err());
+}
@@ -4,11 +4,20 @@
 
 #![feature(portable_simd)]
 
+pub mod cli;
+mod codec;
 // Modules public for testing, don't expect stable API.
 mod cxx;
 pub mod dither;
+pub mod etc1;
+pub mod etc2;
+pub mod ktx;
+mod metrics;
+pub mod mipmap;
 pub mod quant;
+pub mod resize;
 pub mod selectors;
+pub mod stats;
 
 use std::simd::prelude::*;
 use std::simd::Simd;
@@ -16,8 +25,16 @@ use std::simd::Simd;
 use bytemuck::cast_slice;
 
 use crate::dither::dither;
-use crate::quant::{quantize_averages, QuantResult};
-use crate::selectors::search_table_and_selectors;
+use crate::quant::{quantize_averages, quantize_averages_per_orientation, QuantResult};
+use crate::selectors::{eval_block_err, search_table_and_selectors};
+
+pub use crate::codec::{
+    checked_image_size, compress_png, compress_png_to_etc1, decode_image,
+    decode_image_with_max_dimension, etc1_to_png, read_png, read_png_with_max_dimension, write_png,
+    Error, Etc1Image, RgbaImage, DEFAULT_MAX_DIMENSION,
+};
+pub use crate::etc1::Format;
+pub use crate::metrics::{perceptual_psnr, psnr};
 
 // We primarily compute with 16-bit integers and a width of 8 fills a 128-bit
 // wide lane (SSE, NEON). TODO(b/393494744): When we introduce multiversioning
@@ -76,8 +93,8 @@ pub fn interleave_etc1(regs: [UReg; 4]) -> [Simd<u64, QUARTER_WIDTH>; 4] {
 /// Load `SIMD_WIDTH` blocks from a region `4*SIMD_WIDTH` wide and `4` tall,
 /// starting at `base_x` and `base_y`.
 ///
-/// Out of bounds pixels are padded with mirroring. For example, `abcdxy`
-/// becomes `abcdxyyx`.
+/// Out of bounds pixels are padded by replicating the nearest edge pixel. For
+/// example, `abcdx` becomes `abcdxxxx`.
 ///
 /// Returns a 3D array of SIMD vectors. Each block is mapped to a SIMD lane
 /// (from left to right), and each pixel in the block is accessed as
@@ -108,11 +125,8 @@ pub fn load_input_block(
                     // Fast path: load in-bound pixel
                     src[(y * row_width + x) as usize]
                 } else {
-                    // Slow path: mirror out-of-bound pixels
-                    // If width or height is 1, mirroring can overflow, so make it saturate.
-                    let xm = if x >= width { (width - 1).saturating_sub(x - width) } else { x };
-                    let ym = if y >= height { (height - 1).saturating_sub(y - height) } else { y };
-                    src[(ym * row_width + xm) as usize]
+                    // Slow path: replicate the nearest edge pixel
+                    src[(y.min(height - 1) * row_width + x.min(width - 1)) as usize]
                 };
             }
             let rgbx = Simd::from_array(buf);
@@ -127,12 +141,94 @@ pub fn load_input_block(
     data
 }
 
+/// How hard the encoder searches for the best encoding of each block.
+///
+/// The output format is the same for every level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quality {
+    /// Guess the selector table from the luminance of each subblock, rather
+    /// than trying them all.
+    Fast,
+    /// Try every selector table, for the orientation whose averages quantize
+    /// best. This is the original search.
+    #[default]
+    Normal,
+    /// Try every selector table as well as the guessed one, for both
+    /// orientations, and keep whichever decodes closest to the input.
+    Best,
+}
+
+/// Options for the ETC1 encoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub quality: Quality,
+    /// Encode each block with both the horizontal split (two 4x2 subblocks,
+    /// flip bit set) and the vertical split (two 2x4 subblocks), and keep
+    /// whichever decodes closest to the input. When disabled, only the split
+    /// whose subblock averages quantize with the least error is searched,
+    /// which halves the search.
+    pub try_both_orientations: bool,
+    /// Compare candidate encodings of a block by their error weighted by the
+    /// luminance of each channel, rather than by the plain RGB error. This
+    /// favors accuracy in green over red and blue. It has no effect unless
+    /// there are several candidates, i.e. with [`Quality::Best`] or
+    /// `try_both_orientations`.
+    pub perceptual: bool,
+    /// Apply an ordered dither to the input before choosing the colors of each
+    /// block, which masks banding in smooth gradients at the cost of a little
+    /// error. The dither depends only on pixel positions, so the output is
+    /// still deterministic.
+    pub dither: bool,
+}
+
+impl EncodeOptions {
+    /// The options for `quality`, trying both orientations only for
+    /// [`Quality::Best`].
+    pub fn with_quality(quality: Quality) -> Self {
+        EncodeOptions {
+            quality,
+            try_both_orientations: quality == Quality::Best,
+            perceptual: false,
+            dither: false,
+        }
+    }
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions::with_quality(Quality::default())
+    }
+}
+
+/// Compress RGB pixels to ETC1 with the default options, plus dithering, which
+/// this entry point has always applied.
+///
+/// See [`compress_etc1_with_options`].
+pub fn compress_etc1(
+    src: &[u32],
+    dst: &mut [u8],
+    width: u32,
+    height: u32,
+    src_row_width: u32,
+    dst_row_width: u32,
+) {
+    compress_etc1_with_options(
+        src,
+        dst,
+        width,
+        height,
+        src_row_width,
+        dst_row_width,
+        &EncodeOptions { dither: true, ..EncodeOptions::default() },
+    );
+}
+
 /// Compress RGB pixels to ETC1.
 ///
 /// `src` should be in RGBA.
 /// `dst` will be filled with compressed ETC1 blocks.
-/// `width` and `height` does not need to be multiple of 4. The boundary pixels
-/// will be padded with unspecified values.
+/// `width` and `height` does not need to be multiple of 4. Blocks overhanging
+/// the edge of the image are padded by replicating the edge pixels.
 /// `src_row_width` and `dst_row_width` specifies the stride, in units of pixels
 /// and blocks, respectively.
 ///
@@ -142,18 +238,37 @@ pub fn load_input_block(
 /// Additionally, we require extensive shuffling when loading inputs, but
 /// stores to the output straight in the order of pixels. Dealing with
 /// unaligned buffers in the latter case is significantly easier.
-pub fn compress_etc1(
+pub fn compress_etc1_with_options(
     src: &[u32],
     dst: &mut [u8],
     width: u32,
     height: u32,
     src_row_width: u32,
     dst_row_width: u32,
+    options: &EncodeOptions,
+) {
+    for dst_y in 0..height.div_ceil(4) {
+        let dst_row = &mut dst[(dst_y * dst_row_width * 8) as usize..];
+        compress_etc1_block_row(src, width, height, src_row_width, dst_y, options, dst_row);
+    }
+}
+
+/// Compress row `dst_y` of the blocks of the image, as in
+/// [`compress_etc1_with_options`], to the start of `dst_row`.
+///
+/// Rows may be compressed in any order, or in parallel, with the same result.
+pub(crate) fn compress_etc1_block_row(
+    src: &[u32],
+    width: u32,
+    height: u32,
+    src_row_width: u32,
+    dst_y: u32,
+    options: &EncodeOptions,
+    dst_row: &mut [u8],
 ) {
-    let dst_height = height.div_ceil(4);
     let dst_width = width.div_ceil(4);
     // Aligned staging buffer. Data is copied into the potentially unaligned
-    // destination buffer at the end of the each row.
+    // destination buffer at the end.
     let mut staging_row = vec![[Simd::splat(0); 4]; (dst_width as usize).div_ceil(SIMD_WIDTH)];
     let copy_len = dst_width as usize * 8;
     // Note on vectorization scheme:
@@ -163,19 +278,54 @@ pub fn compress_etc1(
     // lanes. The one-block-per-lane scheme, more akin to SPMD programming,
     // allows most of our code to be shuffle-free, and works much better with
     // portable SIMD than schemes that heavily shuffles.
-    for dst_y in 0..dst_height {
-        for dst_x0 in (0..dst_width).step_by(SIMD_WIDTH) {
-            let data = load_input_block(src, width, height, src_row_width, dst_x0 * 4, dst_y * 4);
-
-            let data = dither(&data);
-            let QuantResult { lo: hdr0, hi: hdr1, scaled0: ep0, scaled1: ep1 } =
-                quantize_averages(&data);
-            let best_fit = search_table_and_selectors(hdr0, hdr1, &data, [ep0, ep1]);
-            let codewords = interleave_etc1(best_fit);
-            staging_row[dst_x0 as usize / SIMD_WIDTH] = codewords;
-        }
-        let dst_row = &mut dst[(dst_y * dst_row_width * 8) as usize..];
-        let staging_row_bytes = cast_slice(&*staging_row);
-        dst_row[..copy_len].copy_from_slice(&staging_row_bytes[..copy_len]);
+    for dst_x0 in (0..dst_width).step_by(SIMD_WIDTH) {
+        let data = load_input_block(src, width, height, src_row_width, dst_x0 * 4, dst_y * 4);
+
+        let data = if options.dither { dither(&data, dst_x0, dst_y) } else { data };
+        // Without trying both, the orientation whose averages quantize
+        // best is the only one searched.
+        let (both, best);
+        let orientations: &[QuantResult] = if options.try_both_orientations {
+            both = quantize_averages_per_orientation(&data);
+            &both
+        } else {
+            best = [quantize_averages(&data)];
+            &best
+        };
+        let searches: &[Quality] = match options.quality {
+            Quality::Fast => &[Quality::Fast],
+            Quality::Normal => &[Quality::Normal],
+            Quality::Best => &[Quality::Normal, Quality::Fast],
+        };
+        let best_fit = if let ([q], [quality]) = (orientations, searches) {
+            search_table_and_selectors(q.lo, q.hi, &data, [q.scaled0, q.scaled1], *quality).0
+        } else {
+            // The error from the selector search only measures luminance,
+            // so compare the candidates by the error of the decoded block
+            // instead.
+            let candidates = orientations.iter().flat_map(|q| {
+                let base_color = [q.scaled0, q.scaled1];
+                searches.iter().map(move |&quality| {
+                    let (codewords, _) =
+                        search_table_and_selectors(q.lo, q.hi, &data, base_color, quality);
+                    (codewords, eval_block_err(&data, codewords, base_color, options.perceptual))
+                })
+            });
+            candidates
+                .reduce(|best, candidate| {
+                    let better_32 = candidate.1.simd_lt(best.1);
+                    let better = better_32.cast::<i16>();
+                    (
+                        [0, 1, 2, 3].map(|i| better.select(candidate.0[i], best.0[i])),
+                        better_32.select(candidate.1, best.1),
+                    )
+                })
+                .unwrap()
+                .0
+        };
+        let codewords = interleave_etc1(best_fit);
+        staging_row[dst_x0 as usize / SIMD_WIDTH] = codewords;
     }
+    let staging_row_bytes = cast_slice(&*staging_row);
+    dst_row[..copy_len].copy_from_slice(&staging_row_bytes[..copy_len]);
 }
@@ -20,8 +20,8 @@ fn test_interleave_etc1() {
     expect_eq!(result, expected);
 }
 
-#[gtest(TextureCompressorTest, LoadInputMirror)]
-fn test_load_input_mirror() {
+#[gtest(TextureCompressorTest, LoadInputReplicate)]
+fn test_load_input_replicate() {
     // Skip rustfmt to keep this formatted as a 6x2 image.
     #[rustfmt::skip]
     let input = [
@@ -32,13 +32,13 @@ fn test_load_input_mirror() {
         [0xFF, 0xEE, 0xDD, 0xCC],
         [0x99, 0x88, 0x77, 0x66],
         [0x99, 0x88, 0x77, 0x66],
-        [0xFF, 0xEE, 0xDD, 0xCC],
+        [0x99, 0x88, 0x77, 0x66],
     ];
     let expected1 = [
-        [0xBB, 0xAA, 0xAA, 0xBB],
-        [0x55, 0x44, 0x44, 0x55],
-        [0x55, 0x44, 0x44, 0x55],
-        [0xBB, 0xAA, 0xAA, 0xBB],
+        [0xBB, 0xAA, 0xAA, 0xAA],
+        [0x55, 0x44, 0x44, 0x44],
+        [0x55, 0x44, 0x44, 0x44],
+        [0x55, 0x44, 0x44, 0x44],
     ];
     let result = load_input_block(&input, 6, 2, 6, 0, 0);
     for ch in 0..3 {
@@ -47,8 +47,8 @@ fn test_load_input_mirror() {
     }
 }
 
-#[gtest(TextureCompressorTest, LoadInputMirror1x1)]
-fn test_load_input_mirror_1x1() {
+#[gtest(TextureCompressorTest, LoadInputReplicate1x1)]
+fn test_load_input_replicate_1x1() {
     let input = [0x999999];
     let expected = 0x99;
     let result = load_input_block(&input, 1, 1, 1, 0, 0);
@@ -3,42 +3,45 @@
 // found in the LICENSE file.
 
 use std::env;
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::path::Path;
+use std::process::ExitCode;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 || args.len() > 4 {
-        eprintln!("Usage: {} <input.png> <output.png> [etc1_output.etc1]", args[0]);
-        std::process::exit(1);
-    }
+use texture_compressor::cli::{run, USAGE};
+use texture_compressor::Error;
 
-    let input_path = Path::new(&args[1]);
-    let output_path = Path::new(&args[2]);
-    let etc1_output_path = args.get(3).map(|s| Path::new(s));
+/// Writes log records to stderr, prefixed with their level like errors are.
+struct StderrLogger;
 
-    let decoder = png::Decoder::new(BufReader::new(
-        File::open(input_path).expect("Failed to open input file"),
-    ));
-    let mut reader = decoder.read_info().expect("Failed to read PNG info");
-    let mut buf = vec![0; reader.output_buffer_size()];
-    let info = reader.next_frame(&mut buf).expect("Failed to read PNG frame");
-    let bytes = &buf[..info.buffer_size()];
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
 
-    // TODO: Encode & decode through the ETC1 codec before converting back to PNG.
-    if let Some(etc1_output_path) = etc1_output_path {
-        // TODO: Save the ETC1 encoded blob to etc1_output_path for inspection.
-        println!("ETC1 output will be saved to: {}", etc1_output_path.display());
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}: {}", record.level().as_str().to_lowercase(), record.args());
+        }
     }
 
-    let mut encoder = png::Encoder::new(
-        BufWriter::new(File::create(output_path).expect("Failed to create output file")),
-        info.width,
-        info.height,
-    );
-    encoder.set_color(info.color_type);
-    encoder.set_depth(info.bit_depth);
-    let mut writer = encoder.write_header().expect("Failed to write PNG header");
-    writer.write_image_data(bytes).expect("Failed to write PNG data");
+    fn flush(&self) {}
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Warnings are always shown, and every stage with `--verbose`.
+    let verbose = args.iter().any(|arg| arg == "--verbose");
+    log::set_logger(&StderrLogger).unwrap();
+    log::set_max_level(if verbose { log::LevelFilter::Debug } else { log::LevelFilter::Warn });
+    match run(&args) {
+        Ok(report) => {
+            print!("{}", report);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            if let Error::BadArguments(_) = err {
+                eprintln!("{}", USAGE);
+            }
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,46 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Metrics for the quality of a compressed image.
+
+/// Return the peak signal-to-noise ratio in dB between two images, over the
+/// RGB channels.
+///
+/// Both images should be interleaved RGBA bytes of the same size. Alpha is
+/// ignored. Identical images have an infinite PSNR.
+pub fn psnr(original: &[u8], decoded: &[u8]) -> f64 {
+    assert_eq!(original.len(), decoded.len());
+    assert_eq!(original.len() % 4, 0);
+    let (squared_error, samples) =
+        std::iter::zip(original.chunks_exact(4), decoded.chunks_exact(4))
+            .flat_map(|(a, b)| std::iter::zip(&a[..3], &b[..3]))
+            .fold((0u64, 0u64), |(sum, count), (a, b)| {
+                let diff = u64::from(a.abs_diff(*b));
+                (sum + diff * diff, count + 1)
+            });
+    psnr_from_mse(squared_error as f64 / samples as f64)
+}
+
+/// Weights of each channel in [`perceptual_psnr`] (rec601 luma).
+const LUMA_WEIGHT: [f64; 3] = [0.299, 0.587, 0.114];
+
+/// Like [`psnr`], but with the squared error in each channel weighted by its
+/// contribution to luminance, so that errors in green count for more than
+/// errors in red or blue.
+pub fn perceptual_psnr(original: &[u8], decoded: &[u8]) -> f64 {
+    assert_eq!(original.len(), decoded.len());
+    assert_eq!(original.len() % 4, 0);
+    let squared_error: f64 = std::iter::zip(original.chunks_exact(4), decoded.chunks_exact(4))
+        .flat_map(|(a, b)| std::iter::zip(LUMA_WEIGHT, std::iter::zip(&a[..3], &b[..3])))
+        .map(|(weight, (a, b))| weight * f64::from(a.abs_diff(*b)).powi(2))
+        .sum();
+    psnr_from_mse(squared_error / (original.len() / 4) as f64)
+}
+
+fn psnr_from_mse(mse: f64) -> f64 {
+    if mse == 0.0 {
+        return f64::INFINITY;
+    }
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
@@ -0,0 +1,45 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use texture_compressor::{perceptual_psnr, psnr};
+
+#[gtest(TextureCompressorTest, PsnrIdentical)]
+fn test_psnr_identical() {
+    let image = [10, 20, 30, 255, 40, 50, 60, 255];
+    expect_eq!(psnr(&image, &image), f64::INFINITY);
+}
+
+#[gtest(TextureCompressorTest, PsnrKnownDifference)]
+fn test_psnr_known_difference() {
+    let original = [100; 16];
+    let mut decoded = [100; 16];
+    // One of the 12 RGB samples is off by 10, so the MSE is 100 / 12.
+    decoded[5] = 110;
+    // Differences in alpha are ignored.
+    decoded[3] = 0;
+    let expected = 10.0 * (255.0f64 * 255.0 * 12.0 / 100.0).log10();
+    expect_lt!((psnr(&original, &decoded) - expected).abs(), 1e-9);
+    expect_lt!((psnr(&original, &decoded) - 38.92).abs(), 0.01);
+}
+
+#[gtest(TextureCompressorTest, PerceptualPsnr)]
+fn test_perceptual_psnr() {
+    let original = [100; 8];
+    let mut red = original;
+    red[0] = 110;
+    let mut green = original;
+    green[1] = 110;
+    // The same error costs more in green than in red.
+    expect_gt!(perceptual_psnr(&original, &red), perceptual_psnr(&original, &green));
+    // One of 2 pixels is off by 10 in green, so the MSE is 0.587 * 100 / 2.
+    let expected = 10.0 * (255.0f64 * 255.0 * 2.0 / 58.7).log10();
+    expect_lt!((perceptual_psnr(&original, &green) - expected).abs(), 1e-9);
+    expect_eq!(perceptual_psnr(&original, &original), f64::INFINITY);
+}
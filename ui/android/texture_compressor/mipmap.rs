@@ -0,0 +1,64 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Mipmap generation.
+//!
+//! Each level halves the dimensions of the one above it, rounding down but
+//! never going below 1, until the level is 1x1.
+
+/// Return the dimensions of mip level `level` of a `width`x`height` image.
+pub fn mip_dimensions(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Return the number of levels in a full mip chain for a `width`x`height`
+/// image, including level 0.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Downsample a `width`x`height` RGBA image to the next mip level with a box
+/// filter, returning it along with its dimensions.
+///
+/// Each output pixel is the rounded average of the 2x2 input pixels it covers.
+/// When a dimension is odd, the last row or column is dropped; when it is 1,
+/// the same row or column is used twice.
+pub fn downsample(pixels: &[u32], width: u32, height: u32) -> (Vec<u32>, u32, u32) {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+    let (dst_width, dst_height) = mip_dimensions(width, height, 1);
+    let src = |x: u32, y: u32| pixels[(y.min(height - 1) * width + x.min(width - 1)) as usize];
+    let mut dst = Vec::with_capacity(dst_width as usize * dst_height as usize);
+    for y in 0..dst_height {
+        for x in 0..dst_width {
+            let quad = [
+                src(2 * x, 2 * y),
+                src(2 * x + 1, 2 * y),
+                src(2 * x, 2 * y + 1),
+                src(2 * x + 1, 2 * y + 1),
+            ];
+            let mut sums = [0u32; 4];
+            for pixel in quad {
+                for (sum, channel) in sums.iter_mut().zip(pixel.to_le_bytes()) {
+                    *sum += u32::from(channel);
+                }
+            }
+            dst.push(u32::from_le_bytes(sums.map(|sum| ((sum + 2) / 4) as u8)));
+        }
+    }
+    (dst, dst_width, dst_height)
+}
+
+/// Return every level of the mip chain of a `width`x`height` RGBA image below
+/// level 0, along with their dimensions.
+pub fn mip_chain(pixels: &[u32], width: u32, height: u32) -> Vec<(Vec<u32>, u32, u32)> {
+    let mut levels: Vec<(Vec<u32>, u32, u32)> = vec![];
+    for _ in 1..mip_level_count(width, height) {
+        let (pixels, width, height) = match levels.last() {
+            Some((pixels, width, height)) => (pixels.as_slice(), *width, *height),
+            None => (pixels, width, height),
+        };
+        levels.push(downsample(pixels, width, height));
+    }
+    levels
+}
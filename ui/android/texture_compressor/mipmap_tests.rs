@@ -0,0 +1,62 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use texture_compressor::etc1::read_etc1_blob;
+use texture_compressor::mipmap::{downsample, mip_chain, mip_dimensions, mip_level_count};
+use texture_compressor::{EncodeOptions, Etc1Image, Format};
+
+#[gtest(TextureCompressorTest, MipLevelCount)]
+fn test_mip_level_count() {
+    expect_eq!(mip_level_count(64, 32), 7);
+    expect_eq!(mip_level_count(1, 1), 1);
+    expect_eq!(mip_level_count(5, 3), 3);
+    expect_eq!(mip_dimensions(64, 32, 5), (2, 1));
+    expect_eq!(mip_dimensions(64, 32, 6), (1, 1));
+    expect_eq!(mip_dimensions(5, 3, 1), (2, 1));
+}
+
+#[gtest(TextureCompressorTest, MipDownsample)]
+fn test_mip_downsample() {
+    // A 3x2 image: the last column is dropped, and the rest is averaged.
+    let pixels = [0x00000000, 0x04040404, 0xFFFFFFFF, 0x08080808, 0x0C0C0C0C, 0xFFFFFFFF];
+    expect_eq!(downsample(&pixels, 3, 2), (vec![0x06060606], 1, 1));
+    // A 1-pixel dimension is reused rather than halved to 0.
+    let pixels = [0x00000010, 0x00000020, 0x00000031, 0x00000041];
+    expect_eq!(downsample(&pixels, 4, 1), (vec![0x00000018, 0x00000039], 2, 1));
+}
+
+#[gtest(TextureCompressorTest, MipChain)]
+fn test_mip_chain() {
+    let (width, height) = (64, 32);
+    let pixels = vec![0xFF2080C0; (width * height) as usize];
+    let chain = mip_chain(&pixels, width, height);
+    let dimensions: Vec<(u32, u32)> = chain.iter().map(|(_, w, h)| (*w, *h)).collect();
+    expect_eq!(dimensions, vec![(32, 16), (16, 8), (8, 4), (4, 2), (2, 1), (1, 1)]);
+    expect_true!(chain.iter().all(|(level, _, _)| level.iter().all(|p| *p == 0xFF2080C0)));
+
+    let image = Etc1Image::encode_with_mipmaps(
+        &pixels,
+        width,
+        height,
+        Format::Etc1,
+        &EncodeOptions::default(),
+    );
+    expect_eq!(image.mipmaps.len(), 6);
+    let mut blob = vec![];
+    image.write_blob(&mut blob).unwrap();
+    let (header, levels) = read_etc1_blob(&blob).unwrap();
+    expect_eq!(header.levels.len(), 7);
+    expect_eq!((header.levels[1].width, header.levels[1].height), (32, 16));
+    // 128 + 32 + 8 + 2 blocks for the levels down to 8x4, then 1 each for the
+    // three levels below that.
+    expect_eq!(header.block_count, 128 + 32 + 8 + 2 + 3);
+    expect_eq!(levels[6].len(), 8);
+    expect_eq!(Etc1Image::read_blob(&blob).unwrap(), image);
+}
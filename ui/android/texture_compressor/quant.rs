@@ -215,16 +215,36 @@ fn quantize_endpoint_pairs(
     }
 }
 
+/// Quantize the averages with and without flip, searching through individual /
+/// differential modes for each.
 #[inline]
-/// Search through flip / no-flip and individual / differential modes, and
-/// return the result with the least MSE from original pixels.
-pub fn quantize_averages(data: &[[[Reg; 3]; 4]; 4]) -> QuantResult {
+fn quantize_orientations(data: &[[[Reg; 3]; 4]; 4]) -> [QuantResultWithErr; 2] {
     let stats = prepare_averages(&data);
 
     let flip =
         quantize_endpoint_pairs(stats[0].avg, stats[1].avg, stats[0].sum, stats[1].sum, true);
     let no_flip =
         quantize_endpoint_pairs(stats[2].avg, stats[3].avg, stats[2].sum, stats[3].sum, false);
+    [flip, no_flip]
+}
+
+/// Like [`quantize_averages`], but return the best result for both flip and
+/// no-flip, in that order, so that the caller can pick between them later.
+#[inline]
+pub fn quantize_averages_per_orientation(data: &[[[Reg; 3]; 4]; 4]) -> [QuantResult; 2] {
+    quantize_orientations(data).map(|q| QuantResult {
+        lo: q.lo,
+        hi: q.hi,
+        scaled0: q.scaled0,
+        scaled1: q.scaled1,
+    })
+}
+
+#[inline]
+/// Search through flip / no-flip and individual / differential modes, and
+/// return the result with the least MSE from original pixels.
+pub fn quantize_averages(data: &[[[Reg; 3]; 4]; 4]) -> QuantResult {
+    let [flip, no_flip] = quantize_orientations(data);
 
     let prefer_flip = flip.err.simd_lt(no_flip.err).cast::<i16>();
     QuantResult {
@@ -0,0 +1,69 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Downscaling of input images which are larger than they need to be, before
+//! they are compressed.
+
+/// Return the dimensions of a `width`x`height` image scaled down to fit in
+/// `max_width`x`max_height`, keeping its aspect ratio to the nearest pixel. An
+/// image which already fits keeps its dimensions.
+pub fn fit_dimensions(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    assert!(max_width > 0 && max_height > 0);
+    if width <= max_width && height <= max_height {
+        return (width, height);
+    }
+    let scale = |size: u32, limit: u32, total: u32| {
+        let scaled = (u64::from(size) * u64::from(limit) + u64::from(total) / 2) / u64::from(total);
+        (scaled as u32).max(1)
+    };
+    // Whichever dimension overshoots its limit by more decides the scale.
+    if u64::from(width) * u64::from(max_height) >= u64::from(height) * u64::from(max_width) {
+        (max_width, scale(height, max_width, width))
+    } else {
+        (scale(width, max_height, height), max_height)
+    }
+}
+
+/// Downscale a `width`x`height` RGBA image to `dst_width`x`dst_height` with a
+/// box filter.
+///
+/// Each output pixel is the rounded average of the input pixels it covers,
+/// counting pixels which are only partly covered as if they were wholly
+/// covered.
+pub fn box_downscale(
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    dst_width: u32,
+    dst_height: u32,
+) -> Vec<u32> {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+    assert!(0 < dst_width && dst_width <= width && 0 < dst_height && dst_height <= height);
+    // The range of input pixels covered by output pixel `i` along a dimension.
+    let covered = |i: u32, dst_size: u32, size: u32| {
+        let (i, dst_size, size) = (u64::from(i), u64::from(dst_size), u64::from(size));
+        let start = i * size / dst_size;
+        let end = ((i + 1) * size).div_ceil(dst_size);
+        start as u32..end as u32
+    };
+    let mut dst = Vec::with_capacity(dst_width as usize * dst_height as usize);
+    for y in 0..dst_height {
+        let rows = covered(y, dst_height, height);
+        for x in 0..dst_width {
+            let columns = covered(x, dst_width, width);
+            let count = rows.len() as u32 * columns.len() as u32;
+            let mut sums = [0u32; 4];
+            for src_y in rows.clone() {
+                for src_x in columns.clone() {
+                    let pixel = pixels[(src_y * width + src_x) as usize];
+                    for (sum, channel) in sums.iter_mut().zip(pixel.to_le_bytes()) {
+                        *sum += u32::from(channel);
+                    }
+                }
+            }
+            dst.push(u32::from_le_bytes(sums.map(|sum| ((sum + count / 2) / count) as u8)));
+        }
+    }
+    dst
+}
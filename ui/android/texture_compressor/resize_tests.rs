@@ -0,0 +1,44 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use texture_compressor::resize::{box_downscale, fit_dimensions};
+
+#[gtest(TextureCompressorTest, FitDimensions)]
+fn test_fit_dimensions() {
+    expect_eq!(fit_dimensions(100, 50, 64, 64), (64, 32));
+    expect_eq!(fit_dimensions(50, 100, 64, 64), (32, 64));
+    // The height has further to go, so it decides the scale.
+    expect_eq!(fit_dimensions(100, 90, 80, 45), (50, 45));
+    // Rounded, but never down to 0.
+    expect_eq!(fit_dimensions(100, 51, 64, 64), (64, 33));
+    expect_eq!(fit_dimensions(1000, 1, 10, 10), (10, 1));
+    // An image which fits isn't resized, even to fill the limit.
+    expect_eq!(fit_dimensions(30, 20, 64, 64), (30, 20));
+    expect_eq!(fit_dimensions(64, 64, 64, 64), (64, 64));
+}
+
+#[gtest(TextureCompressorTest, BoxDownscale)]
+fn test_box_downscale() {
+    // Halving averages each 2x2 square.
+    let pixels = [0x00000000, 0x04040404, 0x08080808, 0x0C0C0C0C];
+    expect_eq!(box_downscale(&pixels, 2, 2, 1, 1), vec![0x06060606]);
+    // From 3 columns to 2, the middle column counts towards both.
+    let pixels = [0x00000000, 0x00000030, 0x00000060];
+    expect_eq!(box_downscale(&pixels, 3, 1, 2, 1), vec![0x00000018, 0x00000048]);
+    // The same size is a copy.
+    expect_eq!(box_downscale(&pixels, 3, 1, 3, 1), pixels);
+
+    let (width, height) = (100, 50);
+    let pixels = vec![0xFF2080C0; (width * height) as usize];
+    let (dst_width, dst_height) = fit_dimensions(width, height, 64, 64);
+    let resized = box_downscale(&pixels, width, height, dst_width, dst_height);
+    expect_eq!((dst_width, dst_height), (64, 32));
+    expect_eq!(resized, vec![0xFF2080C0; 64 * 32]);
+}
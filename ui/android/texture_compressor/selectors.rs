@@ -8,7 +8,7 @@
 use std::simd::prelude::*;
 use std::simd::{Mask, Simd};
 
-use crate::{Reg, Reg32, UReg, SIMD_WIDTH};
+use crate::{Quality, Reg, Reg32, UReg, SIMD_WIDTH};
 
 // Selector tables from ETC1 spec. The negative part is omitted due to symmetry.
 pub const TABLES: [[i16; 2]; 8] =
@@ -73,6 +73,77 @@ pub struct Fit {
     pub selector_hi: UReg,
 }
 
+/// Weights of each channel in the grayscale error metric (cf. rec601). They add
+/// up to 64.
+const RGB_WEIGHT: [i16; 3] = [19, 38, 7];
+
+/// Compute gray(q - x), see [`fit_subblock`].
+#[inline]
+fn gray_diff(base_color: [Reg; 3], pixel: [Reg; 3]) -> Reg {
+    let mut base_err = Reg::splat(0);
+    for ch in 0..3 {
+        base_err += (base_color[ch] - pixel[ch]) * Simd::splat(RGB_WEIGHT[ch]);
+    }
+    base_err
+}
+
+/// Find the optimal selectors for a subblock, given the selector table values
+/// `[sm, lg]` for each lane.
+///
+/// Returns the fit with `table_idx` left as 0, for the caller to fill in.
+#[inline]
+fn fit_subblock(data: &[[[Reg; 3]; 4]], base_color: [Reg; 3], sel_table: [Reg; 2]) -> Fit {
+    let mut outer_err = Reg32::splat(0);
+    let mut selector_lo = UReg::splat(0);
+    let mut selector_hi = UReg::splat(0);
+    for (y, row) in data.iter().enumerate().take(2) {
+        for (x, &pixel) in row.iter().enumerate() {
+            // Below, we search for the optimal selector among [-lg, -sm, sm, lg] (sm
+            // and lg is from the selector table).
+            //
+            // We use the error metric:
+            //   abs(gray(q + s - x))
+            //   where q = quantized average, s = selector, x = pixel before compression
+            //         gray(p) = 19*p.r + 38*p.g + 7*p.b  (cf. rec601)
+            //
+            // Note that this is abs(gray(..)) not gray(abs(..)), i.e. the absolute
+            // is taken after computing to grayscale. This allows precomputing
+            // gray(q-x), then exploiting the fact that the selector is same for all
+            // three channels to calculate the final error with a single addition.
+            //
+            // We will first precompute gray(q - x).
+            let base_err = gray_diff(base_color, pixel);
+
+            // Now, the sign of selector can be easily decided. To minimize the
+            // absolute value, the selector should be the opposite sign of
+            // gray(q - x).
+            let prefer_neg = base_err.simd_gt(Simd::splat(0));
+
+            // Finally, we compute the error metric for both sm and lg and decide the
+            // winner.
+            let base_err_abs = base_err.abs();
+            // Subtract in the direction that the final error metric is smaller.
+            // The selector is same for all three channels, so just multiply it by the
+            // total weight.
+            let weight_sum = Reg::splat(64);
+            let err_sm = (base_err_abs - sel_table[0] * weight_sum).abs();
+            let err_lg = (base_err_abs - sel_table[1] * weight_sum).abs();
+            let prefer_lg = err_lg.simd_lt(err_sm);
+
+            // The error can be fairly large (a crude upper bound is 255*64). To avoid
+            // overflow after squaring, we use widening multiply and accumulate. This
+            // is somewhat expensive.
+            let best_err = prefer_lg.select(err_lg, err_sm).cast::<i32>();
+            outer_err += best_err * best_err;
+
+            let pixel_idx = (y + x * 4) as u16;
+            selector_lo |= prefer_lg.select(UReg::splat(1 << pixel_idx), UReg::splat(0));
+            selector_hi |= prefer_neg.select(UReg::splat(1 << pixel_idx), UReg::splat(0));
+        }
+    }
+    Fit { err: outer_err, table_idx: UReg::splat(0), selector_lo, selector_hi }
+}
+
 /// Search for the optimal table and selectors for a subblock.
 ///
 /// `data` should be in flipped layout, i.e. 4x2.
@@ -86,70 +157,18 @@ pub fn search_table_and_selectors_subblock(data: &[[[Reg; 3]; 4]], base_color: [
         .iter()
         .enumerate()
         .fold(None, |best_fit, (table_idx, sel_table)| {
-            let mut outer_err = Reg32::splat(0);
-            let mut selector_lo = UReg::splat(0);
-            let mut selector_hi = UReg::splat(0);
-            for y in 0..2 {
-                for x in 0..4 {
-                    // Below, we search for the optimal selector among [-lg, -sm, sm, lg] (sm
-                    // and lg is from the selector table).
-                    //
-                    // We use the error metric:
-                    //   abs(gray(q + s - x))
-                    //   where q = quantized average, s = selector, x = pixel before compression
-                    //         gray(p) = 19*p.r + 38*p.g + 7*p.b  (cf. rec601)
-                    //
-                    // Note that this is abs(gray(..)) not gray(abs(..)), i.e. the absolute
-                    // is taken after computing to grayscale. This allows precomputing
-                    // gray(q-x), then exploiting the fact that the selector is same for all
-                    // three channels to calculate the final error with a single addition.
-                    //
-                    // We will first precompute gray(q - x).
-                    let mut base_err = Reg::splat(0);
-                    let rgb_weight = [19, 38, 7];
-                    for ch in 0..3 {
-                        base_err += (base_color[ch] - data[y][x][ch]) * Simd::splat(rgb_weight[ch]);
-                    }
-
-                    // Now, the sign of selector can be easily decided. To minimize the
-                    // absolute value, the selector should be the opposite sign of
-                    // gray(q - x).
-                    let prefer_neg = base_err.simd_gt(Simd::splat(0));
-
-                    // Finally, we compute the error metric for both sm and lg and decide the
-                    // winner.
-                    let base_err_abs = base_err.abs();
-                    // Subtract in the direction that the final error metric is smaller.
-                    // The selector is same for all three channels, so just multiply it by the
-                    // total weight.
-                    let weight_sum = 64;
-                    let err_sm = (base_err_abs - Reg::splat(sel_table[0] * weight_sum)).abs();
-                    let err_lg = (base_err_abs - Reg::splat(sel_table[1] * weight_sum)).abs();
-                    let prefer_lg = err_lg.simd_lt(err_sm);
-
-                    // The error can be fairly large (a crude upper bound is 255*64). To avoid
-                    // overflow after squaring, we use widening multiply and accumulate. This
-                    // is somewhat expensive.
-                    let best_err = prefer_lg.select(err_lg, err_sm).cast::<i32>();
-                    outer_err += best_err * best_err;
-
-                    let pixel_idx = (y + x * 4) as u16;
-                    selector_lo |= prefer_lg.select(UReg::splat(1 << pixel_idx), UReg::splat(0));
-                    selector_hi |= prefer_neg.select(UReg::splat(1 << pixel_idx), UReg::splat(0));
-                }
-            }
-
+            let fit = fit_subblock(data, base_color, sel_table.map(Simd::splat));
             let table_idx = UReg::splat(table_idx as u16);
             match best_fit {
-                None => Some(Fit { err: outer_err, table_idx, selector_lo, selector_hi }),
+                None => Some(Fit { table_idx, ..fit }),
                 Some(best) => {
-                    let lt_32 = outer_err.simd_lt(best.err);
+                    let lt_32 = fit.err.simd_lt(best.err);
                     let lt = lt_32.cast::<i16>();
                     Some(Fit {
-                        err: lt_32.select(outer_err, best.err),
+                        err: lt_32.select(fit.err, best.err),
                         table_idx: lt.select(table_idx, best.table_idx),
-                        selector_lo: lt.select(selector_lo, best.selector_lo),
-                        selector_hi: lt.select(selector_hi, best.selector_hi),
+                        selector_lo: lt.select(fit.selector_lo, best.selector_lo),
+                        selector_hi: lt.select(fit.selector_hi, best.selector_hi),
                     })
                 }
             }
@@ -157,17 +176,52 @@ pub fn search_table_and_selectors_subblock(data: &[[[Reg; 3]; 4]], base_color: [
         .unwrap()
 }
 
+/// Guess the table for a subblock from how far its pixels are from the base
+/// color in luminance, then find the selectors for that table alone.
+///
+/// This is several times cheaper than [`search_table_and_selectors_subblock`],
+/// at the cost of sometimes picking a worse table.
+#[inline]
+pub fn guess_table_and_selectors_subblock(data: &[[[Reg; 3]; 4]], base_color: [Reg; 3]) -> Fit {
+    assert_eq!(data.len(), 2);
+    // Mean of abs(gray(q - x)), scaled back down by the total weight so that
+    // it is comparable to the table values. Each term is at most 255, so the
+    // sum fits in 16 bits.
+    let mut deviation = Reg::splat(0);
+    for row in data {
+        for pixel in row {
+            deviation += gray_diff(base_color, *pixel).abs() >> 6;
+        }
+    }
+    let deviation = deviation >> 3;
+
+    // Pick the table whose values are centered closest to the mean deviation.
+    let mut table_idx = UReg::splat(0);
+    let mut sel_table = TABLES[0].map(Simd::splat);
+    let mut best_dist = (deviation - Simd::splat((TABLES[0][0] + TABLES[0][1]) / 2)).abs();
+    for (idx, table) in TABLES.iter().enumerate().skip(1) {
+        let dist = (deviation - Simd::splat((table[0] + table[1]) / 2)).abs();
+        let closer = dist.simd_lt(best_dist);
+        best_dist = closer.select(dist, best_dist);
+        table_idx = closer.select(UReg::splat(idx as u16), table_idx);
+        sel_table = [0, 1].map(|i| closer.select(Simd::splat(table[i]), sel_table[i]));
+    }
+    Fit { table_idx, ..fit_subblock(data, base_color, sel_table) }
+}
+
 /// Search through possible selector tables and selector values for each
-/// subblock.
+/// subblock. With `Quality::Fast`, the table is guessed instead.
 ///
-/// Returns: Four 16-bit codewords coding the optimal coefficients.
+/// Returns: Four 16-bit codewords coding the optimal coefficients, and the
+/// total error of both subblocks.
 #[inline]
 pub fn search_table_and_selectors(
     mut hdr0: UReg,
     hdr1: UReg,
     data: &[[[Reg; 3]; 4]; 4],
     base_color: [[Reg; 3]; 2],
-) -> [UReg; 4] {
+    quality: Quality,
+) -> ([UReg; 4], Reg32) {
     // We need to work on pixels in the first subblock, then the second. To allow
     // uniform indices, the flip functions takes care of moving the first
     // subblock to the top half and the second to bottom half. We will fix up
@@ -177,12 +231,15 @@ pub fn search_table_and_selectors(
 
     let mut selector_lo = UReg::splat(0);
     let mut selector_hi = UReg::splat(0);
+    let mut err = Reg32::splat(0);
 
+    let search_subblock = match quality {
+        Quality::Fast => guess_table_and_selectors_subblock,
+        Quality::Normal | Quality::Best => search_table_and_selectors_subblock,
+    };
     for subblock in 0..2 {
-        let best_fit = search_table_and_selectors_subblock(
-            &permuted_data[subblock * 2..subblock * 2 + 2],
-            base_color[subblock],
-        );
+        let best_fit =
+            search_subblock(&permuted_data[subblock * 2..subblock * 2 + 2], base_color[subblock]);
         let subblock_bit = match subblock {
             0 => 5,
             1 => 2,
@@ -191,8 +248,64 @@ pub fn search_table_and_selectors(
         hdr0 |= best_fit.table_idx << subblock_bit;
         selector_lo |= best_fit.selector_lo << (subblock as u16 * 2);
         selector_hi |= best_fit.selector_hi << (subblock as u16 * 2);
+        err += best_fit.err;
     }
     selector_lo = flip_selectors(selector_lo, !flip);
     selector_hi = flip_selectors(selector_hi, !flip);
-    [selector_lo, selector_hi, hdr0, hdr1]
+    ([selector_lo, selector_hi, hdr0, hdr1], err)
+}
+
+/// Compute the squared RGB error between `data` and the block it would decode
+/// to from `codewords`, as returned by [`search_table_and_selectors`].
+///
+/// Unlike the error returned alongside the codewords, this accounts for
+/// differences in color as well as in luminance, so it can be used to compare
+/// encodings with different base colors.
+///
+/// If `perceptual` is set, the error in each channel is weighted by its
+/// contribution to luminance, as in [`fit_subblock`]. Errors computed with and
+/// without weights are on different scales and should not be compared.
+#[inline]
+pub fn eval_block_err(
+    data: &[[[Reg; 3]; 4]; 4],
+    codewords: [UReg; 4],
+    base_color: [[Reg; 3]; 2],
+    perceptual: bool,
+) -> Reg32 {
+    let weight = if perceptual { RGB_WEIGHT } else { [1; 3] }.map(|w| Simd::splat(i32::from(w)));
+    let [selector_lo, selector_hi, hdr0, _] = codewords;
+    let flip = (hdr0 & UReg::splat(1)).simd_ne(UReg::splat(0));
+    // The table values [sm, lg] of each subblock.
+    let sel_tables = [5, 2].map(|subblock_bit| {
+        let table_idx = (hdr0 >> subblock_bit) & UReg::splat(0b111);
+        TABLES.iter().enumerate().fold([Reg::splat(0); 2], |acc, (idx, table)| {
+            let matches = table_idx.simd_eq(UReg::splat(idx as u16)).cast::<i16>();
+            [0, 1].map(|i| matches.select(Simd::splat(table[i]), acc[i]))
+        })
+    });
+
+    let mut err = Reg32::splat(0);
+    for (y, row) in data.iter().enumerate() {
+        for (x, pixel) in row.iter().enumerate() {
+            let second = if y >= 2 { flip } else { Mask::splat(false) }
+                | if x >= 2 { !flip } else { Mask::splat(false) };
+            let second = second.cast::<i16>();
+            let pixel_bit = UReg::splat(1 << (x * 4 + y));
+            let is_large = (selector_lo & pixel_bit).simd_ne(UReg::splat(0)).cast::<i16>();
+            let is_neg = (selector_hi & pixel_bit).simd_ne(UReg::splat(0)).cast::<i16>();
+            let sm = second.select(sel_tables[1][0], sel_tables[0][0]);
+            let lg = second.select(sel_tables[1][1], sel_tables[0][1]);
+            let magnitude = is_large.select(lg, sm);
+            let modifier = is_neg.select(-magnitude, magnitude);
+            for ((channel, weight), (first_base, second_base)) in
+                pixel.iter().zip(weight).zip(base_color[0].iter().zip(base_color[1]))
+            {
+                let base = second.select(second_base, *first_base);
+                let decoded = (base + modifier).simd_clamp(Simd::splat(0), Simd::splat(255));
+                let diff = (decoded - channel).cast::<i32>();
+                err += diff * diff * weight;
+            }
+        }
+    }
+    err
 }
@@ -10,7 +10,7 @@ chromium::import! {
 }
 
 use texture_compressor::selectors::{
-    flip_pixels, flip_selectors, search_table_and_selectors_subblock, TABLES,
+    eval_block_err, flip_pixels, flip_selectors, search_table_and_selectors_subblock, TABLES,
 };
 
 #[gtest(TextureCompressorTest, FlipPixels)]
@@ -88,3 +88,31 @@ fn test_search_table_and_selectors_max() {
     expect_eq!(result.selector_lo, Simd::splat(0b0011_0011_0011_0011)); // All large
     expect_eq!(result.table_idx, Simd::splat(7)); // Largest variance table
 }
+
+#[gtest(TextureCompressorTest, EvalBlockErr)]
+fn test_eval_block_err() {
+    // Table 0 for both subblocks, all selectors small and positive, so every
+    // pixel decodes to the base color plus 2.
+    let codewords = [Simd::splat(0), Simd::splat(0), Simd::splat(0), Simd::splat(0)];
+    let base_color = [[Simd::splat(100); 3]; 2];
+    let exact = [[[Simd::splat(102); 3]; 4]; 4];
+    expect_eq!(eval_block_err(&exact, codewords, base_color, false), Simd::splat(0));
+    // Off by 2 in each of 3 channels of 16 pixels.
+    let off = [[[Simd::splat(100); 3]; 4]; 4];
+    expect_eq!(eval_block_err(&off, codewords, base_color, false), Simd::splat(16 * 3 * 4));
+}
+
+#[gtest(TextureCompressorTest, EvalBlockErrPerceptual)]
+fn test_eval_block_err_perceptual() {
+    // Every pixel is 28 below the base color in red and blue, but 32 above it
+    // in green. With table 0, the closest modifier by plain RGB error is -8,
+    // but weighting by luminance makes green dominate and +8 wins.
+    let base_color = [[Simd::splat(128); 3]; 2];
+    let data = [[[Simd::splat(100), Simd::splat(160), Simd::splat(100)]; 4]; 4];
+    // All selectors large; negative or positive.
+    let minus_8 = [Simd::splat(0xFFFF), Simd::splat(0xFFFF), Simd::splat(0), Simd::splat(0)];
+    let plus_8 = [Simd::splat(0xFFFF), Simd::splat(0), Simd::splat(0), Simd::splat(0)];
+    let err = |codewords, perceptual| eval_block_err(&data, codewords, base_color, perceptual);
+    expect_true!(err(minus_8, false).simd_lt(err(plus_8, false)).all());
+    expect_true!(err(plus_8, true).simd_lt(err(minus_8, true)).all());
+}
@@ -0,0 +1,85 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Per-block statistics of a compressed image, for finding the blocks that
+//! compress worst.
+
+use std::io::{self, Write};
+
+use crate::etc1::{etc1_block_params, Etc1BlockParams};
+use crate::Etc1Image;
+
+/// The encoding of one block of level 0 of an image, and how far it is from
+/// the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockStats {
+    /// The coordinates of the block, in blocks.
+    pub x: u32,
+    pub y: u32,
+    /// The parameters of the block's color, which is the whole block for ETC1.
+    /// Alpha is not described.
+    pub params: Etc1BlockParams,
+    /// The sum of the squared RGB differences between the decoded block and
+    /// the input, over the pixels inside the image.
+    pub squared_error: u64,
+}
+
+/// The header row of [`write_stats_csv`].
+pub const STATS_CSV_HEADER: &str = "block_x,block_y,flip,differential,table0,table1,\
+                                    base_color0,base_color1,squared_error";
+
+/// Return the statistics of each block of level 0 of `image`, in row major
+/// order, given the RGBA `pixels` it was compressed from.
+pub fn block_stats(image: &Etc1Image, pixels: &[u32]) -> Vec<BlockStats> {
+    let (width, height) = (image.width as usize, image.height as usize);
+    assert_eq!(pixels.len(), width * height);
+    let decoded = image.decode();
+    let blocks_per_row = width.div_ceil(4);
+    let block_count = image.blocks.len() / image.format.block_size();
+    (0..block_count)
+        .map(|block_idx| {
+            let (x, y) = (block_idx % blocks_per_row, block_idx / blocks_per_row);
+            let mut squared_error = 0;
+            for pixel_y in y * 4..(y * 4 + 4).min(height) {
+                for pixel_idx in pixel_y * width + x * 4..pixel_y * width + (x * 4 + 4).min(width) {
+                    let (a, b) =
+                        (pixels[pixel_idx].to_le_bytes(), decoded[pixel_idx].to_le_bytes());
+                    for ch in 0..3 {
+                        squared_error += u64::from(a[ch].abs_diff(b[ch])).pow(2);
+                    }
+                }
+            }
+            BlockStats {
+                x: x as u32,
+                y: y as u32,
+                params: etc1_block_params(image.format.color_block(&image.blocks, block_idx)),
+                squared_error,
+            }
+        })
+        .collect()
+}
+
+/// Write `stats` as CSV, with a header row followed by one row per block. Base
+/// colors are written as hex `rrggbb`.
+pub fn write_stats_csv(writer: &mut impl Write, stats: &[BlockStats]) -> io::Result<()> {
+    writeln!(writer, "{}", STATS_CSV_HEADER)?;
+    for BlockStats { x, y, params, squared_error } in stats {
+        let [base_color0, base_color1] =
+            params.base_colors.map(|[r, g, b]| format!("{:02x}{:02x}{:02x}", r, g, b));
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{}",
+            x,
+            y,
+            u8::from(params.flip),
+            u8::from(params.differential),
+            params.table_indices[0],
+            params.table_indices[1],
+            base_color0,
+            base_color1,
+            squared_error
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+// Copyright 2025 The Chromium Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use rust_gtest_interop::prelude::*;
+
+chromium::import! {
+    "//ui/android:texture_compressor";
+}
+
+use texture_compressor::etc1::decode_etc1;
+use texture_compressor::stats::{block_stats, write_stats_csv, STATS_CSV_HEADER};
+use texture_compressor::{EncodeOptions, Etc1Image, Format};
+
+#[gtest(TextureCompressorTest, StatsCsv)]
+fn test_stats_csv() {
+    // Two blocks side by side: a flat one, which compresses almost exactly, and
+    // a noisy one, which doesn't.
+    let (width, height) = (8, 4);
+    let pixels: Vec<u32> = (0..width * height)
+        .map(|i| if i % width < 4 { 0xFF2080C0 } else { 0xFF000000 | (i * 0x1F3D5B) })
+        .collect();
+    let image = Etc1Image::encode(&pixels, width, height, Format::Etc1, &EncodeOptions::default());
+    let stats = block_stats(&image, &pixels);
+    let mut csv = vec![];
+    write_stats_csv(&mut csv, &stats).unwrap();
+
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    expect_eq!(lines.next(), Some(STATS_CSV_HEADER));
+    let columns = STATS_CSV_HEADER.split(',').count();
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    expect_eq!(rows.len(), 2);
+    for (i, row) in rows.iter().enumerate() {
+        expect_eq!(row.len(), columns);
+        expect_eq!(row[..2], [i.to_string(), "0".to_string()]);
+        expect_eq!(row[8].parse::<u64>().unwrap(), stats[i].squared_error);
+    }
+    expect_lt!(stats[0].squared_error, stats[1].squared_error);
+
+    // The errors add up to that of the whole image.
+    let decoded = decode_etc1(&image.blocks, width, height);
+    let total: u64 = std::iter::zip(&pixels, &decoded)
+        .flat_map(|(a, b)| std::iter::zip(a.to_le_bytes(), b.to_le_bytes()).take(3))
+        .map(|(a, b)| u64::from(a.abs_diff(b)).pow(2))
+        .sum();
+    expect_eq!(stats.iter().map(|s| s.squared_error).sum::<u64>(), total);
+}
@@ -4,6 +4,15 @@
 
 #![feature(portable_simd)]
 
+mod cli_tests;
+mod codec_tests;
+mod etc1_tests;
+mod etc2_tests;
+mod ktx_tests;
 mod lib_tests;
+mod metrics_tests;
+mod mipmap_tests;
 mod quant_tests;
+mod resize_tests;
 mod selectors_tests;
+mod stats_tests;